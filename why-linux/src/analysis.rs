@@ -0,0 +1,615 @@
+// Detection, summary and offender ranking over a stream of snapshots.
+use crate::blockdev::{self, SaturatedDevice};
+use crate::cgroup::{self, Cgroup};
+use crate::cpu::{self, CpuSample};
+use crate::disk::{self, DiskForecast, DiskSample};
+use crate::io::{self, IoSample};
+use crate::load::{self, StuckTasks};
+use crate::net::{self, NetFinding, NetThresholds};
+use crate::sched::{self, Contention};
+use crate::thermal::{self, Throttling};
+use crate::mem::{self, MemGrowth, MemMetric, MemSample};
+use crate::psi::{self, PsiSample, PsiThresholds};
+use crate::sampler::Snapshot;
+use serde::Serialize;
+use serde_json::json;
+use std::collections::{BTreeMap, HashMap};
+
+/// Detector thresholds plus the sustained rule (`min_hits` ticks over threshold).
+#[derive(Debug, Clone)]
+pub struct Thresholds {
+    pub cpu: f32,
+    pub mem: f32,
+    /// Minimum steady growth, in MB/min, for the leak detector
+    pub mem_growth: f32,
+    pub mem_metric: MemMetric,
+    pub disk: f32,
+    /// Inode used percent considered exhausted
+    pub inodes: f32,
+    /// Flag mounts projected to fill within this many hours
+    pub disk_full_hours: f32,
+    pub io_read: u64,
+    pub io_write: u64,
+    /// Block-device %util considered saturated
+    pub device_util: f32,
+    pub net: NetThresholds,
+    /// Run-queue wait over run time that counts as scheduler contention
+    pub sched_wait_ratio: f32,
+    /// Busy-CPU clock below this percent of the maximum counts as throttled
+    pub throttle_freq: f32,
+    /// Thermal zone temperature (°C) considered too hot
+    pub temp: f32,
+    pub psi: PsiThresholds,
+    pub min_hits: usize,
+}
+
+/// Sustained-detection verdicts; a missing entry means that resource looks normal.
+#[derive(Debug, Default, Serialize)]
+pub struct Findings {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cpu: Option<CpuSample>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mem: Option<MemSample>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mem_growth: Option<MemGrowth>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub disk: Option<DiskSample>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub inodes: Option<DiskSample>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub disk_fill: Option<DiskForecast>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub io: Option<IoSample>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub device: Option<SaturatedDevice>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub net: Option<NetFinding>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thermal: Option<Throttling>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub d_state: Option<StuckTasks>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sched: Option<Contention>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub psi: Option<PsiSample>,
+}
+
+pub fn detect(snapshots: &[Snapshot], t: &Thresholds) -> Findings {
+    Findings {
+        cpu: cpu::detect_sustained_high_cpu(snapshots, t.cpu, t.min_hits),
+        mem: mem::detect_sustained_high_mem(snapshots, t.mem, t.min_hits),
+        mem_growth: mem::detect_mem_growth(snapshots, t.mem_metric, t.mem_growth, t.min_hits),
+        disk: disk::detect_sustained_high_disk(snapshots, t.disk, t.min_hits),
+        inodes: disk::detect_sustained_inode_exhaustion(snapshots, t.inodes, t.min_hits),
+        disk_fill: disk::detect_disk_fill(snapshots, t.disk_full_hours, t.min_hits),
+        io: io::detect_sustained_high_io(snapshots, t.io_read, t.io_write, t.min_hits),
+        device: blockdev::detect_saturated_device(snapshots, t.device_util, t.min_hits),
+        net: net::detect_sustained_network(snapshots, &t.net, t.min_hits),
+        thermal: thermal::detect_throttling(snapshots, t.throttle_freq, t.temp, t.min_hits),
+        d_state: load::detect_stuck_tasks(snapshots, t.min_hits),
+        sched: sched::detect_scheduler_contention(snapshots, t.sched_wait_ratio, t.min_hits),
+        psi: psi::detect_sustained_psi(snapshots, &t.psi, t.min_hits),
+    }
+}
+
+#[derive(Clone)]
+pub struct OffenderStats {
+    name: String,
+    pid: u32,
+    cgroup: Option<Cgroup>,
+    sum: f32,
+    max: f32,
+    samples: u32,
+}
+
+#[derive(Serialize)]
+pub struct OffenderRow {
+    pub name: String,
+    pub pid: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cgroup: Option<Cgroup>,
+    pub sum: f32,
+    pub avg: f32,
+    pub max: f32,
+}
+
+fn update_offender(
+    map: &mut HashMap<u32, OffenderStats>,
+    pid: u32,
+    name: &str,
+    cgroup: Option<&Cgroup>,
+    value: f32,
+) {
+    let entry = map.entry(pid).or_insert_with(|| OffenderStats {
+        name: name.to_string(),
+        pid,
+        cgroup: None,
+        sum: 0.0,
+        max: 0.0,
+        samples: 0,
+    });
+    entry.name = name.to_string();
+    entry.cgroup = cgroup.cloned();
+    entry.sum += value;
+    entry.max = entry.max.max(value);
+    entry.samples += 1;
+}
+
+/// Compact duration such as "45s", "12m" or "3h 20m".
+pub fn human_duration(secs: u64) -> String {
+    match secs {
+        0..60 => format!("{}s", secs),
+        60..3600 => format!("{}m", secs / 60),
+        3600..86400 => format!("{}h {}m", secs / 3600, secs % 3600 / 60),
+        _ => format!("{}d {}h", secs / 86400, secs % 86400 / 3600),
+    }
+}
+
+/// Least-squares slope of (x, y) and the R² of that fit.
+fn linear_fit(points: &[(f64, f64)]) -> Option<(f64, f64)> {
+    let n = points.len() as f64;
+    let mean_x = points.iter().map(|p| p.0).sum::<f64>() / n;
+    let mean_y = points.iter().map(|p| p.1).sum::<f64>() / n;
+    let sxx: f64 = points.iter().map(|p| (p.0 - mean_x).powi(2)).sum();
+    let sxy: f64 = points.iter().map(|p| (p.0 - mean_x) * (p.1 - mean_y)).sum();
+    let syy: f64 = points.iter().map(|p| (p.1 - mean_y).powi(2)).sum();
+    if sxx == 0.0 {
+        return None;
+    }
+
+    let slope = sxy / sxx;
+    let r2 = if syy == 0.0 { 0.0 } else { (sxy * sxy) / (sxx * syy) };
+    Some((slope, r2))
+}
+
+/// Slope of a series that grew steadily: at least 80% of the steps non-decreasing and a fit with
+/// R² of 0.8 or more, so one burst or a sawtooth is not taken for a trend.
+pub fn steady_growth(points: &[(f64, f64)]) -> Option<f64> {
+    const MIN_STEADY: f64 = 0.8;
+    const MIN_R2: f64 = 0.8;
+
+    if points.len() < 2 || points[points.len() - 1].1 <= points[0].1 {
+        return None;
+    }
+    let rising = points.windows(2).filter(|w| w[1].1 >= w[0].1).count();
+    if (rising as f64) < MIN_STEADY * (points.len() - 1) as f64 {
+        return None;
+    }
+
+    let (slope, r2) = linear_fit(points)?;
+    (r2 >= MIN_R2 && slope > 0.0).then_some(slope)
+}
+
+fn avg_of(values: &[f32]) -> f32 {
+    if values.is_empty() {
+        0.0
+    } else {
+        values.iter().sum::<f32>() / values.len() as f32
+    }
+}
+
+fn max_of(values: &[f32]) -> f32 {
+    values.iter().cloned().fold(0.0, f32::max)
+}
+
+pub fn top_offenders(map: &HashMap<u32, OffenderStats>, limit: usize) -> Vec<OffenderRow> {
+    let mut rows: Vec<OffenderRow> = map
+        .values()
+        .map(|o| OffenderRow {
+            name: o.name.clone(),
+            pid: o.pid,
+            cgroup: o.cgroup.clone(),
+            sum: o.sum,
+            avg: if o.samples == 0 { 0.0 } else { o.sum / o.samples as f32 },
+            max: o.max,
+        })
+        .collect();
+
+    rows.sort_by(|a, b| b.sum.partial_cmp(&a.sum).unwrap_or(std::cmp::Ordering::Equal));
+    rows.truncate(limit);
+    rows
+}
+
+/// Per-tick top CPU and memory processes accumulated across the window.
+pub struct Offenders {
+    pub cpu: HashMap<u32, OffenderStats>,
+    pub mem: HashMap<u32, OffenderStats>,
+}
+
+pub fn offenders(snapshots: &[Snapshot]) -> Offenders {
+    let mut cpu_offenders = HashMap::new();
+    let mut mem_offenders = HashMap::new();
+
+    for snapshot in snapshots {
+        if let Some(sample) = cpu::get_top_cpu(snapshot) {
+            update_offender(&mut cpu_offenders, sample.pid, &sample.name, sample.cgroup.as_ref(), sample.cpu);
+        }
+        if let Some(sample) = mem::get_top_mem(snapshot) {
+            update_offender(&mut mem_offenders, sample.pid, &sample.name, sample.cgroup.as_ref(), sample.mem);
+        }
+    }
+
+    Offenders { cpu: cpu_offenders, mem: mem_offenders }
+}
+
+/// A leaf cgroup's usage across the window, from `--rank-cgroups` samples.
+#[derive(Serialize)]
+pub struct CgroupRow {
+    #[serde(flatten)]
+    pub cgroup: Cgroup,
+    pub cpu_avg: f32,
+    pub cpu_max: f32,
+    pub mem_max_bytes: u64,
+    pub read_bps_avg: u64,
+    pub write_bps_avg: u64,
+    /// Peak `some avg10` of the cgroup's own pressure files, per resource
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub pressure_max: BTreeMap<&'static str, f32>,
+}
+
+/// Leaf cgroups ranked by average CPU over the window.
+pub fn top_cgroups(snapshots: &[Snapshot], limit: usize) -> Vec<CgroupRow> {
+    let mut per_path: HashMap<&str, Vec<&cgroup::CgroupSample>> = HashMap::new();
+    for snapshot in snapshots {
+        for sample in &snapshot.cgroups {
+            per_path.entry(&sample.path).or_default().push(sample);
+        }
+    }
+
+    let mut rows: Vec<CgroupRow> = per_path
+        .into_iter()
+        .map(|(path, list)| {
+            let cpu: Vec<f32> = list.iter().map(|s| s.cpu).collect();
+            let n = list.len() as u64;
+            let mut pressure_max: BTreeMap<&'static str, f32> = BTreeMap::new();
+            for psi in list.iter().filter_map(|s| s.psi.as_ref()) {
+                for (name, res) in psi.resources() {
+                    if let Some(res) = res {
+                        let peak = pressure_max.entry(name).or_insert(0.0);
+                        *peak = peak.max(res.some.avg10);
+                    }
+                }
+            }
+            CgroupRow {
+                cgroup: cgroup::resolve(path),
+                cpu_avg: avg_of(&cpu),
+                cpu_max: max_of(&cpu),
+                mem_max_bytes: list.iter().map(|s| s.mem_bytes).max().unwrap_or(0),
+                read_bps_avg: list.iter().map(|s| s.read_bps).sum::<u64>() / n,
+                write_bps_avg: list.iter().map(|s| s.write_bps).sum::<u64>() / n,
+                pressure_max,
+            }
+        })
+        .collect();
+
+    rows.sort_by(|a, b| b.cpu_avg.partial_cmp(&a.cpu_avg).unwrap_or(std::cmp::Ordering::Equal));
+    rows.truncate(limit);
+    rows
+}
+
+/// Window average of the system-wide CPU states plus per-core busy avg/max.
+fn cpu_states_summary(snapshots: &[Snapshot]) -> (serde_json::Value, Vec<serde_json::Value>) {
+    let mut per_cpu: Vec<(String, Vec<&cpu::CpuStates>)> = Vec::new();
+
+    for snapshot in snapshots {
+        for states in &snapshot.cpu_states {
+            match per_cpu.iter_mut().find(|(name, _)| *name == states.cpu) {
+                Some((_, list)) => list.push(states),
+                None => per_cpu.push((states.cpu.clone(), vec![states])),
+            }
+        }
+    }
+
+    let avg = |list: &[&cpu::CpuStates], f: fn(&cpu::CpuStates) -> f32| {
+        avg_of(&list.iter().map(|s| f(s)).collect::<Vec<_>>())
+    };
+
+    let mut states = json!({});
+    let mut cores = Vec::new();
+
+    for (name, list) in &per_cpu {
+        let busy: Vec<f32> = list.iter().map(|s| s.busy()).collect();
+        if name == "cpu" {
+            states = json!({
+                "busy": avg_of(&busy),
+                "user": avg(list, |s| s.user),
+                "system": avg(list, |s| s.system),
+                "iowait": avg(list, |s| s.iowait),
+                "irq": avg(list, |s| s.irq),
+                "softirq": avg(list, |s| s.softirq),
+                "steal": avg(list, |s| s.steal),
+                "guest": avg(list, |s| s.guest),
+                "idle": avg(list, |s| s.idle),
+            });
+        } else {
+            cores.push(json!({ "cpu": name, "avg": avg_of(&busy), "max": max_of(&busy) }));
+        }
+    }
+
+    (states, cores)
+}
+
+/// Per-resource maxima of the pressure averages, or null when no snapshot had PSI.
+fn psi_summary(snapshots: &[Snapshot]) -> serde_json::Value {
+    let mut out = serde_json::Map::new();
+
+    for snapshot in snapshots {
+        let Some(psi) = &snapshot.psi else {
+            continue;
+        };
+
+        for (name, res) in psi.resources() {
+            let Some(res) = res else {
+                continue;
+            };
+
+            let entry = out.entry(name).or_insert_with(|| json!({}));
+            let mut bump = |key: &str, value: f32| {
+                let current = entry[key].as_f64().unwrap_or(0.0) as f32;
+                entry[key] = json!(current.max(value));
+            };
+            bump("some_avg10", res.some.avg10);
+            bump("some_avg60", res.some.avg60);
+            if let Some(full) = &res.full {
+                bump("full_avg10", full.avg10);
+                bump("full_avg60", full.avg60);
+            }
+        }
+    }
+
+    if out.is_empty() {
+        serde_json::Value::Null
+    } else {
+        serde_json::Value::Object(out)
+    }
+}
+
+/// Latest memory composition (null without /proc/meminfo) plus window avg/max of the paging rates.
+fn mem_composition_summary(snapshots: &[Snapshot]) -> (serde_json::Value, serde_json::Value) {
+    let composition = match snapshots.iter().rev().find_map(|s| s.meminfo.as_ref()) {
+        Some(m) => json!({
+            "total_kb": m.total_kb,
+            "available_kb": m.available_kb,
+            "anon_kb": m.anon_kb,
+            "page_cache_kb": m.page_cache_kb(),
+            "buffers_kb": m.buffers_kb,
+            "shmem_kb": m.shmem_kb,
+            "slab_reclaimable_kb": m.slab_reclaimable_kb,
+            "slab_unreclaimable_kb": m.slab_unreclaimable_kb,
+            "kernel_stack_kb": m.kernel_stack_kb,
+            "page_tables_kb": m.page_tables_kb,
+            "hugepages_kb": m.hugepages_kb,
+            "swap_used_kb": m.swap_used_kb(),
+            "swap_total_kb": m.swap_total_kb,
+        }),
+        None => serde_json::Value::Null,
+    };
+
+    let paging: Vec<_> = snapshots.iter().filter_map(|s| s.vmstat.as_ref()).collect();
+    if paging.is_empty() {
+        return (composition, serde_json::Value::Null);
+    }
+    let series = |f: fn(&crate::vmstat::VmStat) -> f32| paging.iter().map(|v| f(v)).collect::<Vec<f32>>();
+    let swap_in = series(|v| v.swap_in_bps as f32);
+    let swap_out = series(|v| v.swap_out_bps as f32);
+    let faults = series(|v| v.major_faults_per_sec);
+
+    (
+        composition,
+        json!({
+            "swap_in_bps_avg": avg_of(&swap_in),
+            "swap_in_bps_max": max_of(&swap_in),
+            "swap_out_bps_avg": avg_of(&swap_out),
+            "swap_out_bps_max": max_of(&swap_out),
+            "major_faults_avg": avg_of(&faults),
+            "major_faults_max": max_of(&faults),
+        }),
+    )
+}
+
+/// Latest OOM scores plus the vmstat `oom_kill` counter: its total and the kills within the window.
+fn oom_summary(snapshots: &[Snapshot]) -> serde_json::Value {
+    let scores = snapshots.iter().rev().map(|s| &s.oom_scores).find(|s| !s.is_empty());
+    let vmstat: Vec<_> = snapshots.iter().filter_map(|s| s.vmstat.as_ref()).collect();
+
+    json!({
+        "scores": scores.cloned().unwrap_or_default(),
+        "kill_total": vmstat.last().map(|v| v.oom_kill_total),
+        "kills_in_window": vmstat.iter().map(|v| v.oom_kills).sum::<u64>(),
+    })
+}
+
+/// Per-device window averages (and peak %util), busiest first.
+fn devices_summary(snapshots: &[Snapshot]) -> Vec<serde_json::Value> {
+    let mut per_device: Vec<(&str, Vec<&blockdev::DeviceSample>)> = Vec::new();
+    for snapshot in snapshots {
+        for d in &snapshot.devices {
+            match per_device.iter_mut().find(|(name, _)| *name == d.name) {
+                Some((_, list)) => list.push(d),
+                None => per_device.push((&d.name, vec![d])),
+            }
+        }
+    }
+
+    let mut rows: Vec<serde_json::Value> = per_device
+        .iter()
+        .map(|(name, list)| {
+            let avg = |f: fn(&blockdev::DeviceSample) -> f32| avg_of(&list.iter().map(|d| f(d)).collect::<Vec<_>>());
+            let util: Vec<f32> = list.iter().map(|d| d.util_percent).collect();
+            json!({
+                "name": name,
+                "mounts": list.last().map(|d| d.mounts.clone()).unwrap_or_default(),
+                "read_iops": avg(|d| d.read_iops),
+                "write_iops": avg(|d| d.write_iops),
+                "read_bps": avg(|d| d.read_bps as f32),
+                "write_bps": avg(|d| d.write_bps as f32),
+                "await_ms": avg(|d| d.await_ms),
+                "queue_depth": avg(|d| d.queue_depth),
+                "util_avg": avg_of(&util),
+                "util_max": max_of(&util),
+            })
+        })
+        .collect();
+
+    rows.sort_by(|a, b| {
+        let util = |v: &serde_json::Value| v["util_avg"].as_f64().unwrap_or(0.0);
+        util(b).partial_cmp(&util(a)).unwrap_or(std::cmp::Ordering::Equal)
+    });
+    rows
+}
+
+/// Latest load averages (raw and per CPU) and runnable/D-state task counts over the window.
+fn load_summary(snapshots: &[Snapshot]) -> serde_json::Value {
+    let Some((last, latest)) = snapshots.iter().rev().find_map(|s| Some((s, s.load.as_ref()?))) else {
+        return serde_json::Value::Null;
+    };
+    let cpus = load::cpus(last).unwrap_or(1) as f32;
+    let loads: Vec<&load::LoadSample> = snapshots.iter().filter_map(|s| s.load.as_ref()).collect();
+    let runnable: Vec<f32> = loads.iter().map(|l| l.runnable as f32).collect();
+    let blocked: Vec<f32> = loads.iter().map(|l| l.uninterruptible as f32).collect();
+
+    json!({
+        "load1": latest.load1,
+        "load5": latest.load5,
+        "load15": latest.load15,
+        "per_cpu": [latest.load1 / cpus, latest.load5 / cpus, latest.load15 / cpus],
+        "runnable_avg": avg_of(&runnable),
+        "runnable_max": max_of(&runnable),
+        "d_state_avg": avg_of(&blocked),
+        "d_state_max": max_of(&blocked),
+    })
+}
+
+/// Clock speed, hottest zone and throttle events over the window; null without any sysfs data.
+fn thermal_summary(snapshots: &[Snapshot]) -> serde_json::Value {
+    let samples: Vec<&thermal::ThermalSample> = snapshots.iter().filter_map(|s| s.thermal.as_ref()).collect();
+    if samples.is_empty() {
+        return serde_json::Value::Null;
+    }
+
+    let freqs: Vec<f32> = samples.iter().filter_map(|t| t.freq_percent).collect();
+    let hottest = samples
+        .iter()
+        .filter_map(|t| t.hottest())
+        .max_by(|a, b| a.temp_c.total_cmp(&b.temp_c));
+
+    json!({
+        "freq_percent_avg": (!freqs.is_empty()).then(|| avg_of(&freqs)),
+        "freq_percent_min": samples.iter().filter_map(|t| t.min_freq_percent).reduce(f32::min),
+        "hottest": hottest,
+        "throttle_events": samples.iter().filter_map(|t| t.throttle_events).reduce(|a, b| a + b),
+    })
+}
+
+/// Per-interface window averages plus TCP retransmits and connection counts.
+fn net_summary(snapshots: &[Snapshot]) -> serde_json::Value {
+    let nets: Vec<&net::NetSample> = snapshots.iter().filter_map(|s| s.net.as_ref()).collect();
+    if nets.is_empty() {
+        return serde_json::Value::Null;
+    }
+
+    let mut per_interface: Vec<(&str, Vec<&net::InterfaceSample>)> = Vec::new();
+    for i in nets.iter().flat_map(|n| &n.interfaces) {
+        match per_interface.iter_mut().find(|(name, _)| *name == i.name) {
+            Some((_, list)) => list.push(i),
+            None => per_interface.push((&i.name, vec![i])),
+        }
+    }
+    let interfaces: Vec<serde_json::Value> = per_interface
+        .iter()
+        .map(|(name, list)| {
+            let avg = |f: fn(&net::InterfaceSample) -> f32| avg_of(&list.iter().map(|i| f(i)).collect::<Vec<_>>());
+            let max = |f: fn(&net::InterfaceSample) -> f32| max_of(&list.iter().map(|i| f(i)).collect::<Vec<_>>());
+            json!({
+                "name": name,
+                "rx_bps_avg": avg(|i| i.rx_bps as f32),
+                "rx_bps_max": max(|i| i.rx_bps as f32),
+                "tx_bps_avg": avg(|i| i.tx_bps as f32),
+                "tx_bps_max": max(|i| i.tx_bps as f32),
+                "drops_per_sec_avg": avg(|i| i.drops_per_sec),
+                "errors_per_sec_avg": avg(|i| i.errors_per_sec),
+                "speed_bps": list.last().and_then(|i| i.speed_bps),
+                "virtual": list.last().is_some_and(|i| i.is_virtual),
+            })
+        })
+        .collect();
+
+    let tcp: Vec<&net::TcpStats> = nets.iter().filter_map(|n| n.tcp.as_ref()).collect();
+    let retrans: Vec<f32> = tcp.iter().map(|t| t.retrans_percent).collect();
+    let connections: Vec<f32> = tcp.iter().map(|t| t.sockets.connections() as f32).collect();
+
+    json!({
+        "interfaces": interfaces,
+        "tcp": {
+            "retrans_percent_avg": avg_of(&retrans),
+            "retrans_percent_max": max_of(&retrans),
+            "connections_max": max_of(&connections),
+            "sockets": tcp.last().map(|t| t.sockets),
+        },
+        "processes": nets.last().map(|n| n.processes.clone()).unwrap_or_default(),
+    })
+}
+
+/// Averages and maxima of the per-tick top values, as shown in the summary.
+pub fn summary(snapshots: &[Snapshot]) -> serde_json::Value {
+    let mut cpu_values: Vec<f32> = Vec::with_capacity(snapshots.len());
+    let mut mem_values: Vec<f32> = Vec::with_capacity(snapshots.len());
+    let mut mem_used_values: Vec<f32> = Vec::with_capacity(snapshots.len());
+    let mut disk_values: Vec<f32> = Vec::with_capacity(snapshots.len());
+    let mut inode_values: Vec<f32> = Vec::with_capacity(snapshots.len());
+
+    for snapshot in snapshots {
+        cpu_values.push(cpu::get_top_cpu(snapshot).map(|s| s.cpu).unwrap_or(0.0));
+
+        if let Some(sample) = mem::get_top_mem(snapshot) {
+            mem_values.push(sample.mem);
+            mem_used_values.push(sample.used_percent);
+        } else {
+            mem_values.push(0.0);
+            mem_used_values.push(0.0);
+        }
+
+        disk_values.push(disk::get_top_mount(snapshot).map(|s| s.used_percent).unwrap_or(0.0));
+        inode_values.push(
+            disk::get_top_inode_mount(snapshot)
+                .and_then(|s| s.inodes_used_percent)
+                .unwrap_or(0.0),
+        );
+    }
+
+    let (states, cores) = cpu_states_summary(snapshots);
+    let (composition, paging) = mem_composition_summary(snapshots);
+
+    json!({
+        "cpu": {
+            "avg": avg_of(&cpu_values),
+            "max": max_of(&cpu_values),
+            "states": states,
+            "cores": cores,
+            "thermal": thermal_summary(snapshots),
+            "load": load_summary(snapshots),
+        },
+        "mem": {
+            "avg": avg_of(&mem_values),
+            "max": max_of(&mem_values),
+            "system_avg": avg_of(&mem_used_values),
+            "system_max": max_of(&mem_used_values),
+            "composition": composition,
+            "paging": paging,
+            "oom": oom_summary(snapshots),
+        },
+        "disk": {
+            "avg": avg_of(&disk_values),
+            "max": max_of(&disk_values),
+            "inodes_avg": avg_of(&inode_values),
+            "inodes_max": max_of(&inode_values),
+            "mounts": snapshots.last().map(|s| s.mounts.clone()).unwrap_or_default(),
+        },
+        "devices": devices_summary(snapshots),
+        "io": {
+            "exited": io::exited_summary(snapshots, 10),
+        },
+        "net": net_summary(snapshots),
+        "psi": psi_summary(snapshots),
+    })
+}
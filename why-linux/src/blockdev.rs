@@ -0,0 +1,224 @@
+// Block-device activity from /proc/diskstats: IOPS, throughput, latency, queue depth and
+// utilization per device, including I/O no process is charged for (e.g. writeback).
+use crate::sampler::Snapshot;
+use crate::source::Source;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// diskstats counts sectors in 512-byte units regardless of the device's sector size.
+const SECTOR_BYTES: u64 = 512;
+
+/// One device's activity over a tick.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DeviceSample {
+    pub name: String,
+    /// major:minor, as in mountinfo
+    pub dev: String,
+    pub read_iops: f32,
+    pub write_iops: f32,
+    pub read_bps: u64,
+    pub write_bps: u64,
+    /// Average time per completed request, queueing included
+    pub await_ms: f32,
+    /// Average number of requests in flight
+    pub queue_depth: f32,
+    /// Percent of the tick the device had I/O in flight
+    pub util_percent: f32,
+    /// Mount points backed by this device
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub mounts: Vec<String>,
+}
+
+/// The cumulative counters of one diskstats line.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct Counters {
+    reads: u64,
+    sectors_read: u64,
+    ms_reading: u64,
+    writes: u64,
+    sectors_written: u64,
+    ms_writing: u64,
+    ms_io: u64,
+    weighted_ms: u64,
+}
+
+/// Parses `/proc/diskstats` into (name, major:minor, counters).
+fn parse_diskstats(contents: &str) -> Vec<(String, String, Counters)> {
+    contents
+        .lines()
+        .filter_map(|line| {
+            let f: Vec<&str> = line.split_whitespace().collect();
+            let num = |i: usize| f.get(i).and_then(|v| v.parse::<u64>().ok());
+            let counters = Counters {
+                reads: num(3)?,
+                sectors_read: num(5)?,
+                ms_reading: num(6)?,
+                writes: num(7)?,
+                sectors_written: num(9)?,
+                ms_writing: num(10)?,
+                ms_io: num(12)?,
+                weighted_ms: num(13)?,
+            };
+            Some((f[2].to_string(), format!("{}:{}", f[0], f[1]), counters))
+        })
+        .collect()
+}
+
+/// Computes per-device rates from `/proc/diskstats` deltas between calls.
+pub struct BlockDevSampler {
+    last: HashMap<String, Counters>,
+}
+
+impl BlockDevSampler {
+    pub fn new(source: &Source) -> Self {
+        let last = source
+            .read("diskstats")
+            .map(|c| parse_diskstats(&c).into_iter().map(|(name, _, c)| (name, c)).collect())
+            .unwrap_or_default();
+        BlockDevSampler { last }
+    }
+
+    /// Devices that have done any I/O since boot; idle loop and ram devices are left out.
+    pub fn sample(&mut self, source: &Source, elapsed_secs: f64) -> Vec<DeviceSample> {
+        let Some(contents) = source.read("diskstats") else {
+            return Vec::new();
+        };
+        let elapsed_secs = elapsed_secs.max(f64::EPSILON);
+        let elapsed_ms = elapsed_secs * 1000.0;
+        let mut samples = Vec::new();
+        let mut current = HashMap::new();
+
+        for (name, dev, now) in parse_diskstats(&contents) {
+            if let Some(before) = self.last.get(&name)
+                && now.reads + now.writes > 0
+            {
+                let d = |a: u64, b: u64| a.saturating_sub(b) as f64;
+                let ios = d(now.reads, before.reads) + d(now.writes, before.writes);
+                let busy_ms = d(now.ms_reading, before.ms_reading) + d(now.ms_writing, before.ms_writing);
+
+                samples.push(DeviceSample {
+                    name: name.clone(),
+                    dev,
+                    read_iops: (d(now.reads, before.reads) / elapsed_secs) as f32,
+                    write_iops: (d(now.writes, before.writes) / elapsed_secs) as f32,
+                    read_bps: (d(now.sectors_read, before.sectors_read) * SECTOR_BYTES as f64 / elapsed_secs) as u64,
+                    write_bps: (d(now.sectors_written, before.sectors_written) * SECTOR_BYTES as f64 / elapsed_secs)
+                        as u64,
+                    await_ms: if ios > 0.0 { (busy_ms / ios) as f32 } else { 0.0 },
+                    queue_depth: (d(now.weighted_ms, before.weighted_ms) / elapsed_ms) as f32,
+                    util_percent: (d(now.ms_io, before.ms_io) / elapsed_ms * 100.0).min(100.0) as f32,
+                    mounts: Vec::new(),
+                });
+            }
+            current.insert(name, now);
+        }
+
+        self.last = current;
+        samples
+    }
+}
+
+/// A device busy for most of the window, with its latest rates.
+#[derive(Debug, Serialize)]
+pub struct SaturatedDevice {
+    #[serde(flatten)]
+    pub device: DeviceSample,
+    pub util_avg: f32,
+    pub await_avg_ms: f32,
+}
+
+/// Flags the busiest device whose %util exceeded `threshold` in at least `min_hits` ticks.
+pub fn detect_saturated_device(snapshots: &[Snapshot], threshold: f32, min_hits: usize) -> Option<SaturatedDevice> {
+    let mut per_device: HashMap<&str, Vec<&DeviceSample>> = HashMap::new();
+    for snapshot in snapshots {
+        for d in &snapshot.devices {
+            per_device.entry(&d.name).or_default().push(d);
+        }
+    }
+
+    let mut best: Option<SaturatedDevice> = None;
+    for samples in per_device.values() {
+        let hits = samples.iter().filter(|d| d.util_percent > threshold).count();
+        if hits < min_hits {
+            continue;
+        }
+
+        let n = samples.len() as f32;
+        let util_avg = samples.iter().map(|d| d.util_percent).sum::<f32>() / n;
+        if best.as_ref().is_none_or(|b| util_avg > b.util_avg) {
+            best = Some(SaturatedDevice {
+                device: (*samples.last()?).clone(),
+                util_avg,
+                await_avg_ms: samples.iter().map(|d| d.await_ms).sum::<f32>() / n,
+            });
+        }
+    }
+
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::fake::FakeProc;
+
+    fn line(name: &str, reads: u64, sectors: u64, ms_io: u64, weighted: u64) -> String {
+        // reads merged sectors ms | writes merged sectors ms | in_flight ms_io weighted
+        format!(
+            "   8       0 {} {} 0 {} {} 0 0 0 0 0 {} {}\n",
+            name,
+            reads,
+            sectors,
+            reads * 4,
+            ms_io,
+            weighted
+        )
+    }
+
+    #[test]
+    fn computes_iops_throughput_await_and_util() {
+        let proc = FakeProc::new();
+        proc.file("diskstats", &(line("sda", 100, 800, 1000, 1000) + "   7       0 loop0 0 0 0 0 0 0 0 0 0 0 0\n"));
+        let source = proc.source();
+        let mut sampler = BlockDevSampler::new(&source);
+
+        proc.file("diskstats", &(line("sda", 300, 2848, 1900, 3000) + "   7       0 loop0 0 0 0 0 0 0 0 0 0 0 0\n"));
+        let samples = sampler.sample(&source, 1.0);
+
+        assert_eq!(samples.len(), 1);
+        let sda = &samples[0];
+        assert_eq!((sda.dev.as_str(), sda.read_iops, sda.read_bps), ("8:0", 200.0, 1024 * 1024));
+        assert_eq!((sda.await_ms, sda.queue_depth, sda.util_percent), (4.0, 2.0, 90.0));
+    }
+
+    fn device(name: &str, util_percent: f32, await_ms: f32) -> DeviceSample {
+        DeviceSample {
+            name: name.to_string(),
+            dev: "8:0".to_string(),
+            await_ms,
+            util_percent,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn flags_the_busiest_device_above_threshold_in_min_hits_ticks() {
+        // sda is busy in 3 of 4 ticks; sdb is pegged twice and idle otherwise
+        let utils = [(95.0, 100.0), (99.0, 100.0), (20.0, 10.0), (97.0, 10.0)];
+        let snapshots: Vec<Snapshot> = utils
+            .iter()
+            .map(|&(sda, sdb)| Snapshot { devices: vec![device("sda", sda, 8.0), device("sdb", sdb, 2.0)], ..Default::default() })
+            .collect();
+
+        let saturated = detect_saturated_device(&snapshots, 90.0, 3).unwrap();
+        assert_eq!((saturated.device.name.as_str(), saturated.util_avg, saturated.await_avg_ms), ("sda", 77.75, 8.0));
+
+        // with both qualifying, the higher window average wins
+        let saturated = detect_saturated_device(&snapshots, 90.0, 2).unwrap();
+        assert_eq!(saturated.device.name, "sda");
+
+        // exactly at the threshold does not count
+        assert!(detect_saturated_device(&snapshots, 95.0, 3).is_none());
+        assert!(detect_saturated_device(&snapshots, 90.0, 4).is_none());
+    }
+}
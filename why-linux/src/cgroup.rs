@@ -0,0 +1,271 @@
+// cgroup attribution: which systemd unit, slice or container a process belongs to, and optional
+// ranking of the cgroups themselves from cgroup v2 accounting files.
+use crate::psi::{self, Psi};
+use crate::source::Source;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A cgroup path resolved to the names people actually recognise.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Cgroup {
+    pub path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unit: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub slice: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub container: Option<String>,
+}
+
+impl Cgroup {
+    /// Short human label: container, then unit, then slice.
+    pub fn label(&self) -> Option<String> {
+        if let Some(id) = &self.container {
+            Some(format!("container {}", id))
+        } else {
+            self.unit.clone().or_else(|| self.slice.clone())
+        }
+    }
+}
+
+/// " [label]" for appending to an offender line, or nothing when the cgroup is unknown.
+pub fn suffix(cgroup: Option<&Cgroup>) -> String {
+    cgroup.and_then(Cgroup::label).map(|l| format!(" [{}]", l)).unwrap_or_default()
+}
+
+/// Cgroup path from `/proc/[pid]/cgroup`: the v2 unified entry, else the v1 systemd hierarchy.
+pub fn read_cgroup(source: &Source, pid: u32) -> Option<String> {
+    let contents = source.read_pid(pid, "cgroup")?;
+    let mut fallback = None;
+
+    for line in contents.lines() {
+        let mut parts = line.splitn(3, ':');
+        let (id, controllers, path) = (parts.next()?, parts.next()?, parts.next()?);
+        if id == "0" && controllers.is_empty() {
+            return Some(path.to_string());
+        }
+        if controllers == "name=systemd" || fallback.is_none() {
+            fallback = Some(path.to_string());
+        }
+    }
+
+    fallback
+}
+
+/// Extracts a container id from a cgroup path component, as written by docker, containerd,
+/// CRI-O, podman and kubelet.
+fn container_id(component: &str) -> Option<String> {
+    let id = ["docker-", "cri-containerd-", "crio-", "libpod-"]
+        .iter()
+        .find_map(|prefix| component.strip_prefix(prefix))
+        .map(|rest| rest.trim_end_matches(".scope"))
+        .unwrap_or(component);
+
+    if id.len() >= 32 && id.chars().all(|c| c.is_ascii_hexdigit()) {
+        Some(id[..12].to_string())
+    } else {
+        None
+    }
+}
+
+pub fn resolve(path: &str) -> Cgroup {
+    let components: Vec<&str> = path.split('/').filter(|c| !c.is_empty()).collect();
+    let unit_suffixes = [".service", ".scope", ".socket", ".mount", ".swap", ".timer"];
+
+    Cgroup {
+        path: path.to_string(),
+        unit: components
+            .iter()
+            .rev()
+            .find(|c| unit_suffixes.iter().any(|s| c.ends_with(s)))
+            .map(|c| c.to_string()),
+        slice: components.iter().rev().find(|c| c.ends_with(".slice")).map(|c| c.to_string()),
+        container: components.iter().rev().find_map(|c| container_id(c)),
+    }
+}
+
+/// Resolves a non-empty path; processes without cgroup info get `None`.
+pub fn resolve_opt(path: &str) -> Option<Cgroup> {
+    if path.is_empty() { None } else { Some(resolve(path)) }
+}
+
+/// One leaf cgroup's usage during a tick.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CgroupSample {
+    pub path: String,
+    /// CPU percent over the tick (100 = one core), from `cpu.stat` usage_usec
+    pub cpu: f32,
+    /// `memory.current` in bytes
+    pub mem_bytes: u64,
+    pub read_bps: u64,
+    pub write_bps: u64,
+    /// The cgroup's own `{cpu,memory,io}.pressure`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub psi: Option<Psi>,
+}
+
+fn read_u64(path: &Path) -> Option<u64> {
+    fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+fn read_usage_usec(dir: &Path) -> Option<u64> {
+    let contents = fs::read_to_string(dir.join("cpu.stat")).ok()?;
+    contents
+        .lines()
+        .find_map(|l| l.strip_prefix("usage_usec "))
+        .and_then(|v| v.trim().parse().ok())
+}
+
+/// Sums rbytes/wbytes over all devices in `io.stat`.
+fn parse_io_stat(contents: &str) -> (u64, u64) {
+    let mut read = 0u64;
+    let mut write = 0u64;
+
+    for field in contents.split_whitespace() {
+        if let Some(v) = field.strip_prefix("rbytes=") {
+            read += v.parse::<u64>().unwrap_or(0);
+        } else if let Some(v) = field.strip_prefix("wbytes=") {
+            write += v.parse::<u64>().unwrap_or(0);
+        }
+    }
+
+    (read, write)
+}
+
+/// Leaf cgroups below `root`, as (cgroup path, directory) pairs.
+fn leaf_cgroups(root: &Path) -> Vec<(String, PathBuf)> {
+    let mut leaves = Vec::new();
+    let mut stack = vec![root.to_path_buf()];
+
+    while let Some(dir) = stack.pop() {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        let children: Vec<PathBuf> = entries
+            .flatten()
+            .filter(|e| e.file_type().map(|t| t.is_dir()).unwrap_or(false))
+            .map(|e| e.path())
+            .collect();
+
+        if children.is_empty() && dir != root {
+            let rel = dir.strip_prefix(root).unwrap_or(&dir);
+            leaves.push((format!("/{}", rel.display()), dir));
+        }
+        stack.extend(children);
+    }
+
+    leaves
+}
+
+/// Computes per-cgroup rates from cgroup v2 accounting deltas between calls.
+pub struct CgroupSampler {
+    root: PathBuf,
+    last: HashMap<String, (u64, u64, u64)>,
+}
+
+impl CgroupSampler {
+    pub fn new(source: &Source) -> Self {
+        let mut sampler = CgroupSampler {
+            root: source.mount_path("/sys/fs/cgroup"),
+            last: HashMap::new(),
+        };
+        sampler.sample(1.0);
+        sampler
+    }
+
+    pub fn sample(&mut self, elapsed_secs: f64) -> Vec<CgroupSample> {
+        let elapsed_secs = elapsed_secs.max(f64::EPSILON);
+        let mut current = HashMap::new();
+        let mut samples = Vec::new();
+
+        for (path, dir) in leaf_cgroups(&self.root) {
+            let Some(usage) = read_usage_usec(&dir) else {
+                continue;
+            };
+            let (read, write) = fs::read_to_string(dir.join("io.stat"))
+                .map(|c| parse_io_stat(&c))
+                .unwrap_or((0, 0));
+
+            if let Some((u0, r0, w0)) = self.last.get(&path) {
+                samples.push(CgroupSample {
+                    path: path.clone(),
+                    cpu: (usage.saturating_sub(*u0) as f64 / (elapsed_secs * 1e6) * 100.0) as f32,
+                    mem_bytes: read_u64(&dir.join("memory.current")).unwrap_or(0),
+                    read_bps: (read.saturating_sub(*r0) as f64 / elapsed_secs) as u64,
+                    write_bps: (write.saturating_sub(*w0) as f64 / elapsed_secs) as u64,
+                    psi: psi::read_cgroup_psi(&dir),
+                });
+            }
+            current.insert(path, (usage, read, write));
+        }
+
+        self.last = current;
+        samples
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::fake::FakeProc;
+
+    #[test]
+    fn reads_v2_cgroup_and_v1_systemd_fallback() {
+        let proc = FakeProc::new();
+        proc.file("1/cgroup", "0::/system.slice/backup.service\n")
+            .file("2/cgroup", "12:cpu,cpuacct:/docker/abc\n1:name=systemd:/system.slice/docker-abc.scope\n");
+        let source = proc.source();
+
+        assert_eq!(read_cgroup(&source, 1).as_deref(), Some("/system.slice/backup.service"));
+        assert_eq!(read_cgroup(&source, 2).as_deref(), Some("/system.slice/docker-abc.scope"));
+    }
+
+    #[test]
+    fn resolves_units_slices_and_containers() {
+        let backup = resolve("/system.slice/backup.service");
+        assert_eq!(backup.unit.as_deref(), Some("backup.service"));
+        assert_eq!(backup.slice.as_deref(), Some("system.slice"));
+        assert_eq!(backup.label().as_deref(), Some("backup.service"));
+
+        let id = "abc123def4567890abc123def4567890abc123def4567890abc123def4567890";
+        let docker = resolve(&format!("/system.slice/docker-{}.scope", id));
+        assert_eq!(docker.container.as_deref(), Some("abc123def456"));
+        assert_eq!(docker.label().as_deref(), Some("container abc123def456"));
+
+        let kube = resolve(&format!("/kubepods/burstable/pod1234/{}", id));
+        assert_eq!(kube.container.as_deref(), Some("abc123def456"));
+
+        assert_eq!(resolve("/user.slice/user-1000.slice").label().as_deref(), Some("user-1000.slice"));
+    }
+
+    #[test]
+    fn sampler_ranks_leaf_cgroups_from_accounting_files() {
+        let proc = FakeProc::new();
+        let cg = "1/root/sys/fs/cgroup";
+        proc.file(&format!("{}/cpu.stat", cg), "usage_usec 999\n")
+            .file(&format!("{}/system.slice/cpu.stat", cg), "usage_usec 999\n")
+            .file(&format!("{}/system.slice/backup.service/cpu.stat", cg), "usage_usec 1000000\n")
+            .file(&format!("{}/system.slice/backup.service/io.stat", cg), "8:0 rbytes=100 wbytes=0 rios=1 wios=0\n");
+        let source = proc.source();
+        let mut sampler = CgroupSampler::new(&source);
+
+        proc.file(&format!("{}/system.slice/backup.service/cpu.stat", cg), "usage_usec 1500000\n")
+            .file(&format!("{}/system.slice/backup.service/memory.current", cg), "4096\n")
+            .file(&format!("{}/system.slice/backup.service/io.stat", cg), "8:0 rbytes=100 wbytes=2000 rios=1 wios=4\n")
+            .file(
+                &format!("{}/system.slice/backup.service/io.pressure", cg),
+                "some avg10=42.00 avg60=30.00 avg300=5.00 total=100\nfull avg10=40.00 avg60=28.00 avg300=4.00 total=90\n",
+            );
+        let samples = sampler.sample(1.0);
+
+        assert_eq!(samples.len(), 1);
+        let s = &samples[0];
+        assert_eq!(s.path, "/system.slice/backup.service");
+        assert_eq!((s.cpu, s.mem_bytes, s.read_bps, s.write_bps), (50.0, 4096, 0, 2000));
+        let psi = s.psi.as_ref().unwrap();
+        assert!(psi.cpu.is_none() && psi.memory.is_none());
+        assert_eq!(psi.io.as_ref().unwrap().some.avg10, 42.0);
+    }
+}
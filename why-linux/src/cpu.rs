@@ -1,105 +1,542 @@
-use std::thread::sleep;
-use std::time::Duration;
+use std::collections::{HashMap, HashSet};
 use std::fs;
 
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+
+use crate::cgroup::{self, Cgroup};
+use crate::sampler::{GroupMember, ProcSample, Snapshot};
+use crate::sched::SchedRates;
+use crate::source::Source;
 
 #[derive(Debug, Serialize)]
 pub struct CpuSample {
     pub name: String,
     pub pid: u32,
     pub cpu: f32,
+    /// Context switches and run-queue wait summed over the process's threads
+    #[serde(flatten)]
+    pub sched: Option<SchedRates>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cgroup: Option<Cgroup>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub members: Vec<GroupMember>,
+    /// Hottest threads over the window, only sampled with `--threads`
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub threads: Vec<HotThread>,
+}
+
+/// One thread of a busy process over the sampler's last interval.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThreadCpu {
+    pub tid: u32,
+    /// The thread's `comm`, e.g. "C2 CompilerThre" or "GC Thread#0"
+    pub name: String,
+    pub state: char,
+    pub cpu: f32,
+}
+
+/// A thread of the CPU offender over the whole window.
+#[derive(Debug, Serialize)]
+pub struct HotThread {
+    pub tid: u32,
+    pub name: String,
+    /// Average over the ticks its process was drilled into, counting ticks it was not listed as 0
+    pub cpu_avg: f32,
+    pub cpu_max: f32,
+}
+
+/// Busiest processes per tick whose threads are read with `--threads`.
+const THREAD_PROCS: usize = 3;
+
+/// Fields we care about from `/proc/[pid]/stat`.
+struct ProcStat {
+    name: String,
+    /// R, S, D, Z, ...
+    state: char,
+    ppid: u32,
+    pgrp: u32,
+    /// Session id (field 6)
+    sid: u32,
+    ticks: u64,
+    /// Field 42, aggregated block-I/O delay in clock ticks; missing on very old kernels
+    blkio_ticks: Option<u64>,
 }
 
-pub fn get_top_cpu_excluding(exclude_pid: Option<u32>) -> Option<CpuSample> {
-    let output = std::process::Command::new("ps")
-        .args(["-eo", "pid,comm,%cpu", "--sort=-%cpu"])
-        .output()
-        .ok()?;
+fn parse_proc_stat(contents: &str) -> Option<ProcStat> {
+    // stat format: pid (comm) state ppid ... ; comm may itself contain spaces or ')'
+    let open = contents.find('(')?;
+    let close = contents.rfind(')')?;
+    let name = contents.get(open + 1..close)?.to_string();
+    let fields: Vec<&str> = contents.get(close + 1..)?.split_whitespace().collect();
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let mut lines = stdout.lines();
-    lines.next(); // skip header
+    // fields[0] is field 3 (state), so utime (14) and stime (15) are at 11 and 12
+    let state = fields.first()?.chars().next()?;
+    let ppid: u32 = fields.get(1)?.parse().ok()?;
+    let pgrp: u32 = fields.get(2)?.parse().ok()?;
+    let sid: u32 = fields.get(3)?.parse().ok()?;
+    let utime: u64 = fields.get(11)?.parse().ok()?;
+    let stime: u64 = fields.get(12)?.parse().ok()?;
 
-    for line in lines {
-        let mut parts = line.split_whitespace();
-        let pid: u32 = parts.next()?.parse().ok()?;
-        let name = parts.next()?.to_string();
-        let cpu: f32 = parts.next()?.parse().ok()?;
+    let blkio_ticks = fields.get(39).and_then(|v| v.parse().ok());
 
-        if exclude_pid.is_some_and(|p| p == pid) {
-            continue;
+    Some(ProcStat { name, state, ppid, pgrp, sid, ticks: utime + stime, blkio_ticks })
+}
+
+fn read_proc_stat(source: &Source, pid: u32) -> Option<ProcStat> {
+    parse_proc_stat(&source.read_pid(pid, "stat")?)
+}
+
+/// Cumulative jiffies from one `cpu`/`cpuN` line of `/proc/stat`.
+#[derive(Debug, Clone, Copy, Default)]
+struct CpuTimes {
+    user: u64,
+    nice: u64,
+    system: u64,
+    idle: u64,
+    iowait: u64,
+    irq: u64,
+    softirq: u64,
+    steal: u64,
+    guest: u64,
+    guest_nice: u64,
+}
+
+impl CpuTimes {
+    fn parse(fields: &str) -> Self {
+        let mut v = fields.split_whitespace().map(|f| f.parse::<u64>().unwrap_or(0));
+        let mut next = || v.next().unwrap_or(0);
+        CpuTimes {
+            user: next(),
+            nice: next(),
+            system: next(),
+            idle: next(),
+            iowait: next(),
+            irq: next(),
+            softirq: next(),
+            steal: next(),
+            guest: next(),
+            guest_nice: next(),
         }
+    }
 
-        let mut sample = CpuSample { name, pid, cpu };
+    /// Guest time is already included in user/nice, so it is not added again.
+    fn total(&self) -> u64 {
+        self.user + self.nice + self.system + self.idle + self.iowait + self.irq + self.softirq + self.steal
+    }
+}
 
-        // Check if this is a known browser child process
-        if sample.name == "Web" || sample.name == "GPU" {
-            if let Some(parent) = get_parent_process(sample.pid) {
-                if exclude_pid.is_some_and(|p| p == parent.pid) {
-                    continue;
-                }
-                sample = parent;
+/// Where one CPU (or all of them, for `cpu`) spent the last interval, in percent of its time.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CpuStates {
+    pub cpu: String,
+    /// user + nice, excluding guest time
+    pub user: f32,
+    pub system: f32,
+    pub iowait: f32,
+    pub irq: f32,
+    pub softirq: f32,
+    pub steal: f32,
+    pub guest: f32,
+    pub idle: f32,
+}
+
+impl CpuStates {
+    /// Percent of time doing work; iowait counts as idle, as in `top`.
+    pub fn busy(&self) -> f32 {
+        (100.0 - self.idle - self.iowait).max(0.0)
+    }
+
+    fn between(cpu: &str, prev: &CpuTimes, cur: &CpuTimes) -> Self {
+        let total = cur.total().saturating_sub(prev.total());
+        let pct = |now: u64, before: u64| {
+            if total == 0 {
+                0.0
+            } else {
+                now.saturating_sub(before) as f32 / total as f32 * 100.0
             }
+        };
+        let guest = cur.guest + cur.guest_nice;
+        let prev_guest = prev.guest + prev.guest_nice;
+
+        CpuStates {
+            cpu: cpu.to_string(),
+            user: pct(cur.user + cur.nice, prev.user + prev.nice) - pct(guest, prev_guest),
+            system: pct(cur.system, prev.system),
+            iowait: pct(cur.iowait, prev.iowait),
+            irq: pct(cur.irq, prev.irq),
+            softirq: pct(cur.softirq, prev.softirq),
+            steal: pct(cur.steal, prev.steal),
+            guest: pct(guest, prev_guest),
+            idle: pct(cur.idle, prev.idle),
         }
+    }
+}
+
+/// Returns the `cpu` (all CPUs) and `cpuN` lines of `/proc/stat`, aggregate first.
+fn read_cpu_times(source: &Source) -> Option<Vec<(String, CpuTimes)>> {
+    let contents = source.read("stat")?;
+    let lines: Vec<(String, CpuTimes)> = contents
+        .lines()
+        .filter(|line| line.starts_with("cpu"))
+        .filter_map(|line| {
+            let (name, rest) = line.split_once(char::is_whitespace)?;
+            Some((name.to_string(), CpuTimes::parse(rest)))
+        })
+        .collect();
+
+    if lines.first().is_some_and(|(name, _)| name == "cpu") {
+        Some(lines)
+    } else {
+        None
+    }
+}
+
+/// One process's CPU usage over the sampler's last interval.
+pub struct ProcCpu {
+    pub pid: u32,
+    pub ppid: u32,
+    pub pgrp: u32,
+    pub sid: u32,
+    pub name: String,
+    pub state: char,
+    pub cpu: f32,
+    /// Percent of the interval spent waiting for block I/O; None without delay accounting
+    pub blkio_delay_percent: Option<f32>,
+    /// Hottest threads, for the busiest processes when thread sampling is on
+    pub threads: Vec<ThreadCpu>,
+}
+
+/// Computes per-process CPU usage from `/proc/[pid]/stat` deltas between calls.
+///
+/// Percentages follow `top`: 100% is one fully busy core.
+pub struct CpuSampler {
+    last_times: HashMap<String, CpuTimes>,
+    last_ticks: HashMap<u32, u64>,
+    last_blkio: HashMap<u32, u64>,
+    /// False when kernel.task_delayacct is off, which leaves delayacct_blkio_ticks at zero
+    delayacct: bool,
+    /// Threads kept per drilled process; 0 disables thread sampling
+    threads: usize,
+    last_thread_ticks: HashMap<u32, u64>,
+    /// Processes whose threads were read in the previous call
+    drilled: HashSet<u32>,
+}
+
+/// What one `CpuSampler::sample()` call measured.
+pub struct CpuTick {
+    pub procs: Vec<ProcCpu>,
+    /// Aggregate `cpu` first, then one entry per core
+    pub states: Vec<CpuStates>,
+}
+
+impl CpuSampler {
+    /// Creates a sampler primed with the current counters; the first `sample()` reports
+    /// usage since this call.
+    pub fn new(source: &Source) -> Self {
+        let mut sampler = CpuSampler {
+            last_times: HashMap::new(),
+            last_ticks: HashMap::new(),
+            last_blkio: HashMap::new(),
+            // kernels before 5.14 have no switch and always account when built with it
+            delayacct: source.read("sys/kernel/task_delayacct").is_none_or(|v| v.trim() != "0"),
+            threads: 0,
+            last_thread_ticks: HashMap::new(),
+            drilled: HashSet::new(),
+        };
+        sampler.sample(source);
+        sampler
+    }
 
-        return Some(sample);
+    /// Also reads `/proc/[pid]/task/*/stat` of the busiest processes each call and keeps their
+    /// `n` hottest threads. A process is drilled into from the call after it first ranks among
+    /// the busiest, since its threads need a baseline; this call reads the first one.
+    pub fn with_threads(mut self, source: &Source, n: usize) -> Self {
+        self.threads = n;
+        self.sample(source);
+        self
     }
 
-    None
+    /// Thread CPU percents of `pid`, hottest first; empty when it was not drilled last call.
+    fn sample_threads(&self, source: &Source, pid: u32, to_percent: impl Fn(u64) -> f32, ticks: &mut HashMap<u32, u64>) -> Vec<ThreadCpu> {
+        let Ok(dir) = fs::read_dir(source.pid_path(pid, "task")) else {
+            return Vec::new();
+        };
+        let baseline = self.drilled.contains(&pid);
+        let mut threads: Vec<ThreadCpu> = dir
+            .flatten()
+            .filter_map(|e| e.file_name().to_str()?.parse::<u32>().ok())
+            .filter_map(|tid| {
+                let stat = parse_proc_stat(&source.read_pid(pid, &format!("task/{}/stat", tid))?)?;
+                ticks.insert(tid, stat.ticks);
+                // threads started during the interval are measured from zero
+                let prev = self.last_thread_ticks.get(&tid).copied().unwrap_or(0);
+                baseline.then(|| ThreadCpu { tid, name: stat.name, state: stat.state, cpu: to_percent(stat.ticks.saturating_sub(prev)) })
+            })
+            .collect();
+        threads.sort_by(|a, b| b.cpu.total_cmp(&a.cpu).then(a.tid.cmp(&b.tid)));
+        threads.truncate(self.threads);
+        threads
+    }
+
+    /// Reads fresh counters and returns per-process and per-CPU usage for the elapsed interval.
+    pub fn sample(&mut self, source: &Source) -> CpuTick {
+        let Some(times) = read_cpu_times(source) else {
+            return CpuTick { procs: Vec::new(), states: Vec::new() };
+        };
+        let cpus = (times.len() - 1).max(1);
+        let total = times[0].1.total();
+        let last_total = self.last_times.get("cpu").map(|t| t.total()).unwrap_or(0);
+        let elapsed = total.saturating_sub(last_total);
+
+        let states = times
+            .iter()
+            .filter_map(|(name, cur)| {
+                self.last_times.get(name).map(|prev| CpuStates::between(name, prev, cur))
+            })
+            .collect();
+
+        let mut ticks = HashMap::new();
+        let mut blkio = HashMap::new();
+        let mut procs = Vec::new();
+        // wall-clock jiffies of the interval, as seen by a single task
+        let wall = elapsed as f32 / cpus as f32;
+
+        for pid in source.pids() {
+            let Some(stat) = read_proc_stat(source, pid) else {
+                continue;
+            };
+
+            // processes that appeared during the interval are measured from zero
+            let prev = self.last_ticks.get(&pid).copied().unwrap_or(0);
+            let cpu = if elapsed == 0 || last_total == 0 {
+                0.0
+            } else {
+                let delta = stat.ticks.saturating_sub(prev) as f32;
+                delta / elapsed as f32 * cpus as f32 * 100.0
+            };
+
+            let blkio_delay_percent = match (self.delayacct, stat.blkio_ticks) {
+                (true, Some(cur)) if wall > 0.0 && last_total > 0 => {
+                    let prev = self.last_blkio.get(&pid).copied().unwrap_or(0);
+                    Some((cur.saturating_sub(prev) as f32 / wall * 100.0).min(100.0))
+                }
+                _ => None,
+            };
+            if let Some(cur) = stat.blkio_ticks {
+                blkio.insert(pid, cur);
+            }
+
+            ticks.insert(pid, stat.ticks);
+            procs.push(ProcCpu {
+                pid,
+                ppid: stat.ppid,
+                pgrp: stat.pgrp,
+                sid: stat.sid,
+                name: stat.name,
+                state: stat.state,
+                cpu,
+                blkio_delay_percent,
+                threads: Vec::new(),
+            });
+        }
+
+        if self.threads > 0 {
+            let to_percent = |delta: u64| {
+                if elapsed == 0 || last_total == 0 { 0.0 } else { delta as f32 / elapsed as f32 * cpus as f32 * 100.0 }
+            };
+            let mut busiest: Vec<usize> = (0..procs.len()).collect();
+            busiest.sort_by(|&a, &b| procs[b].cpu.total_cmp(&procs[a].cpu));
+            busiest.truncate(THREAD_PROCS);
+            let mut thread_ticks = HashMap::new();
+            for &i in &busiest {
+                procs[i].threads = self.sample_threads(source, procs[i].pid, to_percent, &mut thread_ticks);
+            }
+            self.drilled = busiest.iter().map(|&i| procs[i].pid).collect();
+            self.last_thread_ticks = thread_ticks;
+        }
+
+        self.last_times = times.into_iter().collect();
+        self.last_ticks = ticks;
+        self.last_blkio = blkio;
+        CpuTick { procs, states }
+    }
 }
 
-fn get_parent_process(pid: u32) -> Option<CpuSample> {
-    let stat_path = format!("/proc/{}/stat", pid);
-    let contents = fs::read_to_string(stat_path).ok()?;
-    let parts: Vec<&str> = contents.split_whitespace().collect();
+fn to_sample(p: &ProcSample) -> CpuSample {
+    CpuSample {
+        name: p.name.clone(),
+        pid: p.pid,
+        cpu: p.cpu,
+        sched: p.sched,
+        cgroup: cgroup::resolve_opt(&p.cgroup),
+        members: p.members.clone(),
+        threads: Vec::new(),
+    }
+}
 
-    // stat format: pid (comm) state ppid ...
-    let ppid: u32 = parts.get(3)?.parse().ok()?;
+/// Per-thread CPU of `pid` over the ticks in which its threads were read, hottest first.
+fn hot_threads(snapshots: &[Snapshot], pid: u32) -> Vec<HotThread> {
+    let drilled: Vec<&ProcSample> =
+        snapshots.iter().filter_map(|s| s.procs.iter().find(|p| p.pid == pid)).filter(|p| !p.threads.is_empty()).collect();
+    let mut per_tid: HashMap<u32, HotThread> = HashMap::new();
+    for t in drilled.iter().flat_map(|p| &p.threads) {
+        let hot = per_tid
+            .entry(t.tid)
+            .or_insert_with(|| HotThread { tid: t.tid, name: String::new(), cpu_avg: 0.0, cpu_max: 0.0 });
+        hot.name.clone_from(&t.name);
+        hot.cpu_avg += t.cpu / drilled.len() as f32;
+        hot.cpu_max = hot.cpu_max.max(t.cpu);
+    }
 
-    // get CPU usage for parent via ps
-    let output = std::process::Command::new("ps")
-        .args(["-p", &ppid.to_string(), "-o", "pid,comm,%cpu"])
-        .output()
-        .ok()?;
+    let mut threads: Vec<HotThread> = per_tid.into_values().collect();
+    threads.sort_by(|a, b| b.cpu_avg.total_cmp(&a.cpu_avg).then(a.tid.cmp(&b.tid)));
+    threads.truncate(drilled.iter().map(|p| p.threads.len()).max().unwrap_or(0));
+    threads
+}
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let mut lines = stdout.lines();
-    lines.next()?; // skip header
-    let line = lines.next()?;
-    let mut parts = line.split_whitespace();
+/// Returns the busiest process in a snapshot.
+pub fn get_top_cpu(snapshot: &Snapshot) -> Option<CpuSample> {
+    let top = snapshot
+        .procs
+        .iter()
+        .max_by(|a, b| a.cpu.partial_cmp(&b.cpu).unwrap_or(std::cmp::Ordering::Equal))?;
 
-    let pid: u32 = parts.next()?.parse().ok()?;
-    let name = parts.next()?.to_string();
-    let cpu: f32 = parts.next()?.parse().ok()?;
+    // Check if this is a known browser child process (--group-by tree handles this generally)
+    if top.members.is_empty()
+        && (top.name == "Web" || top.name == "GPU")
+        && let Some(parent) = snapshot.procs.iter().find(|p| p.pid == top.ppid)
+    {
+        return Some(to_sample(parent));
+    }
 
-    Some(CpuSample { pid, name, cpu })
+    Some(to_sample(top))
 }
 
 pub fn detect_sustained_high_cpu(
+    snapshots: &[Snapshot],
     threshold: f32,
-    samples: usize,
     min_hits: usize,
-    interval_secs: u64,
-    exclude_pid: Option<u32>,
 ) -> Option<CpuSample> {
     let mut hits = 0;
     let mut last_sample = None;
 
-    for _ in 0..samples {
-        if let Some(sample) = get_top_cpu_excluding(exclude_pid) {
-            if sample.cpu > threshold {
-                hits += 1;
-                last_sample = Some(sample);
-            }
+    for snapshot in snapshots {
+        if let Some(sample) = get_top_cpu(snapshot)
+            && sample.cpu > threshold
+        {
+            hits += 1;
+            last_sample = Some(sample);
         }
-
-        sleep(Duration::from_secs(interval_secs));
     }
 
     if hits >= min_hits {
-        last_sample
+        last_sample.map(|sample| CpuSample { threads: hot_threads(snapshots, sample.pid), ..sample })
     } else {
         None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sampler::proc_sample;
+    use crate::source::fake::FakeProc;
+
+    fn stat_line(pid: u32, comm: &str, ppid: u32, utime: u64, stime: u64) -> String {
+        format!(
+            "{} ({}) S {} {} {} 0 -1 4194560 100 0 0 0 {} {} 0 0 20 0 1 0 100 1000 50",
+            pid, comm, ppid, pid, pid, utime, stime
+        )
+    }
+
+    #[test]
+    fn parses_comm_with_spaces_and_parens() {
+        let stat = parse_proc_stat(&stat_line(7, "Web Content (x)", 3, 11, 4)).unwrap();
+        assert_eq!(stat.name, "Web Content (x)");
+        assert_eq!((stat.state, stat.ppid), ('S', 3));
+        assert_eq!(stat.ticks, 15);
+        assert_eq!(stat.blkio_ticks, None);
+    }
+
+    #[test]
+    fn reads_blkio_delay_from_field_42() {
+        let line = format!("{} 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 37 0 0", stat_line(7, "dd", 1, 0, 0));
+        assert_eq!(parse_proc_stat(&line).unwrap().blkio_ticks, Some(37));
+    }
+
+    #[test]
+    fn sampler_reports_interval_usage_not_lifetime_average() {
+        let proc = FakeProc::new();
+        proc.file("stat", "cpu  1000 0 1000 0 0 0 0 0 0 0\ncpu0 500 0 500 0\ncpu1 500 0 500 0\n")
+            .file("10/stat", &stat_line(10, "idle-daemon", 1, 900, 0))
+            .file("11/stat", &stat_line(11, "busy", 1, 0, 0));
+        let source = proc.source();
+        let mut sampler = CpuSampler::new(&source);
+
+        // 100 jiffies elapse across 2 CPUs; "busy" burns 50 of them, the daemon none
+        proc.file("stat", "cpu  1050 0 1050 0 0 0 0 0 0 0\ncpu0 525 0 525 0\ncpu1 525 0 525 0\n")
+            .file("11/stat", &stat_line(11, "busy", 1, 40, 10));
+        let procs = sampler.sample(&source).procs;
+
+        let cpu_of = |name: &str| procs.iter().find(|p| p.name == name).unwrap().cpu;
+        assert_eq!(cpu_of("idle-daemon"), 0.0);
+        assert_eq!(cpu_of("busy"), 100.0);
+    }
+
+    #[test]
+    fn drills_into_the_threads_of_the_busiest_process() {
+        let proc = FakeProc::new();
+        proc.file("stat", "cpu  1000 0 1000 0 0 0 0 0 0 0\ncpu0 1000 0 1000 0\n")
+            .file("11/stat", &stat_line(11, "java", 1, 0, 0))
+            .file("11/task/11/stat", &stat_line(11, "java", 1, 0, 0))
+            .file("11/task/12/stat", &stat_line(12, "GC Thread#0", 1, 0, 0))
+            .file("11/task/13/stat", &stat_line(13, "C2 CompilerThre", 1, 0, 0));
+        let source = proc.source();
+        let mut sampler = CpuSampler::new(&source).with_threads(&source, 2);
+
+        // 100 jiffies on one CPU: the compiler thread burns 60, GC 30, main 10
+        proc.file("stat", "cpu  1050 0 1050 0 0 0 0 0 0 0\ncpu0 1050 0 1050 0\n")
+            .file("11/stat", &stat_line(11, "java", 1, 100, 0))
+            .file("11/task/11/stat", &stat_line(11, "java", 1, 10, 0))
+            .file("11/task/12/stat", &stat_line(12, "GC Thread#0", 1, 30, 0))
+            .file("11/task/13/stat", &stat_line(13, "C2 CompilerThre", 1, 50, 10));
+        let procs = sampler.sample(&source).procs;
+
+        let threads: Vec<(&str, f32)> = procs[0].threads.iter().map(|t| (t.name.as_str(), t.cpu.round())).collect();
+        assert_eq!(threads, vec![("C2 CompilerThre", 60.0), ("GC Thread#0", 30.0)]);
+    }
+
+    #[test]
+    fn browser_children_are_reported_as_their_parent() {
+        let snapshot = Snapshot {
+            procs: vec![
+                ProcSample { cpu: 5.0, ..proc_sample(100, 1, "firefox") },
+                ProcSample { cpu: 80.0, ..proc_sample(101, 100, "Web") },
+            ],
+            ..Default::default()
+        };
+
+        let top = get_top_cpu(&snapshot).unwrap();
+        assert_eq!((top.pid, top.name.as_str()), (100, "firefox"));
+    }
+
+    #[test]
+    fn per_core_states_separate_steal_iowait_and_guest() {
+        let proc = FakeProc::new();
+        proc.file("stat", "cpu  100 0 100 800 0 0 0 0 0 0\ncpu0 50 0 50 400 0 0 0 0 0 0\ncpu1 50 0 50 400 0 0 0 0 0 0\n");
+        let source = proc.source();
+        let mut sampler = CpuSampler::new(&source);
+
+        // cpu0: 50 user of which 20 guest, 50 steal; cpu1: 100 iowait
+        proc.file("stat", "cpu  150 0 100 800 100 0 0 50 20 0\ncpu0 100 0 50 400 0 0 0 50 20 0\ncpu1 50 0 50 400 100 0 0 0 0 0\n");
+        let states = sampler.sample(&source).states;
+
+        assert_eq!(states.iter().map(|s| s.cpu.as_str()).collect::<Vec<_>>(), ["cpu", "cpu0", "cpu1"]);
+        let cpu0 = &states[1];
+        assert_eq!((cpu0.user, cpu0.guest, cpu0.steal), (30.0, 20.0, 50.0));
+        assert_eq!(cpu0.busy(), 100.0);
+        let cpu1 = &states[2];
+        assert_eq!((cpu1.iowait, cpu1.busy()), (100.0, 0.0));
+        assert_eq!((states[0].steal, states[0].iowait), (25.0, 50.0));
+    }
+}
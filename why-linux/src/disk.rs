@@ -1,70 +1,261 @@
-use serde::Serialize;
-use std::process::Command;
-use std::thread::sleep;
-use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::ffi::CString;
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
 
-#[derive(Debug, Serialize)]
+use crate::analysis::{human_duration, steady_growth};
+use crate::sampler::Snapshot;
+use crate::source::Source;
+
+/// One mounted filesystem during a tick.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DiskSample {
+    /// Mount source, e.g. /dev/sda1
     pub fs: String,
     pub mount: String,
+    #[serde(default)]
+    pub fstype: String,
+    /// major:minor of the backing device
+    #[serde(default)]
+    pub dev: String,
+    #[serde(default)]
+    pub read_only: bool,
     pub used_percent: f32,
+    #[serde(default)]
+    pub total_bytes: u64,
+    #[serde(default)]
+    pub used_bytes: u64,
+    /// Space available to unprivileged users
+    #[serde(default)]
+    pub avail_bytes: u64,
+    #[serde(default)]
+    pub inodes_total: u64,
+    #[serde(default)]
+    pub inodes_used: u64,
+    /// None on filesystems without a fixed inode table (e.g. btrfs)
+    #[serde(default)]
+    pub inodes_used_percent: Option<f32>,
 }
 
-pub fn get_top_mount_usage() -> Option<DiskSample> {
-    let output = Command::new("df").arg("-P").output().ok()?;
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let mut best: Option<DiskSample> = None;
+/// `--disk-include`/`--disk-exclude`: entries starting with `/` match a mount point and
+/// everything below it, anything else matches the filesystem type.
+#[derive(Debug, Clone, Default)]
+pub struct DiskFilter {
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+}
 
-    for (i, line) in stdout.lines().enumerate() {
-        if i == 0 {
-            continue; // header
+impl DiskFilter {
+    fn matches(pattern: &str, fstype: &str, mount: &str) -> bool {
+        if pattern.starts_with('/') {
+            let prefix = pattern.trim_end_matches('/');
+            mount == pattern || mount.strip_prefix(prefix).is_some_and(|rest| rest.starts_with('/'))
+        } else {
+            fstype == pattern
         }
+    }
 
-        let parts: Vec<&str> = line.split_whitespace().collect();
-        // Expect: filesystem 1024-blocks used available capacity Mounted_on
-        if parts.len() < 6 {
-            continue;
+    pub fn allows(&self, fstype: &str, mount: &str) -> bool {
+        (self.include.is_empty() || self.include.iter().any(|p| Self::matches(p, fstype, mount)))
+            && !self.exclude.iter().any(|p| Self::matches(p, fstype, mount))
+    }
+}
+
+/// One line of `/proc/[pid]/mountinfo`.
+#[derive(Debug, PartialEq)]
+struct MountEntry {
+    dev: String,
+    mount: String,
+    read_only: bool,
+    fstype: String,
+    source: String,
+}
+
+/// Decodes the octal escapes (`\040` for space etc.) used in /proc mount tables.
+fn unescape_mount(field: &str) -> String {
+    let mut out = Vec::with_capacity(field.len());
+    let bytes = field.as_bytes();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if bytes[i] == b'\\'
+            && let Some(code) = field.get(i + 1..i + 4).and_then(|o| u8::from_str_radix(o, 8).ok())
+        {
+            out.push(code);
+            i += 4;
+        } else {
+            out.push(bytes[i]);
+            i += 1;
         }
+    }
 
-        let fs = parts[0].to_string();
-        let cap = parts[4];
-        let mount = parts[5].to_string();
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// Parses a mountinfo table: `id parent maj:min root mount opts [optional...] - fstype source superopts`.
+fn parse_mountinfo(contents: &str) -> Vec<MountEntry> {
+    contents
+        .lines()
+        .filter_map(|line| {
+            let (left, right) = line.split_once(" - ")?;
+            let left: Vec<&str> = left.split_whitespace().collect();
+            let mut right = right.split_whitespace();
+            let options = left.get(5)?;
+
+            Some(MountEntry {
+                dev: left.get(2)?.to_string(),
+                mount: unescape_mount(left.get(4)?),
+                read_only: options.split(',').any(|o| o == "ro"),
+                fstype: right.next()?.to_string(),
+                source: unescape_mount(right.next()?),
+            })
+        })
+        .collect()
+}
+
+/// Capacity and inode counts from statvfs.
+struct FsStats {
+    total_bytes: u64,
+    used_bytes: u64,
+    avail_bytes: u64,
+    used_percent: f32,
+    inodes_total: u64,
+    inodes_used: u64,
+}
+
+/// Usage as `df` reports it, or None for pseudo filesystems without blocks.
+fn statvfs(path: &Path) -> Option<FsStats> {
+    let c_path = CString::new(path.as_os_str().as_bytes()).ok()?;
+    let mut st: libc::statvfs = unsafe { std::mem::zeroed() };
+
+    // SAFETY: c_path is NUL-terminated and st is a valid, writable statvfs
+    if unsafe { libc::statvfs(c_path.as_ptr(), &mut st) } != 0 || st.f_blocks == 0 {
+        return None;
+    }
+
+    let frsize = st.f_frsize as u64;
+    let used = st.f_blocks.saturating_sub(st.f_bfree) as u64;
+    let avail = st.f_bavail as u64;
+    if used + avail == 0 {
+        return None;
+    }
+
+    Some(FsStats {
+        total_bytes: st.f_blocks as u64 * frsize,
+        used_bytes: used * frsize,
+        avail_bytes: avail * frsize,
+        // df rounds the capacity up to the next whole percent
+        used_percent: (used as f64 / (used + avail) as f64 * 100.0).ceil() as f32,
+        inodes_total: st.f_files as u64,
+        inodes_used: st.f_files.saturating_sub(st.f_ffree) as u64,
+    })
+}
 
-        let percent = cap.trim_end_matches('%').parse::<f32>().ok()?;
+pub fn get_mount_usage(source: &Source, filter: &DiskFilter) -> Vec<DiskSample> {
+    let Some(contents) = source.mountinfo() else {
+        return Vec::new();
+    };
+    let mut mounts = Vec::new();
 
-        let sample = DiskSample {
-            fs,
-            mount,
-            used_percent: percent,
+    for entry in parse_mountinfo(&contents) {
+        if !filter.allows(&entry.fstype, &entry.mount) {
+            continue;
+        }
+        // skip mounts that are unreachable or pseudo filesystems, like df does
+        let Some(stats) = statvfs(&source.mount_path(&entry.mount)) else {
+            continue;
         };
 
-        match &best {
+        mounts.push(DiskSample {
+            fs: entry.source,
+            mount: entry.mount,
+            fstype: entry.fstype,
+            dev: entry.dev,
+            read_only: entry.read_only,
+            used_percent: stats.used_percent,
+            total_bytes: stats.total_bytes,
+            used_bytes: stats.used_bytes,
+            avail_bytes: stats.avail_bytes,
+            inodes_total: stats.inodes_total,
+            inodes_used: stats.inodes_used,
+            inodes_used_percent: (stats.inodes_total > 0)
+                .then(|| (stats.inodes_used as f64 / stats.inodes_total as f64 * 100.0) as f32),
+        });
+    }
+
+    mounts
+}
+
+/// Returns the fullest mount in a snapshot.
+pub fn get_top_mount(snapshot: &Snapshot) -> Option<DiskSample> {
+    let mut best: Option<&DiskSample> = None;
+
+    for sample in &snapshot.mounts {
+        match best {
             Some(b) if b.used_percent >= sample.used_percent => {}
             _ => best = Some(sample),
         }
     }
 
-    best
+    best.cloned()
 }
 
+#[allow(clippy::collapsible_if)]
 pub fn detect_sustained_high_disk(
+    snapshots: &[Snapshot],
     threshold: f32,
-    samples: usize,
     min_hits: usize,
-    interval_secs: u64,
 ) -> Option<DiskSample> {
     let mut hits = 0;
     let mut last = None;
 
-    for _ in 0..samples {
-        if let Some(sample) = get_top_mount_usage() {
+    for snapshot in snapshots {
+        if let Some(sample) = get_top_mount(snapshot) {
             if sample.used_percent > threshold {
                 hits += 1;
                 last = Some(sample);
             }
         }
+    }
 
-        sleep(Duration::from_secs(interval_secs));
+    if hits >= min_hits {
+        last
+    } else {
+        None
+    }
+}
+
+/// Returns the mount with the highest inode usage in a snapshot.
+pub fn get_top_inode_mount(snapshot: &Snapshot) -> Option<DiskSample> {
+    snapshot
+        .mounts
+        .iter()
+        .filter(|m| m.inodes_used_percent.is_some())
+        .max_by(|a, b| {
+            a.inodes_used_percent
+                .partial_cmp(&b.inodes_used_percent)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        })
+        .cloned()
+}
+
+/// Inode exhaustion: writes fail with ENOSPC even though `df` shows free space.
+pub fn detect_sustained_inode_exhaustion(
+    snapshots: &[Snapshot],
+    threshold: f32,
+    min_hits: usize,
+) -> Option<DiskSample> {
+    let mut hits = 0;
+    let mut last = None;
+
+    for snapshot in snapshots {
+        if let Some(sample) = get_top_inode_mount(snapshot)
+            && sample.inodes_used_percent.unwrap_or(0.0) > threshold
+        {
+            hits += 1;
+            last = Some(sample);
+        }
     }
 
     if hits >= min_hits {
@@ -73,3 +264,186 @@ pub fn detect_sustained_high_disk(
         None
     }
 }
+
+/// A mount projected to run out of space at its current fill rate.
+#[derive(Debug, Serialize)]
+pub struct DiskForecast {
+    pub fs: String,
+    pub mount: String,
+    pub used_percent: f32,
+    pub avail_bytes: u64,
+    /// Fitted growth of used bytes over the window
+    pub fill_bytes_per_sec: f64,
+    pub full_in_secs: u64,
+}
+
+impl DiskForecast {
+    /// "/var – 60% used, +1024.0 MB/min, full in ~40m"
+    pub fn describe(&self) -> String {
+        format!(
+            "{} ({}) – {:.0}% used, +{:.1} MB/min, full in ~{}",
+            self.mount,
+            self.fs,
+            self.used_percent,
+            self.fill_bytes_per_sec * 60.0 / 1e6,
+            human_duration(self.full_in_secs)
+        )
+    }
+}
+
+/// Fits used bytes over time per mount and reports the one that would fill soonest, if that is
+/// within `within_hours`; independent of `--disk-threshold`. Only steady growth counts, as for
+/// memory, so a burst of writes that is then deleted does not forecast a full disk.
+pub fn detect_disk_fill(snapshots: &[Snapshot], within_hours: f32, min_hits: usize) -> Option<DiskForecast> {
+    let mut series: HashMap<&str, Vec<(f64, f64)>> = HashMap::new();
+    for snapshot in snapshots {
+        for m in &snapshot.mounts {
+            series.entry(&m.mount).or_default().push((snapshot.ts as f64, m.used_bytes as f64));
+        }
+    }
+
+    let latest = snapshots.last()?;
+    let mut best: Option<DiskForecast> = None;
+
+    for (mount, points) in series {
+        if points.len() < min_hits.max(3) {
+            continue;
+        }
+        let Some(bytes_per_sec) = steady_growth(&points) else {
+            continue;
+        };
+        let Some(current) = latest.mounts.iter().find(|m| m.mount == mount) else {
+            continue;
+        };
+        if current.read_only {
+            continue;
+        }
+
+        let full_in_secs = (current.avail_bytes as f64 / bytes_per_sec) as u64;
+        if full_in_secs as f64 > within_hours as f64 * 3600.0 {
+            continue;
+        }
+        if best.as_ref().is_none_or(|b| full_in_secs < b.full_in_secs) {
+            best = Some(DiskForecast {
+                fs: current.fs.clone(),
+                mount: current.mount.clone(),
+                used_percent: current.used_percent,
+                avail_bytes: current.avail_bytes,
+                fill_bytes_per_sec: bytes_per_sec,
+                full_in_secs,
+            });
+        }
+    }
+
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::fake::FakeProc;
+
+    #[test]
+    fn parses_mountinfo_with_escaped_paths_and_flags() {
+        let mounts = parse_mountinfo(
+            "29 1 8:1 / / rw,relatime shared:1 - ext4 /dev/sda1 rw,errors=remount-ro\n\
+             30 29 0:5 / /proc rw,nosuid - proc proc rw\n\
+             41 29 8:17 / /mnt/My\\040Disk ro,relatime - ext4 /dev/sdb1 rw\n\
+             52 29 7:3 / /snap/core/1 ro,nodev master:12 - squashfs /dev/loop3 ro\n",
+        );
+        assert_eq!(mounts.len(), 4);
+        assert_eq!(
+            mounts[2],
+            MountEntry {
+                dev: "8:17".to_string(),
+                mount: "/mnt/My Disk".to_string(),
+                read_only: true,
+                fstype: "ext4".to_string(),
+                source: "/dev/sdb1".to_string(),
+            }
+        );
+        assert_eq!((mounts[3].fstype.as_str(), mounts[0].read_only), ("squashfs", false));
+    }
+
+    #[test]
+    fn filter_matches_fstypes_and_mount_subtrees() {
+        let filter = DiskFilter {
+            include: Vec::new(),
+            exclude: vec!["squashfs".to_string(), "/var/lib/docker".to_string()],
+        };
+        assert!(filter.allows("ext4", "/"));
+        assert!(!filter.allows("squashfs", "/snap/core/1"));
+        assert!(!filter.allows("overlay", "/var/lib/docker/overlay2/abc/merged"));
+        assert!(filter.allows("ext4", "/var/lib/dockerd"));
+
+        let only_home = DiskFilter {
+            include: vec!["/home".to_string()],
+            exclude: Vec::new(),
+        };
+        assert!(only_home.allows("ext4", "/home") && !only_home.allows("ext4", "/"));
+    }
+
+    #[test]
+    fn forecasts_fill_below_the_percent_threshold() {
+        const GB: u64 = 1_000_000_000;
+        let mount = |used: u64, mount: &str| DiskSample {
+            fs: "/dev/sda1".to_string(),
+            mount: mount.to_string(),
+            fstype: "ext4".to_string(),
+            dev: "8:1".to_string(),
+            read_only: false,
+            used_percent: (used * 100 / (100 * GB)) as f32,
+            total_bytes: 100 * GB,
+            used_bytes: used,
+            avail_bytes: 100 * GB - used,
+            inodes_total: 0,
+            inodes_used: 0,
+            inodes_used_percent: None,
+        };
+        // /var grows 1 GB/min from 60% full; /home is static
+        let snapshots: Vec<Snapshot> = (0..5u64)
+            .map(|i| Snapshot {
+                ts: i * 60,
+                mounts: vec![mount(60 * GB + i * GB, "/var"), mount(50 * GB, "/home")],
+                ..Default::default()
+            })
+            .collect();
+
+        let forecast = detect_disk_fill(&snapshots, 6.0, 3).unwrap();
+        assert_eq!((forecast.mount.as_str(), forecast.full_in_secs), ("/var", 36 * 60));
+        assert!(detect_disk_fill(&snapshots, 0.5, 3).is_none());
+        assert!(detect_disk_fill(&snapshots, 6.0, 6).is_none());
+
+        // a 20 GB burst that is deleted again and rewritten ends higher, but is no trend
+        let sawtooth: Vec<Snapshot> = [60, 80, 60, 80, 61, 81]
+            .iter()
+            .enumerate()
+            .map(|(i, used)| Snapshot {
+                ts: i as u64 * 60,
+                mounts: vec![mount(used * GB, "/var")],
+                ..Default::default()
+            })
+            .collect();
+        assert!(detect_disk_fill(&sawtooth, 6.0, 3).is_none());
+    }
+
+    #[test]
+    fn pseudo_filesystems_are_skipped() {
+        // the fake root sits on whatever holds the temp dir; its /proc is a real procfs, which
+        // has no blocks, and /mnt/gone is in mountinfo but unreachable
+        let proc = FakeProc::new();
+        proc.file(
+            "1/mountinfo",
+            "29 1 8:1 / / rw,relatime - ext4 /dev/sda1 rw\n\
+             30 29 0:5 / /proc rw,nosuid - proc proc rw\n\
+             41 29 8:17 / /mnt/gone rw - ext4 /dev/sdb1 rw\n",
+        )
+        .symlink("1/root/proc", Path::new("/proc"));
+
+        let mounts = get_mount_usage(&proc.source(), &DiskFilter::default());
+        assert_eq!(mounts.iter().map(|m| m.mount.as_str()).collect::<Vec<_>>(), vec!["/"]);
+        let root = &mounts[0];
+        assert_eq!((root.fs.as_str(), root.dev.as_str()), ("/dev/sda1", "8:1"));
+        assert!(root.total_bytes >= root.used_bytes && root.total_bytes > 0);
+    }
+}
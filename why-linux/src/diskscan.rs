@@ -0,0 +1,306 @@
+// Opt-in `--disk-scan`: what is using the space on a full mount. Walks the mount without crossing
+// into other filesystems and looks for the usual culprits.
+use crate::source::Source;
+use serde::Serialize;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::fs;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// Entries listed per category.
+const TOP_N: usize = 10;
+/// Directory totals are kept this many levels below the mount.
+const DIR_DEPTH: usize = 4;
+/// The walk stops after this many entries so a huge tree cannot stall the report.
+const MAX_ENTRIES: usize = 2_000_000;
+/// Culprits smaller than this are not worth listing.
+const MIN_CULPRIT_BYTES: u64 = 1 << 20;
+/// Files modified within this window count as recently grown.
+const RECENT: Duration = Duration::from_secs(24 * 3600);
+
+/// Well-known space hogs, as absolute paths; found on whichever mount holds them.
+const CULPRITS: &[(&str, &str)] = &[
+    ("systemd journal", "/var/log/journal"),
+    ("apt package cache", "/var/cache/apt/archives"),
+    ("dnf package cache", "/var/cache/dnf"),
+    ("yum package cache", "/var/cache/yum"),
+    ("pacman package cache", "/var/cache/pacman/pkg"),
+    ("docker storage", "/var/lib/docker"),
+    ("containerd storage", "/var/lib/containerd"),
+    ("podman storage", "/var/lib/containers"),
+    ("systemd-coredump dumps", "/var/lib/systemd/coredump"),
+    ("apport crash reports", "/var/crash"),
+];
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub struct PathSize {
+    pub bytes: u64,
+    pub path: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Culprit {
+    pub what: String,
+    pub path: String,
+    pub bytes: u64,
+}
+
+/// A file that was unlinked but is still open, so its space is not freed yet.
+#[derive(Debug, Serialize)]
+pub struct DeletedOpen {
+    pub pid: u32,
+    pub name: String,
+    pub path: String,
+    pub bytes: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ScanReport {
+    pub mount: String,
+    pub largest_dirs: Vec<PathSize>,
+    pub largest_files: Vec<PathSize>,
+    /// Files modified in the last 24 hours, largest first
+    pub recently_grown: Vec<PathSize>,
+    pub culprits: Vec<Culprit>,
+    pub deleted_open: Vec<DeletedOpen>,
+    /// Set when the walk hit its entry limit; totals are then lower bounds
+    pub truncated: bool,
+}
+
+/// Keeps the `TOP_N` largest entries seen.
+#[derive(Default)]
+struct TopN(BinaryHeap<Reverse<PathSize>>);
+
+impl TopN {
+    fn push(&mut self, bytes: u64, path: impl FnOnce() -> String) {
+        if self.0.len() < TOP_N {
+            self.0.push(Reverse(PathSize { bytes, path: path() }));
+        } else if self.0.peek().is_some_and(|min| bytes > min.0.bytes) {
+            self.0.pop();
+            self.0.push(Reverse(PathSize { bytes, path: path() }));
+        }
+    }
+
+    fn into_sorted(self) -> Vec<PathSize> {
+        let mut v: Vec<PathSize> = self.0.into_iter().map(|r| r.0).collect();
+        v.sort_by(|a, b| b.cmp(a));
+        v
+    }
+}
+
+/// Maps a path under the scan root back to the path users know, e.g. `<proc>/1/root/var` -> `/var`.
+fn display(root: &Path, mount: &str, path: &Path) -> String {
+    let rel = path.strip_prefix(root).unwrap_or(path);
+    if rel.as_os_str().is_empty() {
+        mount.to_string()
+    } else {
+        format!("{}/{}", mount.trim_end_matches('/'), rel.display())
+    }
+}
+
+/// Where an absolute `path` lies below `mount`, e.g. `/var/lib/docker` on `/var` is `lib/docker`;
+/// None when it is on another mount.
+fn below_mount<'a>(mount: &str, path: &'a str) -> Option<&'a str> {
+    let mount = mount.trim_end_matches('/');
+    let rel = path.strip_prefix(mount)?;
+    if mount.is_empty() {
+        Some(rel.trim_start_matches('/'))
+    } else {
+        rel.strip_prefix('/')
+    }
+}
+
+fn is_core_dump(name: &str) -> bool {
+    name == "core" || name.strip_prefix("core.").is_some_and(|rest| rest.chars().all(|c| c.is_ascii_digit()))
+}
+
+/// Files on the filesystem `dev` that are deleted but still held open, from `/proc/[pid]/fd`.
+fn deleted_open_files(source: &Source, dev: u64) -> Vec<DeletedOpen> {
+    let mut found = Vec::new();
+
+    for pid in source.pids() {
+        let Ok(fds) = fs::read_dir(source.pid_path(pid, "fd")) else {
+            continue;
+        };
+        for fd in fds.flatten() {
+            let Ok(target) = fs::read_link(fd.path()) else {
+                continue;
+            };
+            let Some(path) = target.to_str().and_then(|t| t.strip_suffix(" (deleted)")) else {
+                continue;
+            };
+            // stat through the fd link reaches the open inode even though its name is gone
+            let Ok(meta) = fs::metadata(fd.path()) else {
+                continue;
+            };
+            if meta.dev() != dev || !meta.is_file() {
+                continue;
+            }
+
+            let name = source
+                .read_pid(pid, "comm")
+                .map(|c| c.trim().to_string())
+                .unwrap_or_default();
+            found.push(DeletedOpen {
+                pid,
+                name,
+                path: path.to_string(),
+                bytes: meta.len(),
+            });
+        }
+    }
+
+    found.sort_by_key(|d| Reverse(d.bytes));
+    found
+}
+
+/// Walks `mount` (as reachable through `source`) and attributes its used space.
+pub fn scan(source: &Source, mount: &str) -> Option<ScanReport> {
+    let root = source.mount_path(mount);
+    let dev = fs::symlink_metadata(&root).ok()?.dev();
+    let recent_since = SystemTime::now() - RECENT;
+
+    let mut dirs: HashMap<PathBuf, u64> = HashMap::new();
+    let mut largest_files = TopN::default();
+    let mut recently_grown = TopN::default();
+    let mut core_dumps = 0u64;
+    let mut entries = 0usize;
+    let mut stack = vec![root.clone()];
+
+    while let Some(dir) = stack.pop() {
+        let Ok(children) = fs::read_dir(&dir) else {
+            continue;
+        };
+        for child in children.flatten() {
+            entries += 1;
+            if entries > MAX_ENTRIES {
+                break;
+            }
+            let Ok(meta) = child.metadata() else {
+                continue;
+            };
+            // don't descend into other filesystems mounted below this one
+            if meta.dev() != dev {
+                continue;
+            }
+            let path = child.path();
+
+            if meta.is_dir() {
+                stack.push(path);
+                continue;
+            }
+            if !meta.is_file() {
+                continue;
+            }
+
+            // allocated size, so sparse files count as what they really use
+            let bytes = meta.blocks() * 512;
+            for ancestor in path.ancestors().skip(1) {
+                if !ancestor.starts_with(&root) || ancestor == root {
+                    break;
+                }
+                if ancestor.strip_prefix(&root).map(|r| r.components().count()).unwrap_or(0) <= DIR_DEPTH {
+                    *dirs.entry(ancestor.to_path_buf()).or_insert(0) += bytes;
+                }
+            }
+
+            largest_files.push(bytes, || display(&root, mount, &path));
+            if meta.modified().is_ok_and(|m| m >= recent_since) {
+                recently_grown.push(bytes, || display(&root, mount, &path));
+            }
+            if child.file_name().to_str().is_some_and(is_core_dump) {
+                core_dumps += bytes;
+            }
+        }
+        if entries > MAX_ENTRIES {
+            break;
+        }
+    }
+
+    let mut culprits: Vec<Culprit> = CULPRITS
+        .iter()
+        .filter_map(|(what, path)| {
+            let rel = below_mount(mount, path)?;
+            let bytes = *dirs.get(&root.join(rel))?;
+            (bytes >= MIN_CULPRIT_BYTES).then(|| Culprit {
+                what: what.to_string(),
+                path: display(&root, mount, &root.join(rel)),
+                bytes,
+            })
+        })
+        .collect();
+    if core_dumps >= MIN_CULPRIT_BYTES {
+        culprits.push(Culprit {
+            what: "core files".to_string(),
+            path: mount.to_string(),
+            bytes: core_dumps,
+        });
+    }
+    culprits.sort_by_key(|c| Reverse(c.bytes));
+
+    let mut largest_dirs = TopN::default();
+    for (path, bytes) in &dirs {
+        largest_dirs.push(*bytes, || display(&root, mount, path));
+    }
+
+    Some(ScanReport {
+        mount: mount.to_string(),
+        largest_dirs: largest_dirs.into_sorted(),
+        largest_files: largest_files.into_sorted(),
+        recently_grown: recently_grown.into_sorted(),
+        culprits,
+        deleted_open: deleted_open_files(source, dev),
+        truncated: entries > MAX_ENTRIES,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::fake::FakeProc;
+
+    #[test]
+    fn attributes_space_to_dirs_files_culprits_and_deleted_files() {
+        let proc = FakeProc::new();
+        let big = "x".repeat(1 << 20);
+        proc.file("1/root/var/log/journal/abc/system.journal", &big)
+            .file("1/root/home/user/core", &big)
+            .file("1/root/home/user/notes.txt", "hi")
+            .file("gone/app.log (deleted)", &big)
+            .file("7/comm", "logger\n")
+            .symlink("7/fd/3", &proc.path("gone/app.log (deleted)"));
+
+        let report = scan(&proc.source(), "/").unwrap();
+
+        assert_eq!(report.largest_files.len(), 3);
+        assert!(report.largest_files[0].bytes >= 1 << 20);
+        assert!(report.largest_dirs.iter().any(|d| d.path == "/var/log/journal"));
+        assert_eq!(report.recently_grown.len(), 3);
+
+        let whats: Vec<&str> = report.culprits.iter().map(|c| c.what.as_str()).collect();
+        assert!(whats.contains(&"systemd journal") && whats.contains(&"core files"));
+
+        assert_eq!(report.deleted_open.len(), 1);
+        assert_eq!((report.deleted_open[0].pid, report.deleted_open[0].name.as_str()), (7, "logger"));
+        assert!(!report.truncated);
+    }
+
+    #[test]
+    fn finds_culprits_on_a_separate_var_mount() {
+        let proc = FakeProc::new();
+        let big = "x".repeat(1 << 20);
+        proc.file("1/root/var/lib/docker/overlay2/abc/layer.tar", &big)
+            .file("1/root/var/log/journal/abc/system.journal", &big);
+
+        let report = scan(&proc.source(), "/var").unwrap();
+
+        let culprits: Vec<(&str, &str)> = report.culprits.iter().map(|c| (c.what.as_str(), c.path.as_str())).collect();
+        assert!(culprits.contains(&("docker storage", "/var/lib/docker")));
+        assert!(culprits.contains(&("systemd journal", "/var/log/journal")));
+        assert_eq!(below_mount("/var", "/var/lib/docker"), Some("lib/docker"));
+        assert_eq!(below_mount("/data", "/var/lib/docker"), None);
+        assert_eq!(below_mount("/var/lib", "/var/library"), None);
+    }
+}
@@ -33,6 +33,42 @@ Mitigation:
 • move large files to another disk or expand the filesystem
 • consider adding separate partitions for var/tmp or adding more disk space",
 
+        "mem_growth" =>
+            "Memory that keeps growing at a steady rate is the classic sign of a leak.
+Common causes:
+• caches or queues without a size limit
+• objects kept alive by forgotten references, listeners or timers
+• long-running processes that never return freed memory to the system
+
+Mitigation:
+• confirm with a longer window, or with `--watch`, before acting
+• capture a heap profile (e.g. heaptrack, jemalloc profiling, language-specific tools)
+• restart the process on a schedule or cap it with a cgroup memory limit until it is fixed",
+
+        "disk_fill" =>
+            "A filesystem is filling at a rate that will exhaust it soon, even if it is not nearly full yet.
+Common causes:
+• a log file growing without rotation, often from a component stuck in an error loop
+• core dumps, crash reports or debug traces being written repeatedly
+• a backup, download or database export writing to the wrong filesystem
+
+Mitigation:
+• find what is growing (e.g. run `du -x --max-depth=2` twice a minute apart)
+• stop or throttle the writer, then rotate or truncate the file (truncate, don't delete, if it is still open)
+• add log rotation or size limits (logrotate, journald SystemMaxUse)",
+
+        "inodes" =>
+            "The filesystem is running out of inodes: new files cannot be created even though `df` shows free space.
+Common causes:
+• millions of tiny files (mail or session spools, caches, build trees, node_modules)
+• runaway temp or log file creation
+• container image layers unpacked on a filesystem created with few inodes
+
+Mitigation:
+• find directories with the most files (e.g. `du --inodes -x / | sort -n | tail`)
+• delete or archive small files, or move them to a filesystem with more inodes
+• recreate the filesystem with a smaller bytes-per-inode ratio (mkfs.ext4 -i) or use one with dynamic inodes",
+
         "io" =>
             "High disk I/O (read/write) can make systems feel very slow, even with free space.
 Common causes:
@@ -40,11 +76,100 @@ Common causes:
 • database or indexing workloads
 • log-heavy applications or runaway processes writing continuously
 
+The pattern line tells the usual shapes apart:
+• many tiny syscalls: small unbuffered reads/writes; CPU and syscall overhead dominate, batching helps most
+• large streaming writes: bulk copies or dumps; limited by device throughput
+• blocked waiting on disk: the process spends its time in D state; the device, not the process, is the bottleneck
+
 Mitigation:
 • identify the process with high I/O and throttle or reschedule it
 • move heavy activity to off-peak times or faster storage
 • add io-weighting via cgroups/ionice to deprioritize background jobs",
 
+        "device" =>
+            "A block device is busy nearly all the time, so every read and write queues behind it.
+This can happen without any single heavy process: many small writers add up, and kernel writeback
+of dirty pages or swap traffic is not charged to any process at all.
+Common causes:
+• slow or failing storage (check await: tens of milliseconds on an SSD is a warning sign, and `dmesg` for errors)
+• a burst of dirty-page writeback after a large write, or swapping under memory pressure
+• several services sharing one disk (databases, logs and backups on the same device)
+
+Mitigation:
+• move heavy workloads to separate or faster devices
+• throttle background writers (ionice, io.max/io.weight in cgroups) or tune vm.dirty_* limits
+• note that %util overstates saturation on NVMe and RAID devices that serve requests in parallel",
+
+        "sched" =>
+            "Processes are runnable but spend more time waiting in the CPU run queue than running.
+The CPUs are oversubscribed: more threads want to run than there are cores, so everything gets
+a smaller share and latency climbs, even when no single process shows high CPU.
+Common causes:
+• more busy threads or worker processes than cores (thread pools sized for a bigger machine)
+• CPU quotas or cpusets in containers limiting a workload to fewer cores than it uses
+• noisy neighbours: batch jobs or builds competing with latency-sensitive services
+• in VMs, steal time from an oversubscribed host (check steal in the CPU states)
+
+Mitigation:
+• size thread pools and worker counts to the available cores (`nproc`, cgroup cpu.max)
+• lower the priority of batch work (nice, cgroup cpu.weight) or move it elsewhere
+• add cores, or spread the workload over more machines",
+
+        "d_state" =>
+            "These processes sit in uninterruptible sleep (D state): they wait inside the kernel, usually
+for I/O, and cannot be interrupted or killed until it completes. Each one adds 1 to the load average
+without using CPU, which is why load can be high while the CPUs are idle.
+Common causes:
+• slow, failing or saturated storage (check the block device stats above)
+• unreachable network filesystems (NFS, CIFS, FUSE) with hard mounts
+• heavy swapping or memory reclaim, page-cache writeback stalls
+• kernel or driver bugs (the wchan and stack show where it is stuck)
+
+Mitigation:
+• read the wchan/stack: nfs_* or rpc_* points to the network share, io_schedule or folio_wait_* to the disk
+• fix or fail over the backing storage; use soft or intr-capable mounts for network shares where safe
+• check `dmesg` for hung task warnings and I/O errors",
+
+        "thermal" =>
+            "The CPUs are being slowed down, so work takes longer even though CPU usage looks normal.
+Common causes:
+• overheating: blocked vents, failed or dusty fans, dried-out thermal paste, hot server rooms
+• power or current limits (RAPL, laptop on battery, undersized PSU) capping the clock
+• an energy-saving governor or platform profile (`powersave`, BIOS power capping) under load
+• in VMs, the host throttling the physical CPU (this usually cannot be seen from inside)
+
+Mitigation:
+• check the hardware: `sensors`, fan speeds and airflow, and the BMC or IPMI event log on servers
+• review the cpufreq governor and platform profile (`cpupower frequency-info`, `powerprofilesctl`)
+• spread or reschedule sustained heavy work; throttling drops once the package cools",
+
+        "net" =>
+            "The network is the bottleneck: an interface is moving a lot of data, TCP is resending
+a noticeable share of its segments, or the box holds an unusual number of connections.
+Common causes:
+• backups, image pulls or replication saturating the uplink
+• packet loss on a congested or faulty link (retransmits and drops go up together)
+• connection storms: clients reconnecting in a loop, missing keep-alive or connection pooling
+• many CLOSE_WAIT sockets: an application that never closes connections the peer already closed
+
+Mitigation:
+• find who is talking with `ss -tanp` and the process list above, then throttle or reschedule bulk transfers
+• check the link and NIC (`ip -s link`, `ethtool -S`) when drops or errors are non-zero
+• reuse connections (pooling, keep-alive) and fix leaks; tune tcp_tw_reuse only as a last resort",
+
+        "psi" =>
+            "Pressure stall information (PSI) measures how much time tasks spent waiting instead of running.
+\"some\" means at least one task was stalled; \"full\" means every non-idle task was stalled at once.
+Common causes:
+• cpu: more runnable work than cores (oversubscription, CPU limits in cgroups)
+• memory: reclaim and swapping because the working set no longer fits in RAM
+• io: slow or saturated storage, often made worse by memory pressure evicting page cache
+
+Mitigation:
+• cpu: reduce parallelism, raise CPU limits or move work to other hosts
+• memory: find the largest processes, add RAM or swap, or set memory limits on batch jobs
+• io: identify heavy readers/writers, use faster storage or deprioritize with ionice/io.weight",
+
         _ =>
             "Sustained high resource usage usually means a process is busy, leaking memory, or stuck.
 If this happens while idle, consider:
@@ -0,0 +1,244 @@
+// Folds per-process rows into groups (process tree, name, cgroup or user) so that multi-process
+// workloads are ranked and detected as one offender.
+use crate::io::IoDetail;
+use crate::mem::ProcMem;
+use crate::sampler::{GroupMember, ProcSample, Snapshot};
+use crate::source::Source;
+use clap::ValueEnum;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum GroupBy {
+    /// Individual processes (no grouping)
+    Pid,
+    /// A session leader or top-level service plus all its descendants (e.g. a shell and its jobs)
+    Tree,
+    /// Processes with the same command name
+    Name,
+    /// Processes in the same cgroup
+    Cgroup,
+    /// Processes owned by the same user
+    User,
+}
+
+/// uid -> user name from an /etc/passwd style file.
+fn parse_passwd(contents: &str) -> HashMap<u32, String> {
+    contents
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.split(':');
+            let name = parts.next()?;
+            let uid = parts.nth(1)?.parse().ok()?;
+            Some((uid, name.to_string()))
+        })
+        .collect()
+}
+
+pub struct Grouper {
+    by: GroupBy,
+    users: HashMap<u32, String>,
+}
+
+impl Grouper {
+    pub fn new(by: GroupBy, source: &Source) -> Self {
+        let users = if by == GroupBy::User {
+            std::fs::read_to_string(source.mount_path("/etc/passwd")).map(|c| parse_passwd(&c)).unwrap_or_default()
+        } else {
+            HashMap::new()
+        };
+        Grouper { by, users }
+    }
+
+    /// Climbs parent links up to the session leader or the child of init (or kthreadd) it descends from.
+    fn tree_root<'a>(procs: &HashMap<u32, &'a ProcSample>, mut p: &'a ProcSample) -> &'a ProcSample {
+        // bounded in case pid reuse makes the parent links loop
+        for _ in 0..procs.len() {
+            if p.pid == p.sid {
+                break;
+            }
+            let Some(parent) = procs.get(&p.ppid) else {
+                break;
+            };
+            if parent.ppid == 0 || parent.pid == p.pid {
+                break;
+            }
+            p = parent;
+        }
+        p
+    }
+
+    /// Returns a copy of the snapshot whose processes are replaced by one row per group.
+    pub fn apply(&self, snapshot: &Snapshot) -> Snapshot {
+        if self.by == GroupBy::Pid {
+            return snapshot.clone();
+        }
+
+        let by_pid: HashMap<u32, &ProcSample> = snapshot.procs.iter().map(|p| (p.pid, p)).collect();
+        let mut groups: Vec<(String, u32, Vec<&ProcSample>)> = Vec::new();
+        let mut index: HashMap<String, usize> = HashMap::new();
+
+        for p in &snapshot.procs {
+            let (key, leader) = match self.by {
+                GroupBy::Tree => {
+                    let root = Self::tree_root(&by_pid, p);
+                    (format!("{}:{}", root.pid, root.name), Some(root))
+                }
+                GroupBy::Name => (p.name.clone(), None),
+                GroupBy::Cgroup => (p.cgroup.clone(), None),
+                GroupBy::User => (
+                    self.users.get(&p.uid).cloned().unwrap_or_else(|| p.uid.to_string()),
+                    None,
+                ),
+                GroupBy::Pid => unreachable!(),
+            };
+
+            let i = *index.entry(key.clone()).or_insert_with(|| {
+                let (name, pid) = match leader {
+                    Some(root) => (root.name.clone(), root.pid),
+                    None => (key.clone(), p.pid),
+                };
+                groups.push((name, pid, Vec::new()));
+                groups.len() - 1
+            });
+            groups[i].2.push(p);
+        }
+
+        let procs = groups
+            .into_iter()
+            .map(|(name, pid, members)| {
+                let leader = by_pid.get(&pid).copied().unwrap_or(members[0]);
+                let mut member_rows: Vec<GroupMember> = members
+                    .iter()
+                    .map(|m| GroupMember {
+                        pid: m.pid,
+                        name: m.name.clone(),
+                        cpu: m.cpu,
+                        mem: m.mem,
+                        read_bps: m.read_bps,
+                        write_bps: m.write_bps,
+                    })
+                    .collect();
+                member_rows.sort_by(|a, b| b.cpu.partial_cmp(&a.cpu).unwrap_or(std::cmp::Ordering::Equal));
+
+                ProcSample {
+                    // lowest pid keeps name/user/cgroup groups stable across ticks
+                    pid: if self.by == GroupBy::Tree { pid } else { members.iter().map(|m| m.pid).min().unwrap_or(pid) },
+                    ppid: leader.ppid,
+                    pgrp: leader.pgrp,
+                    sid: leader.sid,
+                    uid: leader.uid,
+                    cgroup: leader.cgroup.clone(),
+                    name,
+                    // a group is as stuck as its worst member
+                    state: if members.iter().any(|m| m.state == 'D') { 'D' } else { leader.state },
+                    cpu: members.iter().map(|m| m.cpu).sum(),
+                    mem: members.iter().map(|m| m.mem).sum(),
+                    mem_kb: members.iter().fold(ProcMem::default(), |mut sum, m| {
+                        sum.add(&m.mem_kb);
+                        sum
+                    }),
+                    read_bps: members.iter().map(|m| m.read_bps).sum(),
+                    write_bps: members.iter().map(|m| m.write_bps).sum(),
+                    sched: members.iter().filter_map(|m| m.sched).reduce(|mut sum, s| {
+                        sum.add(&s);
+                        sum
+                    }),
+                    io: members.iter().fold(IoDetail::default(), |mut sum, m| {
+                        sum.add(&m.io);
+                        sum
+                    }),
+                    members: if member_rows.len() > 1 { member_rows } else { Vec::new() },
+                    threads: {
+                        let mut threads: Vec<_> = members.iter().flat_map(|m| m.threads.iter().cloned()).collect();
+                        threads.sort_by(|a, b| b.cpu.total_cmp(&a.cpu));
+                        threads
+                    },
+                }
+            })
+            .collect();
+
+        Snapshot {
+            procs,
+            ..snapshot.clone()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sampler::proc_sample;
+    use crate::source::fake::FakeProc;
+
+    fn session_member(pid: u32, ppid: u32, sid: u32, name: &str, cpu: f32) -> ProcSample {
+        ProcSample {
+            sid,
+            uid: 1000,
+            cgroup: "/user.slice".to_string(),
+            cpu,
+            mem: 1.0,
+            write_bps: 10,
+            ..proc_sample(pid, ppid, name)
+        }
+    }
+
+    #[test]
+    fn tree_groups_descendants_under_the_session_leader_or_top_level_service() {
+        // init(1) -> sshd(5) -> sshd(9) -> bash(10, session 10) -> make(20) -> sh(21) -> gcc(22) -> cc1(23);
+        // vim(30) is another job of the same shell, and the job-server's helper(40) sits directly under init
+        let snap = Snapshot {
+            procs: vec![
+                session_member(1, 0, 1, "systemd", 0.0),
+                session_member(5, 1, 5, "sshd", 0.0),
+                session_member(9, 5, 5, "sshd", 0.5),
+                session_member(10, 9, 10, "bash", 0.0),
+                session_member(20, 10, 10, "make", 1.0),
+                session_member(21, 20, 10, "sh", 0.0),
+                session_member(22, 21, 10, "gcc", 2.0),
+                session_member(23, 22, 10, "cc1", 90.0),
+                session_member(30, 10, 10, "vim", 5.0),
+                session_member(40, 1, 10, "helper", 3.0),
+            ],
+            ..Default::default()
+        };
+
+        let grouped = Grouper::new(GroupBy::Tree, &FakeProc::new().source()).apply(&snap);
+        let bash = grouped.procs.iter().find(|p| p.name == "bash").unwrap();
+        assert_eq!((bash.pid, bash.cpu, bash.write_bps, bash.members.len()), (10, 98.0, 60, 6));
+        assert_eq!(bash.members[0].name, "cc1");
+        let sshd = grouped.procs.iter().find(|p| p.name == "sshd").unwrap();
+        assert_eq!((sshd.pid, sshd.members.len()), (5, 2));
+        assert_eq!(grouped.procs.len(), 4);
+    }
+
+    #[test]
+    fn user_groups_are_named_from_the_monitored_root_passwd() {
+        let proc = FakeProc::new();
+        proc.file("1/root/etc/passwd", "root:x:0:0:root:/root:/bin/bash\nbuilder:x:1000:1000::/home/builder:/bin/sh\n");
+
+        let snap = Snapshot { procs: vec![session_member(10, 1, 10, "make", 1.0)], ..Default::default() };
+        let grouped = Grouper::new(GroupBy::User, &proc.source()).apply(&snap);
+        assert_eq!(grouped.procs[0].name, "builder");
+    }
+
+    #[test]
+    fn name_groups_sum_and_keep_lowest_pid() {
+        let snap = Snapshot {
+            procs: vec![
+                session_member(50, 1, 50, "chrome", 10.0),
+                session_member(40, 1, 40, "chrome", 20.0),
+            ],
+            ..Default::default()
+        };
+
+        let grouped = Grouper::new(GroupBy::Name, &FakeProc::new().source()).apply(&snap);
+        assert_eq!(grouped.procs.len(), 1);
+        assert_eq!((grouped.procs[0].pid, grouped.procs[0].cpu), (40, 30.0));
+    }
+
+    #[test]
+    fn parses_passwd() {
+        let users = parse_passwd("root:x:0:0:root:/root:/bin/bash\nalice:x:1000:1000::/home/alice:/bin/sh\n");
+        assert_eq!(users.get(&1000).map(String::as_str), Some("alice"));
+    }
+}
@@ -1,8 +1,10 @@
-use serde::Serialize;
-use std::collections::HashMap;
-use std::fs;
-use std::thread::sleep;
-use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+use crate::cgroup::{self, Cgroup};
+use crate::cpu::ProcCpu;
+use crate::sampler::{GroupMember, ProcSample, Snapshot};
+use crate::source::Source;
 
 #[derive(Debug, Serialize)]
 pub struct IoSample {
@@ -10,128 +12,459 @@ pub struct IoSample {
     pub name: String,
     pub read_bps: u64,
     pub write_bps: u64,
+    #[serde(flatten)]
+    pub detail: IoDetail,
+    /// What kind of I/O this is, when it clearly fits one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cgroup: Option<Cgroup>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub members: Vec<GroupMember>,
+}
+
+/// Per-second rates from the rest of `/proc/[pid]/io`, plus the time spent blocked on disk.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct IoDetail {
+    /// Bytes passed to read()-like syscalls, page-cache hits included
+    pub rchar_bps: u64,
+    /// Bytes passed to write()-like syscalls, before the page cache absorbs them
+    pub wchar_bps: u64,
+    pub syscr_per_sec: f32,
+    pub syscw_per_sec: f32,
+    /// Dirty page-cache bytes that were never written because the file was truncated or deleted
+    pub cancelled_write_bps: u64,
+    /// Percent of the tick spent waiting for block I/O (delayacct_blkio_ticks); None when the
+    /// kernel does not do delay accounting
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub blkio_delay_percent: Option<f32>,
+}
+
+/// Syscall rate above which small average transfers count as "many tiny syscalls".
+const TINY_SYSCALLS_PER_SEC: f32 = 1000.0;
+const TINY_SYSCALL_BYTES: f32 = 4096.0;
+/// Average bytes per write() that count as streaming.
+const STREAMING_WRITE_BYTES: f32 = 64.0 * 1024.0;
+/// Share of the tick spent waiting on disk that counts as blocked.
+const BLOCKED_PERCENT: f32 = 50.0;
+
+impl IoDetail {
+    /// Folds another process's rates into a group total; blocked time is the worst member's.
+    pub fn add(&mut self, other: &IoDetail) {
+        self.rchar_bps += other.rchar_bps;
+        self.wchar_bps += other.wchar_bps;
+        self.syscr_per_sec += other.syscr_per_sec;
+        self.syscw_per_sec += other.syscw_per_sec;
+        self.cancelled_write_bps += other.cancelled_write_bps;
+        self.blkio_delay_percent = match (self.blkio_delay_percent, other.blkio_delay_percent) {
+            (Some(a), Some(b)) => Some(a.max(b)),
+            (a, b) => a.or(b),
+        };
+    }
+
+    /// Tells "blocked waiting on disk", "many tiny syscalls" and "large streaming writes" apart.
+    pub fn pattern(&self, write_bps: u64) -> Option<String> {
+        if let Some(delay) = self.blkio_delay_percent.filter(|d| *d >= BLOCKED_PERCENT) {
+            return Some(format!("blocked waiting on disk {:.0}% of the time", delay));
+        }
+
+        let syscalls = self.syscr_per_sec + self.syscw_per_sec;
+        let per_syscall = (self.rchar_bps + self.wchar_bps) as f32 / syscalls.max(1.0);
+        if syscalls >= TINY_SYSCALLS_PER_SEC && per_syscall < TINY_SYSCALL_BYTES {
+            return Some(format!("many tiny syscalls ({:.0}/s, {:.0} bytes each on average)", syscalls, per_syscall));
+        }
+
+        let per_write = self.wchar_bps as f32 / self.syscw_per_sec.max(1.0);
+        if write_bps > 0 && per_write >= STREAMING_WRITE_BYTES {
+            return Some(format!("large streaming writes ({:.0} KiB per write)", per_write / 1024.0));
+        }
+
+        None
+    }
+}
+
+/// Cumulative counters from `/proc/[pid]/io`.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct IoCounters {
+    rchar: u64,
+    wchar: u64,
+    syscr: u64,
+    syscw: u64,
+    read_bytes: u64,
+    write_bytes: u64,
+    cancelled_write_bytes: u64,
+}
+
+impl IoCounters {
+    fn saturating_sub(&self, other: &IoCounters) -> IoCounters {
+        IoCounters {
+            rchar: self.rchar.saturating_sub(other.rchar),
+            wchar: self.wchar.saturating_sub(other.wchar),
+            syscr: self.syscr.saturating_sub(other.syscr),
+            syscw: self.syscw.saturating_sub(other.syscw),
+            read_bytes: self.read_bytes.saturating_sub(other.read_bytes),
+            write_bytes: self.write_bytes.saturating_sub(other.write_bytes),
+            cancelled_write_bytes: self.cancelled_write_bytes.saturating_sub(other.cancelled_write_bytes),
+        }
+    }
+
+    fn add(&mut self, other: &IoCounters) {
+        self.rchar += other.rchar;
+        self.wchar += other.wchar;
+        self.syscr += other.syscr;
+        self.syscw += other.syscw;
+        self.read_bytes += other.read_bytes;
+        self.write_bytes += other.write_bytes;
+        self.cancelled_write_bytes += other.cancelled_write_bytes;
+    }
 }
 
-fn read_proc_io(pid: u32) -> Option<(u64, u64)> {
-    let path = format!("/proc/{}/io", pid);
-    let contents = fs::read_to_string(path).ok()?;
+/// Parses `/proc/[pid]/io`; read_bytes and write_bytes are required, the rest default to zero.
+fn parse_proc_io(contents: &str) -> Option<IoCounters> {
+    let mut counters = IoCounters::default();
     let mut read_bytes: Option<u64> = None;
     let mut write_bytes: Option<u64> = None;
 
     for line in contents.lines() {
-        if let Some(rest) = line.strip_prefix("read_bytes:") {
-            read_bytes = rest.trim().parse().ok();
-        } else if let Some(rest) = line.strip_prefix("write_bytes:") {
-            write_bytes = rest.trim().parse().ok();
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim().parse().ok();
+        match key {
+            "rchar" => counters.rchar = value.unwrap_or(0),
+            "wchar" => counters.wchar = value.unwrap_or(0),
+            "syscr" => counters.syscr = value.unwrap_or(0),
+            "syscw" => counters.syscw = value.unwrap_or(0),
+            "read_bytes" => read_bytes = value,
+            "write_bytes" => write_bytes = value,
+            "cancelled_write_bytes" => counters.cancelled_write_bytes = value.unwrap_or(0),
+            _ => {}
         }
     }
 
-    Some((read_bytes?, write_bytes?))
+    counters.read_bytes = read_bytes?;
+    counters.write_bytes = write_bytes?;
+    Some(counters)
+}
+
+fn read_proc_io(source: &Source, pid: u32) -> Option<IoCounters> {
+    parse_proc_io(&source.read_pid(pid, "io")?)
+}
+
+/// A process that exited during the window, with the I/O it did while we watched it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExitedIo {
+    pub pid: u32,
+    pub ppid: u32,
+    pub name: String,
+    pub read_bytes: u64,
+    pub write_bytes: u64,
+}
+
+/// One pid's rates over the time since it was last read.
+#[derive(Debug, Default)]
+pub struct IoRate {
+    pub read_bps: u64,
+    pub write_bps: u64,
+    /// Everything but `blkio_delay_percent`, which comes from `/proc/[pid]/stat`
+    pub detail: IoDetail,
+}
+
+/// What one `IoSampler::sample()` call measured.
+pub struct IoTick {
+    pub rates: HashMap<u32, IoRate>,
+    /// Tracked processes that disappeared since the last tick
+    pub exited: Vec<ExitedIo>,
+}
+
+/// The counters last read for one pid.
+struct Tracked {
+    counters: IoCounters,
+    /// Sampler clock at the read
+    at: f64,
+    /// Bytes charged to this pid since tracking started, reaped children's shares excluded
+    own: (u64, u64),
+    ppid: u32,
+    name: String,
+}
+
+/// Pids re-read every tick, re-ranked by their latest rates.
+const TOP_N: usize = 64;
+/// Other known pids re-read per tick, least recently read first, so a process that
+/// starts doing I/O late is promoted within a few ticks.
+const SCAN_SLICE: usize = 64;
+
+/// Computes per-process read/write rates from `/proc/[pid]/io` deltas between calls.
+///
+/// Reading every pid's io file on every tick is costly on busy hosts, so each tick reads the
+/// current top candidates, every pid born since the last tick and a rotating slice of the rest.
+/// When a process is reaped the kernel folds its counters into its parent's; those bytes are
+/// taken back out of the parent's next delta and the exited process is reported on its own.
+/// Children that lived and died between two ticks are never seen and stay charged to the parent.
+pub struct IoSampler {
+    candidates: Vec<u32>,
+    tracked: HashMap<u32, Tracked>,
+    /// Pids whose io file we may not read (other users' processes without root)
+    unreadable: HashSet<u32>,
+    /// Counters of reaped children, keyed by parent, not yet deducted from the parent
+    reaped: HashMap<u32, IoCounters>,
+    clock: f64,
 }
 
-fn all_pids() -> Vec<u32> {
-    let mut v = Vec::new();
-    if let Ok(entries) = fs::read_dir("/proc") {
-        for e in entries.flatten() {
-            if let Ok(name) = e.file_name().into_string() {
-                if let Ok(pid) = name.parse::<u32>() {
-                    v.push(pid);
+impl IoSampler {
+    /// Primes every readable pid and picks the initial candidates by lifetime I/O.
+    pub fn new(source: &Source) -> Self {
+        let mut totals: Vec<(u32, u64)> = Vec::new();
+        let mut tracked = HashMap::new();
+        let mut unreadable = HashSet::new();
+        for pid in source.pids() {
+            match read_proc_io(source, pid) {
+                Some(counters) => {
+                    totals.push((pid, counters.read_bytes.saturating_add(counters.write_bytes)));
+                    tracked.insert(pid, Tracked { counters, at: 0.0, own: (0, 0), ppid: 0, name: String::new() });
+                }
+                None => {
+                    unreadable.insert(pid);
                 }
             }
         }
+
+        totals.sort_by_key(|t| std::cmp::Reverse(t.1));
+        let candidates = totals.iter().take(TOP_N).map(|(pid, _)| *pid).collect();
+
+        IoSampler { candidates, tracked, unreadable, reaped: HashMap::new(), clock: 0.0 }
     }
-    v
-}
 
-fn read_name(pid: u32) -> Option<String> {
-    let path = format!("/proc/{}/comm", pid);
-    let s = fs::read_to_string(path).ok()?;
-    Some(s.trim().to_string())
-}
+    /// Returns rates for the pids read this tick, `elapsed_secs` after the previous call, and
+    /// the tracked processes that are no longer in `procs`.
+    pub fn sample(&mut self, source: &Source, elapsed_secs: f64, procs: &[ProcCpu]) -> IoTick {
+        let elapsed_secs = elapsed_secs.max(f64::EPSILON);
+        self.clock += elapsed_secs;
+        let alive: HashMap<u32, &ProcCpu> = procs.iter().map(|p| (p.pid, p)).collect();
 
-pub fn detect_sustained_high_io(
-    read_threshold: u64,
-    write_threshold: u64,
-    samples: usize,
-    min_hits: usize,
-    interval_secs: u64,
-) -> Option<IoSample> {
-    let mut hits: HashMap<u32, usize> = HashMap::new();
-    let mut last_values: HashMap<u32, (u64, u64)> = HashMap::new();
-    let mut last_seen: HashMap<u32, (u64, u64, String)> = HashMap::new();
-
-    // To avoid scanning every /proc pid on every sample (costly on systems with many processes),
-    // choose a candidate set of top-N pids by current total IO (read+write) and only sample those.
-    const TOP_N: usize = 64;
-
-    // pick initial candidates by a single scan
-    let mut candidates: Vec<u32> = Vec::new();
-    let mut totals: Vec<(u32, u64)> = Vec::new();
-    for pid in all_pids() {
-        if let Some((r, w)) = read_proc_io(pid) {
-            totals.push((pid, r.saturating_add(w)));
+        let mut exited = Vec::new();
+        let gone: Vec<u32> = self.tracked.keys().filter(|pid| !alive.contains_key(pid)).copied().collect();
+        for pid in gone {
+            let t = self.tracked.remove(&pid).unwrap();
+            self.reaped.entry(t.ppid).or_default().add(&t.counters);
+
+            let (read_bytes, write_bytes) = t.own;
+            if read_bytes > 0 || write_bytes > 0 {
+                exited.push(ExitedIo { pid, ppid: t.ppid, name: t.name, read_bytes, write_bytes });
+            }
         }
-    }
+        self.unreadable.retain(|pid| alive.contains_key(pid));
+        self.reaped.retain(|pid, _| alive.contains_key(pid));
 
-    // sort descending by total bytes and keep top N
-    totals.sort_by(|a, b| b.1.cmp(&a.1));
-    for (pid, _) in totals.iter().take(TOP_N) {
-        candidates.push(*pid);
-    }
+        for (pid, t) in self.tracked.iter_mut() {
+            let p = alive[pid];
+            t.ppid = p.ppid;
+            if t.name.is_empty() {
+                t.name = p.name.clone();
+            }
+        }
 
-    // Fallback: if no candidates found, scan all pids
-    if candidates.is_empty() {
-        candidates = all_pids();
-    }
+        let mut to_read: Vec<u32> = self.candidates.iter().filter(|pid| alive.contains_key(pid)).copied().collect();
+        to_read.extend(self.reaped.keys().copied());
+        to_read.extend(procs.iter().map(|p| p.pid).filter(|pid| !self.tracked.contains_key(pid) && !self.unreadable.contains(pid)));
+        let mut stale: Vec<(&u32, f64)> = self.tracked.iter().map(|(pid, t)| (pid, t.at)).collect();
+        stale.sort_by(|a, b| a.1.total_cmp(&b.1));
+        to_read.extend(stale.iter().take(SCAN_SLICE).map(|(pid, _)| **pid));
+        to_read.sort_unstable();
+        to_read.dedup();
+
+        let mut rates = HashMap::new();
+        for pid in to_read {
+            let Some(counters) = read_proc_io(source, pid) else {
+                self.tracked.remove(&pid);
+                self.unreadable.insert(pid);
+                continue;
+            };
 
-    for _ in 0..samples {
-        // snapshot t0 for candidates
-        last_values.clear();
-        for pid in &candidates {
-            if let Some((r, w)) = read_proc_io(*pid) {
-                last_values.insert(*pid, (r, w));
+            // a pid we have not read before was born since the last tick: all its I/O is new
+            let (mut delta, secs) = match self.tracked.get(&pid) {
+                Some(t) => (counters.saturating_sub(&t.counters), self.clock - t.at),
+                None => (counters, elapsed_secs),
+            };
+            if let Some(reaped) = self.reaped.remove(&pid) {
+                delta = delta.saturating_sub(&reaped);
             }
+
+            let secs = secs.max(f64::EPSILON);
+            let per_sec = |v: u64| (v as f64 / secs) as u64;
+            rates.insert(
+                pid,
+                IoRate {
+                    read_bps: per_sec(delta.read_bytes),
+                    write_bps: per_sec(delta.write_bytes),
+                    detail: IoDetail {
+                        rchar_bps: per_sec(delta.rchar),
+                        wchar_bps: per_sec(delta.wchar),
+                        syscr_per_sec: (delta.syscr as f64 / secs) as f32,
+                        syscw_per_sec: (delta.syscw as f64 / secs) as f32,
+                        cancelled_write_bps: per_sec(delta.cancelled_write_bytes),
+                        blkio_delay_percent: None,
+                    },
+                },
+            );
+
+            let (own, ppid, name) = match self.tracked.remove(&pid) {
+                Some(t) => (t.own, t.ppid, t.name),
+                None => ((0, 0), alive[&pid].ppid, alive[&pid].name.clone()),
+            };
+            let own = (own.0 + delta.read_bytes, own.1 + delta.write_bytes);
+            self.tracked.insert(pid, Tracked { counters, at: self.clock, own, ppid, name });
         }
 
-        sleep(Duration::from_secs(interval_secs));
+        let mut ranked: Vec<(u32, u64)> = rates
+            .iter()
+            .map(|(pid, rate)| (*pid, rate.read_bps.saturating_add(rate.write_bps)))
+            .filter(|(_, total)| *total > 0)
+            .collect();
+        ranked.sort_by_key(|t| std::cmp::Reverse(t.1));
+        self.candidates = ranked.iter().take(TOP_N).map(|(pid, _)| *pid).collect();
+
+        IoTick { rates, exited }
+    }
+}
+
+/// I/O of exited processes summed per pid over the window, largest first.
+pub fn exited_summary(snapshots: &[Snapshot], limit: usize) -> Vec<ExitedIo> {
+    let mut by_pid: HashMap<u32, ExitedIo> = HashMap::new();
+    for e in snapshots.iter().flat_map(|s| &s.exited_io) {
+        by_pid.insert(e.pid, e.clone());
+    }
+
+    let mut rows: Vec<ExitedIo> = by_pid.into_values().collect();
+    rows.sort_by_key(|e| std::cmp::Reverse(e.read_bytes.saturating_add(e.write_bytes)));
+    rows.truncate(limit);
+    rows
+}
 
-        // snapshot t1 and compute deltas for candidates
-        for pid in &candidates {
-            let pid = *pid;
-            if let (Some((r0, w0)), Some((r1, w1))) = (last_values.get(&pid), read_proc_io(pid)) {
-                let read_delta = r1.saturating_sub(*r0);
-                let write_delta = w1.saturating_sub(*w0);
+#[allow(clippy::collapsible_if)]
+pub fn detect_sustained_high_io(
+    snapshots: &[Snapshot],
+    read_threshold: u64,
+    write_threshold: u64,
+    min_hits: usize,
+) -> Option<IoSample> {
+    let mut hits: HashMap<u32, usize> = HashMap::new();
+    let mut last_seen: HashMap<u32, &ProcSample> = HashMap::new();
 
-                if read_delta > 0 || write_delta > 0 {
-                    let name = read_name(pid).unwrap_or_else(|| "?".to_string());
-                    last_seen.insert(pid, (read_delta, write_delta, name.clone()));
+    for snapshot in snapshots {
+        for p in &snapshot.procs {
+            if p.read_bps > 0 || p.write_bps > 0 {
+                last_seen.insert(p.pid, p);
 
-                    if read_delta >= read_threshold || write_delta >= write_threshold {
-                        *hits.entry(pid).or_insert(0) += 1;
-                    }
+                if p.read_bps >= read_threshold || p.write_bps >= write_threshold {
+                    *hits.entry(p.pid).or_insert(0) += 1;
                 }
             }
         }
     }
 
     // pick the pid with hits >= min_hits and highest combined bps
-    let mut best: Option<(u32, u64, u64, String)> = None;
+    let mut best: Option<&ProcSample> = None;
 
     for (pid, &count) in &hits {
         if count >= min_hits {
-            if let Some((r, w, name)) = last_seen.get(pid) {
-                match &best {
-                    Some((_, br, bw, _)) if (br + bw) >= (r + w) => {}
-                    _ => best = Some((*pid, *r, *w, name.clone())),
+            if let Some(&p) = last_seen.get(pid) {
+                match best {
+                    Some(b) if (b.read_bps + b.write_bps) >= (p.read_bps + p.write_bps) => {}
+                    _ => best = Some(p),
                 }
             }
         }
     }
 
-    best.map(|(pid, r, w, name)| IoSample {
-        pid,
-        name,
-        read_bps: r,
-        write_bps: w,
+    best.map(|p| IoSample {
+        pid: p.pid,
+        name: p.name.clone(),
+        read_bps: p.read_bps,
+        write_bps: p.write_bps,
+        detail: p.io,
+        pattern: p.io.pattern(p.write_bps),
+        cgroup: cgroup::resolve_opt(&p.cgroup),
+        members: p.members.clone(),
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::fake::FakeProc;
+
+    fn io_file(read: u64, write: u64) -> String {
+        io_file_with(0, 0, 0, 0, read, write)
+    }
+
+    fn io_file_with(rchar: u64, wchar: u64, syscr: u64, syscw: u64, read: u64, write: u64) -> String {
+        format!(
+            "rchar: {}\nwchar: {}\nsyscr: {}\nsyscw: {}\nread_bytes: {}\nwrite_bytes: {}\ncancelled_write_bytes: 0\n",
+            rchar, wchar, syscr, syscw, read, write
+        )
+    }
+
+    fn proc_cpu(pid: u32, ppid: u32, name: &str) -> ProcCpu {
+        ProcCpu { pid, ppid, pgrp: pid, sid: pid, name: name.to_string(), state: 'S', cpu: 0.0, blkio_delay_percent: None, threads: Vec::new() }
+    }
+
+    #[test]
+    fn sampler_reports_per_second_rates() {
+        let proc = FakeProc::new();
+        proc.file("20/io", &io_file(1000, 0));
+        let source = proc.source();
+        let mut sampler = IoSampler::new(&source);
+
+        proc.file("20/io", &io_file(5000, 8000));
+        let tick = sampler.sample(&source, 2.0, &[proc_cpu(20, 1, "cp")]);
+        let rate = &tick.rates[&20];
+        assert_eq!((rate.read_bps, rate.write_bps), (2000, 4000));
+    }
+
+    #[test]
+    fn rates_tell_tiny_syscalls_from_streaming_writes() {
+        let proc = FakeProc::new();
+        proc.file("20/io", &io_file(0, 0)).file("21/io", &io_file(0, 0));
+        let source = proc.source();
+        let mut sampler = IoSampler::new(&source);
+
+        // 20: 10k writes/s of 100 bytes; 21: 50 writes/s of 1 MiB
+        proc.file("20/io", &io_file_with(0, 2_000_000, 0, 20_000, 0, 0))
+            .file("21/io", &io_file_with(0, 100 << 20, 0, 100, 0, 100 << 20));
+        let tick = sampler.sample(&source, 2.0, &[proc_cpu(20, 1, "logger"), proc_cpu(21, 1, "dd")]);
+
+        let tiny = &tick.rates[&20];
+        assert_eq!((tiny.detail.wchar_bps, tiny.detail.syscw_per_sec), (1_000_000, 10_000.0));
+        assert!(tiny.detail.pattern(tiny.write_bps).unwrap().starts_with("many tiny syscalls"));
+
+        let streaming = &tick.rates[&21];
+        assert!(streaming.detail.pattern(streaming.write_bps).unwrap().starts_with("large streaming writes"));
+
+        let blocked = IoDetail { blkio_delay_percent: Some(80.0), ..streaming.detail };
+        assert!(blocked.pattern(streaming.write_bps).unwrap().starts_with("blocked waiting on disk"));
+    }
+
+    #[test]
+    fn tracks_new_pids_and_moves_reaped_io_off_the_parent() {
+        let proc = FakeProc::new();
+        proc.file("10/io", &io_file(0, 0));
+        let source = proc.source();
+        let mut sampler = IoSampler::new(&source);
+
+        // rsync starts mid-window: everything it did so far counts
+        proc.file("30/io", &io_file(0, 6000));
+        let tick = sampler.sample(&source, 2.0, &[proc_cpu(10, 1, "sh"), proc_cpu(30, 10, "rsync")]);
+        assert_eq!(tick.rates[&30].write_bps, 3000);
+
+        proc.file("30/io", &io_file(0, 10_000));
+        sampler.sample(&source, 2.0, &[proc_cpu(10, 1, "sh"), proc_cpu(30, 10, "rsync")]);
+
+        // reaped: the parent's counters absorb rsync's lifetime total
+        proc.file("10/io", &io_file(0, 12_000));
+        let tick = sampler.sample(&source, 2.0, &[proc_cpu(10, 1, "sh")]);
+        assert_eq!(tick.exited.len(), 1);
+        assert_eq!((tick.exited[0].name.as_str(), tick.exited[0].write_bytes), ("rsync", 10_000));
+        assert_eq!(tick.rates[&10].write_bps, 1000);
+    }
+}
@@ -0,0 +1,287 @@
+// Load average and task states: /proc/loadavg plus the processes in uninterruptible sleep
+// (D state), which raise the load without using any CPU.
+use crate::sampler::{ProcSample, Snapshot};
+use crate::source::Source;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+
+/// Load averages and task counts at one tick.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LoadSample {
+    pub load1: f32,
+    pub load5: f32,
+    pub load15: f32,
+    /// Runnable tasks (running or waiting for a CPU), threads included
+    pub runnable: u32,
+    /// Tasks in uninterruptible sleep, threads included (`procs_blocked` in `/proc/stat`)
+    pub uninterruptible: u32,
+}
+
+/// A task in D state at one tick, with where in the kernel it is waiting.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlockedTask {
+    /// Thread id; equal to the process id for a main thread
+    pub pid: u32,
+    /// Owning process when the task is one of its other threads
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tgid: Option<u32>,
+    pub name: String,
+    /// Kernel function the task sleeps in; empty when hidden (kernel.kptr_restrict or permissions)
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub wchan: String,
+    /// `/proc/[pid]/stack` frames, innermost first; only readable as root
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub stack: Vec<String>,
+}
+
+/// Tasks whose wchan and stack are read per tick; a storm of D-state tasks shares a few causes.
+const MAX_BLOCKED: usize = 10;
+/// Processes per ranking (block-I/O delay, disk I/O, CPU) whose threads are scanned for D state
+/// besides those already in D.
+const BLOCKED_CANDIDATES: usize = 5;
+
+/// (load1, load5, load15, runnable) from `/proc/loadavg`, e.g. "0.50 0.40 0.30 2/345 6789".
+fn parse_loadavg(contents: &str) -> Option<(f32, f32, f32, u32)> {
+    let f: Vec<&str> = contents.split_whitespace().collect();
+    let runnable = f.get(3)?.split('/').next()?.parse().ok()?;
+    Some((f.first()?.parse().ok()?, f.get(1)?.parse().ok()?, f.get(2)?.parse().ok()?, runnable))
+}
+
+/// `procs_blocked` from `/proc/stat`: tasks currently in uninterruptible sleep.
+fn parse_procs_blocked(contents: &str) -> Option<u32> {
+    contents.lines().find_map(|line| line.strip_prefix("procs_blocked ")?.trim().parse().ok())
+}
+
+/// (comm, state) from a `stat` file; comm may itself contain spaces or ')'.
+fn parse_comm_state(contents: &str) -> Option<(String, char)> {
+    let open = contents.find('(')?;
+    let close = contents.rfind(')')?;
+    let state = contents.get(close + 1..)?.split_whitespace().next()?.chars().next()?;
+    Some((contents.get(open + 1..close)?.to_string(), state))
+}
+
+/// Frames of `/proc/[pid]/stack` without their `[<0>]` address prefix.
+fn parse_stack(contents: &str) -> Vec<String> {
+    contents
+        .lines()
+        .map(|line| line.split_once("] ").map_or(line, |(_, frame)| frame).trim().to_string())
+        .filter(|frame| !frame.is_empty())
+        .collect()
+}
+
+/// Load averages, with D-state tasks from `procs_blocked` (else this tick's D-state processes).
+pub fn read_load(source: &Source, procs: &[ProcSample]) -> Option<LoadSample> {
+    let (load1, load5, load15, runnable) = parse_loadavg(&source.read("loadavg")?)?;
+    Some(LoadSample {
+        load1,
+        load5,
+        load15,
+        runnable,
+        uninterruptible: source
+            .read("stat")
+            .and_then(|c| parse_procs_blocked(&c))
+            .unwrap_or_else(|| procs.iter().filter(|p| p.state == 'D').count() as u32),
+    })
+}
+
+/// The D-state tasks of this tick, with their wchan and kernel stack.
+///
+/// A process's own state is its main thread's, so the worker threads that actually block (an
+/// NFS writer in a database, say) are found by scanning `task/*/stat`, only when `load` reports
+/// blocked tasks and only in the processes of `blocked_candidates`.
+pub fn read_blocked(source: &Source, procs: &[ProcSample], load: Option<&LoadSample>) -> Vec<BlockedTask> {
+    if load.is_some_and(|l| l.uninterruptible == 0) {
+        return Vec::new();
+    }
+
+    let mut blocked = Vec::new();
+    for p in blocked_candidates(procs) {
+        let Ok(dir) = fs::read_dir(source.pid_path(p.pid, "task")) else {
+            continue;
+        };
+        let mut tids: Vec<u32> = dir.flatten().filter_map(|e| e.file_name().to_str()?.parse().ok()).collect();
+        tids.sort_unstable();
+
+        for tid in tids {
+            if blocked.len() == MAX_BLOCKED {
+                return blocked;
+            }
+            let read = |file: &str| source.read_pid(p.pid, &format!("task/{}/{}", tid, file));
+            let Some((name, 'D')) = read("stat").and_then(|c| parse_comm_state(&c)) else {
+                continue;
+            };
+            blocked.push(BlockedTask {
+                pid: tid,
+                tgid: (tid != p.pid).then_some(p.pid),
+                name,
+                wchan: read("wchan").map(|w| w.trim().to_string()).filter(|w| w != "0").unwrap_or_default(),
+                stack: read("stack").map(|s| parse_stack(&s)).unwrap_or_default(),
+            });
+        }
+    }
+    blocked
+}
+
+/// Processes in D state, then the top few by block-I/O delay, disk I/O and CPU: where blocked
+/// threads hide without walking every thread of every process.
+fn blocked_candidates(procs: &[ProcSample]) -> Vec<&ProcSample> {
+    let mut candidates: Vec<&ProcSample> = procs.iter().filter(|p| p.state == 'D').collect();
+    let rankings: [fn(&ProcSample) -> f32; 3] =
+        [|p| p.io.blkio_delay_percent.unwrap_or(0.0), |p| (p.read_bps + p.write_bps) as f32, |p| p.cpu];
+    for key in rankings {
+        let mut ranked: Vec<&ProcSample> = procs.iter().filter(|p| key(p) > 0.0).collect();
+        ranked.sort_by(|a, b| key(b).total_cmp(&key(a)));
+        for p in ranked.into_iter().take(BLOCKED_CANDIDATES) {
+            if !candidates.iter().any(|c| c.pid == p.pid) {
+                candidates.push(p);
+            }
+        }
+    }
+    candidates
+}
+
+/// A process found in D state in most ticks.
+#[derive(Debug, Serialize)]
+pub struct StuckTask {
+    #[serde(flatten)]
+    pub task: BlockedTask,
+    /// Ticks in which it was in D state
+    pub hits: usize,
+}
+
+/// Processes stuck in uninterruptible sleep, with the load they cause.
+#[derive(Debug, Serialize)]
+pub struct StuckTasks {
+    pub tasks: Vec<StuckTask>,
+    /// Latest 1-minute load average divided by the number of CPUs
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub load1_per_cpu: Option<f32>,
+    /// Average busy percent of all CPUs over the window
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cpu_busy: Option<f32>,
+}
+
+/// Number of CPUs from a snapshot's per-core states (the first entry is the aggregate).
+pub fn cpus(snapshot: &Snapshot) -> Option<usize> {
+    let states = snapshot.cpu_states.len();
+    (states > 0).then(|| (states - 1).max(1))
+}
+
+/// Flags processes in D state in at least `min_hits` ticks, longest-stuck first.
+pub fn detect_stuck_tasks(snapshots: &[Snapshot], min_hits: usize) -> Option<StuckTasks> {
+    let mut hits: HashMap<u32, (usize, &BlockedTask)> = HashMap::new();
+    for task in snapshots.iter().flat_map(|s| &s.blocked) {
+        let entry = hits.entry(task.pid).or_insert((0, task));
+        entry.0 += 1;
+        // keep the latest sighting that has a stack, if any
+        if !task.stack.is_empty() || entry.1.stack.is_empty() {
+            entry.1 = task;
+        }
+    }
+
+    let mut tasks: Vec<StuckTask> = hits
+        .into_values()
+        .filter(|(n, _)| *n >= min_hits)
+        .map(|(n, task)| StuckTask { task: task.clone(), hits: n })
+        .collect();
+    if tasks.is_empty() {
+        return None;
+    }
+    tasks.sort_by(|a, b| b.hits.cmp(&a.hits).then(a.task.pid.cmp(&b.task.pid)));
+    tasks.truncate(MAX_BLOCKED);
+
+    let last = snapshots.last()?;
+    let busy: Vec<f32> = snapshots.iter().filter_map(|s| s.cpu_states.first()).map(|c| c.busy()).collect();
+    Some(StuckTasks {
+        tasks,
+        load1_per_cpu: last.load.as_ref().zip(cpus(last)).map(|(l, n)| l.load1 / n as f32),
+        cpu_busy: (!busy.is_empty()).then(|| busy.iter().sum::<f32>() / busy.len() as f32),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sampler::proc_sample;
+    use crate::source::fake::FakeProc;
+
+    fn blocked(pid: u32, stack: &[&str]) -> BlockedTask {
+        BlockedTask {
+            pid,
+            tgid: None,
+            name: format!("task{}", pid),
+            wchan: String::new(),
+            stack: stack.iter().map(|f| f.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn parses_loadavg_and_kernel_stack() {
+        assert_eq!(parse_loadavg("8.12 4.50 2.01 3/512 48211\n"), Some((8.12, 4.5, 2.01, 3)));
+        assert_eq!(
+            parse_stack("[<0>] io_schedule+0x16/0x40\n[<0>] folio_wait_bit_common+0x13f/0x340\n"),
+            vec!["io_schedule+0x16/0x40", "folio_wait_bit_common+0x13f/0x340"]
+        );
+    }
+
+    #[test]
+    fn blocked_worker_threads_are_found_under_their_process() {
+        let proc = FakeProc::new();
+        proc.file("loadavg", "4.00 2.00 1.00 1/200 999\n")
+            .file("stat", "cpu  1 0 1 1 0 0 0 0 0 0\nprocs_running 1\nprocs_blocked 1\n")
+            .file("40/task/40/stat", "40 (postgres) S 1 40 40 0 -1 0\n")
+            .file("40/task/41/stat", "41 (bg writer) D 1 40 40 0 -1 0\n")
+            .file("40/task/41/wchan", "nfs_wait_on_request\n")
+            .file("40/task/41/stack", "[<0>] nfs_wait_on_request+0x1/0x2\n");
+        let source = proc.source();
+        let mut postgres = proc_sample(40, 1, "postgres");
+        postgres.io.blkio_delay_percent = Some(30.0);
+        let procs = vec![postgres];
+
+        let load = read_load(&source, &procs).unwrap();
+        assert_eq!(load.uninterruptible, 1);
+
+        let tasks = read_blocked(&source, &procs, Some(&load));
+        assert_eq!(tasks.len(), 1);
+        assert_eq!((tasks[0].pid, tasks[0].tgid, tasks[0].name.as_str()), (41, Some(40), "bg writer"));
+        assert_eq!(tasks[0].wchan, "nfs_wait_on_request");
+        assert_eq!(tasks[0].stack, vec!["nfs_wait_on_request+0x1/0x2"]);
+
+        let idle = LoadSample { uninterruptible: 0, ..load };
+        assert!(read_blocked(&source, &procs, Some(&idle)).is_empty());
+    }
+
+    #[test]
+    fn only_blocked_and_busy_processes_are_scanned_for_blocked_threads() {
+        let mut procs: Vec<ProcSample> =
+            (1..=20).map(|pid| proc_sample(pid, 1, "idle")).collect();
+        procs[0].state = 'D';
+        procs[1].io.blkio_delay_percent = Some(40.0);
+        procs[2].write_bps = 50_000_000;
+        procs[3].cpu = 90.0;
+
+        let candidates: Vec<u32> = blocked_candidates(&procs).iter().map(|p| p.pid).collect();
+        assert_eq!(&candidates[..4], &[1, 2, 3, 4]);
+        assert_eq!(candidates.len(), 4);
+    }
+
+    #[test]
+    fn stuck_tasks_need_min_hits_and_keep_the_sighting_with_a_stack() {
+        let snapshots = vec![
+            Snapshot { blocked: vec![blocked(7, &["io_schedule"]), blocked(8, &[])], ..Default::default() },
+            Snapshot { blocked: vec![blocked(7, &[])], ..Default::default() },
+            Snapshot { blocked: vec![blocked(7, &[]), blocked(8, &[])], ..Default::default() },
+        ];
+
+        let stuck = detect_stuck_tasks(&snapshots, 3).unwrap();
+        assert_eq!(stuck.tasks.len(), 1);
+        assert_eq!((stuck.tasks[0].task.pid, stuck.tasks[0].hits), (7, 3));
+        assert_eq!(stuck.tasks[0].task.stack, vec!["io_schedule"]);
+
+        let stuck = detect_stuck_tasks(&snapshots, 2).unwrap();
+        assert_eq!(stuck.tasks.iter().map(|t| (t.task.pid, t.hits)).collect::<Vec<_>>(), vec![(7, 3), (8, 2)]);
+
+        assert!(detect_stuck_tasks(&snapshots, 4).is_none());
+    }
+}
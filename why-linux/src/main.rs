@@ -1,85 +1,36 @@
+mod analysis;
+mod blockdev;
+mod cgroup;
 mod cpu;
 mod explain;
+mod group;
 mod mem;
+mod net;
+mod oom;
+mod psi;
 mod disk;
+mod diskscan;
 mod io;
+mod load;
+mod record;
 mod report;
+mod sampler;
+mod sched;
+mod serve;
+mod source;
+mod thermal;
+mod vmstat;
+mod watch;
 
-use clap::Parser;
-use serde::Serialize;
+use clap::{Parser, Subcommand};
 use serde_json::json;
-use std::collections::HashMap;
 
-use cpu::detect_sustained_high_cpu;
+use analysis::{Findings, Thresholds, top_offenders};
 use explain::explain_process;
-use mem::detect_sustained_high_mem;
+use group::{GroupBy, Grouper};
 use report::{TimelineSample, write_html_report};
-
-#[derive(Clone)]
-struct OffenderStats {
-    name: String,
-    pid: u32,
-    sum: f32,
-    max: f32,
-    samples: u32,
-}
-
-#[derive(Serialize)]
-struct OffenderRow {
-    name: String,
-    pid: u32,
-    sum: f32,
-    avg: f32,
-    max: f32,
-}
-
-fn update_offender(
-    map: &mut HashMap<u32, OffenderStats>,
-    pid: u32,
-    name: &str,
-    value: f32,
-) {
-    let entry = map.entry(pid).or_insert_with(|| OffenderStats {
-        name: name.to_string(),
-        pid,
-        sum: 0.0,
-        max: 0.0,
-        samples: 0,
-    });
-    entry.name = name.to_string();
-    entry.sum += value;
-    entry.max = entry.max.max(value);
-    entry.samples += 1;
-}
-
-fn avg_of(values: &[f32]) -> f32 {
-    if values.is_empty() {
-        0.0
-    } else {
-        values.iter().sum::<f32>() / values.len() as f32
-    }
-}
-
-fn max_of(values: &[f32]) -> f32 {
-    values.iter().cloned().fold(0.0, f32::max)
-}
-
-fn top_offenders(map: &HashMap<u32, OffenderStats>, limit: usize) -> Vec<OffenderRow> {
-    let mut rows: Vec<OffenderRow> = map
-        .values()
-        .map(|o| OffenderRow {
-            name: o.name.clone(),
-            pid: o.pid,
-            sum: o.sum,
-            avg: if o.samples == 0 { 0.0 } else { o.sum / o.samples as f32 },
-            max: o.max,
-        })
-        .collect();
-
-    rows.sort_by(|a, b| b.sum.partial_cmp(&a.sum).unwrap_or(std::cmp::Ordering::Equal));
-    rows.truncate(limit);
-    rows
-}
+use sampler::{Sampler, Snapshot};
+use source::Source;
 
 #[derive(Parser, Debug)]
 #[command(author, version, about = "Monitor sustained CPU and memory usage")]
@@ -100,11 +51,36 @@ struct Args {
     #[arg(long, default_value_t = 80.0)]
     mem_threshold: f32,
 
+    /// Steady per-process memory growth (MB/min) reported as a likely leak
+    #[arg(long, default_value_t = 1.0)]
+    mem_growth_threshold: f32,
+
 
     /// Disk usage percent threshold to consider high
     #[arg(long, default_value_t = 90.0)]
     disk_threshold: f32,
 
+    /// Report mounts projected to fill up within this many hours at their current rate
+    #[arg(long, default_value_t = 6.0)]
+    disk_full_hours: f32,
+
+    /// When a mount is full or filling up, walk it to find the largest directories and files,
+    /// known culprits (journal, package caches, container storage, core dumps) and deleted-but-open files
+    #[arg(long)]
+    disk_scan: bool,
+
+    /// Inode used percent to consider exhausted
+    #[arg(long, default_value_t = 90.0)]
+    inode_threshold: f32,
+
+    /// Only sample these mounts: filesystem types (e.g. ext4) or mount points (e.g. /home), comma-separated
+    #[arg(long, value_delimiter = ',')]
+    disk_include: Vec<String>,
+
+    /// Skip these mounts: filesystem types (e.g. tmpfs,overlay,squashfs) or mount points, comma-separated
+    #[arg(long, value_delimiter = ',')]
+    disk_exclude: Vec<String>,
+
 
     /// Read bytes/sec threshold to consider high (bytes/sec)
     #[arg(long, default_value_t = 5_000_000)]
@@ -114,6 +90,45 @@ struct Args {
     #[arg(long, default_value_t = 5_000_000)]
     io_write_threshold: u64,
 
+    /// Block-device %util to consider saturated
+    #[arg(long, default_value_t = 90.0)]
+    device_util_threshold: f32,
+
+    /// Per-interface receive bytes/sec to consider high, for interfaces of unknown link speed
+    #[arg(long, default_value_t = 12_500_000)]
+    net_rx_threshold: u64,
+
+    /// Per-interface transmit bytes/sec to consider high, for interfaces of unknown link speed
+    #[arg(long, default_value_t = 12_500_000)]
+    net_tx_threshold: u64,
+
+    /// Percent of an interface's link speed to consider saturated
+    #[arg(long, default_value_t = 80.0)]
+    net_util_threshold: f32,
+
+    /// Also check virtual interfaces (veth, bridges, tunnels) for high throughput
+    #[arg(long)]
+    net_virtual: bool,
+
+    /// Percent of sent TCP segments retransmitted to consider high
+    #[arg(long, default_value_t = 2.0)]
+    tcp_retrans_threshold: f32,
+
+    /// Open TCP connections (any state but LISTEN) to consider a connection storm
+    #[arg(long, default_value_t = 10_000)]
+    tcp_connections_threshold: u32,
+
+    /// Run-queue wait time, as a multiple of run time, to consider scheduler contention
+    #[arg(long, default_value_t = 1.0)]
+    sched_wait_ratio: f32,
+
+    /// Busy-CPU clock below this percent of the maximum frequency to consider throttled
+    #[arg(long, default_value_t = 70.0)]
+    throttle_freq_threshold: f32,
+
+    /// Thermal zone temperature (°C) to consider too hot
+    #[arg(long, default_value_t = 90.0)]
+    temp_threshold: f32,
 
     /// Output machine-readable JSON
     #[arg(short, long)]
@@ -122,164 +137,768 @@ struct Args {
     /// Write an HTML timeline report to the given path (optional)
     #[arg(long)]
     report: Option<String>,
+
+    /// PSI "some" avg10 percent considered high
+    #[arg(long, default_value_t = 25.0)]
+    psi_some_avg10: f32,
+
+    /// PSI "some" avg60 percent considered high
+    #[arg(long, default_value_t = 10.0)]
+    psi_some_avg60: f32,
+
+    /// PSI "full" avg10 percent considered high
+    #[arg(long, default_value_t = 10.0)]
+    psi_full_avg10: f32,
+
+    /// PSI "full" avg60 percent considered high
+    #[arg(long, default_value_t = 5.0)]
+    psi_full_avg60: f32,
+
+    /// Per-process memory figure used to rank memory offenders
+    #[arg(long, value_enum, default_value_t = mem::MemMetric::Pss)]
+    mem_metric: mem::MemMetric,
+
+    /// Aggregate CPU, memory and IO per process tree, name, cgroup or user instead of per process
+    #[arg(long, value_enum, default_value_t = GroupBy::Pid)]
+    group_by: GroupBy,
+
+    /// Sample indefinitely over a rolling --duration window, reporting when detections start or clear
+    #[arg(long)]
+    watch: bool,
+
+    /// Record every raw snapshot to the given file (newline-delimited JSON) for `replay`
+    #[arg(long)]
+    record: Option<String>,
+
+    /// Also rank leaf cgroups (systemd units, containers) by CPU, memory and IO from cgroup v2 accounting
+    #[arg(long)]
+    rank_cgroups: bool,
+
+    /// Also sample /proc/[pid]/task/*/stat of the busiest processes and report the CPU offender's N hottest threads
+    #[arg(long, value_name = "N")]
+    threads: Option<usize>,
+
+    /// Read OOM-kill history from this kernel log (dmesg or /dev/kmsg format) instead of /dev/kmsg
+    #[arg(long)]
+    kmsg_file: Option<String>,
+
+    /// Read process and system state from this procfs mount (e.g. /host/proc from a sidecar)
+    #[arg(long, default_value = source::DEFAULT_PROC_ROOT)]
+    proc_root: String,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Serve OpenMetrics on /metrics, sampling continuously over a rolling --duration window
+    Serve {
+        /// Address to listen on
+        #[arg(long, default_value = "127.0.0.1:9464")]
+        listen: String,
+    },
+    /// Rerun detection, offender ranking and the report from a --record file
+    Replay {
+        /// Recording written by --record
+        file: String,
+    },
 }
 
 fn main() {
     let args = Args::parse();
+    // our pid in the monitored proc mount, not std::process::id(), which differs in a container
+    let self_pid = Source::new(&args.proc_root).self_pid();
 
-    println!("Monitoring CPU + memory usage...\n");
-    let self_pid = std::process::id();
-
-    // Extract needed args so we can move them into threads.
     let duration = args.duration.max(1);
     let interval = args.interval.max(1);
     let samples = (duration / interval).max(1) as usize;
     let min_hits = (samples / 2).max(1);
 
-    let cpu_threshold = args.cpu_threshold;
-    let mem_threshold = args.mem_threshold;
-    let disk_threshold = args.disk_threshold;
-    let io_read_threshold = args.io_read_threshold;
-    let io_write_threshold = args.io_write_threshold;
+    let thresholds = Thresholds {
+        cpu: args.cpu_threshold,
+        mem: args.mem_threshold,
+        mem_growth: args.mem_growth_threshold,
+        mem_metric: args.mem_metric,
+        disk: args.disk_threshold,
+        inodes: args.inode_threshold,
+        disk_full_hours: args.disk_full_hours,
+        io_read: args.io_read_threshold,
+        io_write: args.io_write_threshold,
+        device_util: args.device_util_threshold,
+        net: net::NetThresholds {
+            rx_bps: args.net_rx_threshold,
+            tx_bps: args.net_tx_threshold,
+            util_percent: args.net_util_threshold,
+            include_virtual: args.net_virtual,
+            retrans_percent: args.tcp_retrans_threshold,
+            connections: args.tcp_connections_threshold,
+        },
+        sched_wait_ratio: args.sched_wait_ratio,
+        throttle_freq: args.throttle_freq_threshold,
+        temp: args.temp_threshold,
+        psi: psi::PsiThresholds {
+            some_avg10: args.psi_some_avg10,
+            some_avg60: args.psi_some_avg60,
+            full_avg10: args.psi_full_avg10,
+            full_avg60: args.psi_full_avg60,
+        },
+        min_hits,
+    };
 
-    // Start parallel detectors (they still sample internally) and also collect per-second
-    // timeline samples for the maximum of the configured sample windows so the report has data.
-    let cpu_handle = std::thread::spawn(move || {
-        detect_sustained_high_cpu(cpu_threshold, samples, min_hits, interval, Some(self_pid))
-    });
+    let create_recorder = || match args.record.as_deref().map(record::Recorder::create).transpose() {
+        Ok(r) => r,
+        Err(e) => {
+            eprintln!("Failed to create recording: {}", e);
+            std::process::exit(1);
+        }
+    };
 
-    let mem_handle = std::thread::spawn(move || {
-        detect_sustained_high_mem(mem_threshold, samples, min_hits, interval, Some(self_pid))
-    });
+    if let Some(Command::Serve { listen }) = args.command.as_ref() {
+        println!("Serving OpenMetrics on http://{}/metrics", listen);
+        let sampler = new_sampler(&args, self_pid);
+        let grouper = Grouper::new(args.group_by, &Source::new(&args.proc_root));
+        if let Err(e) = serve::run(listen, sampler, grouper, interval, samples, thresholds, create_recorder()) {
+            eprintln!("Failed to serve on {}: {}", listen, e);
+            std::process::exit(1);
+        }
+        return;
+    }
 
-    let disk_handle = std::thread::spawn(move || {
-        disk::detect_sustained_high_disk(disk_threshold, samples, min_hits, interval)
-    });
+    if let Some(Command::Replay { file }) = args.command.as_ref() {
+        if args.record.is_some() {
+            eprintln!("--record cannot be combined with replay; the recording already exists");
+            std::process::exit(1);
+        }
+        let mut snapshots = match record::load(file) {
+            Ok(s) => s,
+            Err(e) => {
+                eprintln!("Failed to read recording {}: {}", file, e);
+                std::process::exit(1);
+            }
+        };
 
-    let io_handle = std::thread::spawn(move || {
-        io::detect_sustained_high_io(io_read_threshold, io_write_threshold, samples, min_hits, interval)
-    });
+        mem::apply_metric(&mut snapshots, args.mem_metric);
 
-    // collect per-second samples for timeline (duration = max configured samples)
-    let mut timeline: Vec<TimelineSample> = Vec::with_capacity(samples);
-    let mut cpu_values: Vec<f32> = Vec::with_capacity(samples);
-    let mut mem_values: Vec<f32> = Vec::with_capacity(samples);
-    let mut mem_used_values: Vec<f32> = Vec::with_capacity(samples);
-    let mut disk_values: Vec<f32> = Vec::with_capacity(samples);
-    let mut cpu_offenders: HashMap<u32, OffenderStats> = HashMap::new();
-    let mut mem_offenders: HashMap<u32, OffenderStats> = HashMap::new();
+        // the recording defines the window, so the sustained rule follows its length
+        let thresholds = Thresholds {
+            min_hits: (snapshots.len() / 2).max(1),
+            ..thresholds
+        };
+        let span = match (snapshots.first(), snapshots.get(1), snapshots.last()) {
+            (Some(first), Some(second), Some(last)) => {
+                last.ts.saturating_sub(first.ts) + second.ts.saturating_sub(first.ts)
+            }
+            _ => snapshots.len() as u64,
+        };
 
-    for _ in 0..samples {
-        let ts = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
-        let cpu = cpu::get_top_cpu_excluding(Some(self_pid));
-        let mem = mem::get_top_mem_excluding(Some(self_pid));
-        let disk = disk::get_top_mount_usage();
-
-        if let Some(ref sample) = cpu {
-            cpu_values.push(sample.cpu);
-            update_offender(&mut cpu_offenders, sample.pid, &sample.name, sample.cpu);
-        } else {
-            cpu_values.push(0.0);
+        if !args.json {
+            println!("Replaying {} samples from {}...\n", snapshots.len(), file);
         }
+        // the live kernel log says nothing about a recording, so only read an explicit file
+        let kills = args.kmsg_file.as_deref().map(oom::read_kills).unwrap_or_default();
+        output(&args, &snapshots, &thresholds, span, &kills);
+        return;
+    }
 
-        if let Some(ref sample) = mem {
-            mem_values.push(sample.mem);
-            mem_used_values.push(sample.used_percent);
-            update_offender(&mut mem_offenders, sample.pid, &sample.name, sample.mem);
-        } else {
-            mem_values.push(0.0);
-            mem_used_values.push(0.0);
-        }
+    let mut recorder = create_recorder();
 
-        if let Some(ref sample) = disk {
-            disk_values.push(sample.used_percent);
-        } else {
-            disk_values.push(0.0);
+    if args.watch {
+        if !args.json {
+            println!("Watching over a rolling {}s window (Ctrl-C to stop)...\n", duration);
         }
+        let mut sampler = new_sampler(&args, self_pid);
+        let grouper = Grouper::new(args.group_by, &Source::new(&args.proc_root));
+        watch::run(&mut sampler, &grouper, interval, samples, &thresholds, args.json, recorder.as_mut());
+        return;
+    }
+
+    println!("Monitoring CPU + memory usage...\n");
 
-        timeline.push(TimelineSample { ts, cpu, mem, disk });
+    // One snapshot per tick feeds the detectors, the offender tables and the timeline.
+    let mut sampler = new_sampler(&args, self_pid);
+    let mut snapshots: Vec<Snapshot> = Vec::with_capacity(samples);
+
+    for _ in 0..samples {
         std::thread::sleep(std::time::Duration::from_secs(interval));
+        let snapshot = sampler.tick();
+        if let Some(r) = recorder.as_mut()
+            && let Err(e) = r.write(&snapshot)
+        {
+            eprintln!("Failed to record sample: {}", e);
+        }
+        snapshots.push(snapshot);
     }
 
-    let summary = json!({
-        "cpu": {
-            "avg": avg_of(&cpu_values),
-            "max": max_of(&cpu_values),
-        },
-        "mem": {
-            "avg": avg_of(&mem_values),
-            "max": max_of(&mem_values),
-            "system_avg": avg_of(&mem_used_values),
-            "system_max": max_of(&mem_used_values),
-        },
-        "disk": {
-            "avg": avg_of(&disk_values),
-            "max": max_of(&disk_values),
-        }
-    });
+    let kills = oom::read_kills(args.kmsg_file.as_deref().unwrap_or(oom::DEFAULT_KMSG));
+    output(&args, &snapshots, &thresholds, duration, &kills);
+}
 
-    let offenders = json!({
-        "cpu": top_offenders(&cpu_offenders, 5),
-        "mem": top_offenders(&mem_offenders, 5),
-    });
+/// Sampler for the live modes, following the kernel log for OOM kills, with cgroup accounting
+/// when `--rank-cgroups` is set.
+fn new_sampler(args: &Args, self_pid: Option<u32>) -> Sampler {
+    let sampler = Sampler::new(Source::new(&args.proc_root), self_pid)
+        .with_mem_metric(args.mem_metric)
+        .with_kills(args.kmsg_file.as_deref().unwrap_or(oom::DEFAULT_KMSG))
+        .with_disk_filter(disk::DiskFilter {
+            include: args.disk_include.clone(),
+            exclude: args.disk_exclude.clone(),
+        });
+    let sampler = match args.threads {
+        Some(n) if n > 0 => sampler.with_threads(n),
+        _ => sampler,
+    };
+    if args.rank_cgroups { sampler.with_cgroups() } else { sampler }
+}
+
+/// Prints (or writes as JSON/HTML) the findings, summary and offenders for a window.
+fn output(args: &Args, snapshots: &[Snapshot], thresholds: &Thresholds, duration: u64, kills: &[oom::OomKill]) {
+    let grouper = Grouper::new(args.group_by, &Source::new(&args.proc_root));
+    let snapshots: Vec<Snapshot> = snapshots.iter().map(|s| grouper.apply(s)).collect();
 
-    // Join results (if a thread panics we treat as no result)
-    let cpu_result = cpu_handle.join().ok().flatten();
-    let mem_result = mem_handle.join().ok().flatten();
-    let disk_result = disk_handle.join().ok().flatten();
-    let io_result = io_handle.join().ok().flatten();
+    let findings = analysis::detect(&snapshots, thresholds);
+    let timeline: Vec<TimelineSample> = snapshots.iter().map(TimelineSample::from).collect();
+    let scan_mount = findings.disk.as_ref().map(|d| &d.mount).or(findings.disk_fill.as_ref().map(|f| &f.mount));
+    let scan = scan_mount
+        .filter(|_| args.disk_scan)
+        .and_then(|mount| diskscan::scan(&Source::new(&args.proc_root), mount));
+    let mut summary = analysis::summary(&snapshots);
+    summary["mem"]["oom"]["kills"] = json!(kills);
+    let offender_stats = analysis::offenders(&snapshots);
+
+    let cgroup_top = analysis::top_cgroups(&snapshots, 5);
+
+    let mut offenders = json!({
+        "cpu": top_offenders(&offender_stats.cpu, 5),
+        "mem": top_offenders(&offender_stats.mem, 5),
+    });
+    if !cgroup_top.is_empty() {
+        offenders["cgroups"] = json!(cgroup_top);
+    }
 
     if args.json {
-        let mut out = json!({});
+        let out = findings_json(&findings, &summary, &offenders, scan.as_ref());
 
-        if let Some(c) = cpu_result {
-            if let serde_json::Value::Object(ref mut map) = out {
-                map.insert("cpu".to_string(), serde_json::to_value(&c).unwrap());
-            }
+        println!("{}", serde_json::to_string_pretty(&out).unwrap());
+        if let Some(path) = args.report.as_ref() {
+            // include JSON findings in the report
+            let summary_json = serde_json::to_string_pretty(&out).unwrap();
+            let _ = write_html_report(path, &timeline, &summary_json);
         }
 
-        if let Some(m) = mem_result {
-            if let serde_json::Value::Object(ref mut map) = out {
-                map.insert("mem".to_string(), serde_json::to_value(&m).unwrap());
-            }
+        return;
+    }
+
+    print_findings(&findings, &summary);
+    if let Some(scan) = &scan {
+        print_disk_scan(scan);
+    }
+
+    println!("\nSummary ({}s):", duration);
+    println!(
+        "CPU avg {:.1}% | max {:.1}%",
+        summary["cpu"]["avg"].as_f64().unwrap_or(0.0),
+        summary["cpu"]["max"].as_f64().unwrap_or(0.0)
+    );
+    print_cpu_states(&summary["cpu"]);
+    print_load(&summary["cpu"]["load"]);
+    print_thermal(&summary["cpu"]["thermal"]);
+    print_psi(&summary["psi"]);
+    println!(
+        "Mem avg {:.1}% | max {:.1}% | system avg {:.1}% | system max {:.1}%",
+        summary["mem"]["avg"].as_f64().unwrap_or(0.0),
+        summary["mem"]["max"].as_f64().unwrap_or(0.0),
+        summary["mem"]["system_avg"].as_f64().unwrap_or(0.0),
+        summary["mem"]["system_max"].as_f64().unwrap_or(0.0)
+    );
+    print_mem_composition(&summary["mem"]);
+    print_oom(&summary["mem"]["oom"]);
+    println!(
+        "Disk avg {:.1}% | max {:.1}%",
+        summary["disk"]["avg"].as_f64().unwrap_or(0.0),
+        summary["disk"]["max"].as_f64().unwrap_or(0.0)
+    );
+    print_mounts(&summary["disk"]["mounts"]);
+    print_devices(&summary["devices"]);
+    print_net(&summary["net"]);
+    print_exited_io(&summary["io"]["exited"]);
+
+    let cpu_top = top_offenders(&offender_stats.cpu, 3);
+    if !cpu_top.is_empty() {
+        println!("\nTop CPU offenders:");
+        for row in cpu_top {
+            println!(
+                "• {} (PID {}){} – sum {:.1} | avg {:.1} | max {:.1}",
+                row.name,
+                row.pid,
+                cgroup::suffix(row.cgroup.as_ref()),
+                row.sum,
+                row.avg,
+                row.max
+            );
         }
+    }
 
-        if let Some(d) = disk_result {
-            if let serde_json::Value::Object(ref mut map) = out {
-                map.insert("disk".to_string(), serde_json::to_value(&d).unwrap());
-            }
+    let mem_top = top_offenders(&offender_stats.mem, 3);
+    if !mem_top.is_empty() {
+        println!("\nTop memory offenders:");
+        for row in mem_top {
+            println!(
+                "• {} (PID {}){} – sum {:.1} | avg {:.1} | max {:.1}",
+                row.name,
+                row.pid,
+                cgroup::suffix(row.cgroup.as_ref()),
+                row.sum,
+                row.avg,
+                row.max
+            );
         }
+    }
 
-        if let Some(io) = io_result {
-            if let serde_json::Value::Object(ref mut map) = out {
-                map.insert("io".to_string(), serde_json::to_value(&io).unwrap());
+    if !cgroup_top.is_empty() {
+        println!("\nTop cgroups:");
+        for row in &cgroup_top {
+            let pressure: Vec<String> =
+                row.pressure_max.iter().map(|(name, peak)| format!("{} {:.1}%", name, peak)).collect();
+            println!(
+                "• {} – cpu avg {:.1}% | max {:.1}% | mem max {} MiB | read {} B/s | write {} B/s{}",
+                row.cgroup.label().unwrap_or_else(|| row.cgroup.path.clone()),
+                row.cpu_avg,
+                row.cpu_max,
+                row.mem_max_bytes / (1024 * 1024),
+                row.read_bps_avg,
+                row.write_bps_avg,
+                if pressure.is_empty() { String::new() } else { format!(" | pressure {}", pressure.join(", ")) }
+            );
+        }
+    }
+
+    if let Some(path) = args.report.as_ref() {
+        let out = findings_json(&findings, &summary, &offenders, scan.as_ref());
+
+        let summary_json = serde_json::to_string_pretty(&out).unwrap();
+        match write_html_report(path, &timeline, &summary_json) {
+            Ok(()) => println!("Wrote HTML report to {}", path),
+            Err(e) => eprintln!("Failed to write report: {}", e),
+        }
+    }
+}
+
+/// System-wide CPU state split and the busiest cores, from the summary's `cpu` section.
+fn print_cpu_states(cpu: &serde_json::Value) {
+    let states = &cpu["states"];
+    if states.get("busy").is_none() {
+        return;
+    }
+
+    let pct = |key: &str| states[key].as_f64().unwrap_or(0.0);
+    println!(
+        "CPU states avg: user {:.1}% | system {:.1}% | iowait {:.1}% | irq {:.1}% | softirq {:.1}% | steal {:.1}% | guest {:.1}% | idle {:.1}%",
+        pct("user"),
+        pct("system"),
+        pct("iowait"),
+        pct("irq"),
+        pct("softirq"),
+        pct("steal"),
+        pct("guest"),
+        pct("idle")
+    );
+
+    let mut cores: Vec<&serde_json::Value> = cpu["cores"].as_array().map(|c| c.iter().collect()).unwrap_or_default();
+    cores.sort_by(|a, b| {
+        let avg = |v: &serde_json::Value| v["avg"].as_f64().unwrap_or(0.0);
+        avg(b).partial_cmp(&avg(a)).unwrap_or(std::cmp::Ordering::Equal)
+    });
+    let busiest: Vec<String> = cores
+        .iter()
+        .take(4)
+        .map(|c| {
+            format!(
+                "{} avg {:.1}% max {:.1}%",
+                c["cpu"].as_str().unwrap_or("?"),
+                c["avg"].as_f64().unwrap_or(0.0),
+                c["max"].as_f64().unwrap_or(0.0)
+            )
+        })
+        .collect();
+    if !busiest.is_empty() {
+        println!("Busiest cores ({} total): {}", cores.len(), busiest.join(" | "));
+    }
+}
+
+/// What `--disk-scan` found on the full mount.
+fn print_disk_scan(scan: &diskscan::ScanReport) {
+    let size = |bytes: u64| {
+        if bytes >= 1 << 30 {
+            format!("{:.1} GiB", bytes as f64 / (1u64 << 30) as f64)
+        } else {
+            format!("{:.1} MiB", bytes as f64 / (1u64 << 20) as f64)
+        }
+    };
+    let list = |title: &str, items: &[diskscan::PathSize]| {
+        if !items.is_empty() {
+            println!("{}:", title);
+            for item in items.iter().take(5) {
+                println!("• {} – {}", item.path, size(item.bytes));
             }
         }
+    };
 
-        if let serde_json::Value::Object(ref mut map) = out {
-            map.insert("summary".to_string(), summary.clone());
-            map.insert("offenders".to_string(), offenders.clone());
+    println!(
+        "\nDisk scan of {}{}:",
+        scan.mount,
+        if scan.truncated { " (stopped early, sizes are lower bounds)" } else { "" }
+    );
+    list("Largest directories", &scan.largest_dirs);
+    list("Largest files", &scan.largest_files);
+    list("Recently grown files (last 24h)", &scan.recently_grown);
+    if !scan.culprits.is_empty() {
+        println!("Known culprits:");
+        for c in &scan.culprits {
+            println!("• {} ({}) – {}", c.what, c.path, size(c.bytes));
         }
+    }
+    if !scan.deleted_open.is_empty() {
+        println!("Deleted but still open (space is freed when the process closes them):");
+        for d in &scan.deleted_open {
+            println!("• {} held by {} (PID {}) – {}", d.path, d.name, d.pid, size(d.bytes));
+        }
+    }
+}
 
-        println!("{}", serde_json::to_string_pretty(&out).unwrap());
-        if let Some(path) = args.report.as_ref() {
-            // include JSON findings in the report
-            let summary_json = serde_json::to_string_pretty(&out).unwrap();
-            let _ = write_html_report(path, &timeline, &summary_json);
+/// Every sampled mount with space and inode usage, from the summary's `disk.mounts`.
+fn print_mounts(mounts: &serde_json::Value) {
+    let Some(mounts) = mounts.as_array().filter(|m| !m.is_empty()) else {
+        return;
+    };
+
+    println!("Mounts:");
+    for m in mounts {
+        let gib = |key: &str| m[key].as_f64().unwrap_or(0.0) / (1024.0 * 1024.0 * 1024.0);
+        let inodes = m["inodes_used_percent"]
+            .as_f64()
+            .map(|p| format!(" | inodes {:.1}%", p))
+            .unwrap_or_default();
+        println!(
+            "• {} ({}, {}{}) – {:.0}% used, {:.1} of {:.1} GiB free{}",
+            m["mount"].as_str().unwrap_or("?"),
+            m["fs"].as_str().unwrap_or("?"),
+            m["fstype"].as_str().unwrap_or("?"),
+            if m["read_only"].as_bool() == Some(true) { ", ro" } else { "" },
+            m["used_percent"].as_f64().unwrap_or(0.0),
+            gib("avail_bytes"),
+            gib("total_bytes"),
+            inodes
+        );
+    }
+}
+
+/// Busiest block devices over the window, from the summary's `devices`.
+fn print_devices(devices: &serde_json::Value) {
+    let Some(devices) = devices.as_array().filter(|d| !d.is_empty()) else {
+        return;
+    };
+
+    println!("Block devices (avg):");
+    for d in devices.iter().take(5) {
+        let num = |key: &str| d[key].as_f64().unwrap_or(0.0);
+        let mounts: Vec<&str> = d["mounts"].as_array().map(|m| m.iter().filter_map(|v| v.as_str()).collect()).unwrap_or_default();
+        println!(
+            "• {}{} – util {:.1}% (max {:.1}%) | await {:.1} ms | queue {:.2} | r {:.0} IOPS {:.1} MB/s | w {:.0} IOPS {:.1} MB/s",
+            d["name"].as_str().unwrap_or("?"),
+            if mounts.is_empty() { String::new() } else { format!(" ({})", mounts.join(", ")) },
+            num("util_avg"),
+            num("util_max"),
+            num("await_ms"),
+            num("queue_depth"),
+            num("read_iops"),
+            num("read_bps") / 1e6,
+            num("write_iops"),
+            num("write_bps") / 1e6
+        );
+    }
+}
+
+/// Load averages and task counts, from the summary's `cpu.load`.
+fn print_load(load: &serde_json::Value) {
+    if load.is_null() {
+        return;
+    }
+
+    let num = |key: &str| load[key].as_f64().unwrap_or(0.0);
+    let per_cpu = |i: usize| load["per_cpu"][i].as_f64().unwrap_or(0.0);
+    println!(
+        "Load avg {:.2} {:.2} {:.2} (per CPU {:.2} {:.2} {:.2}) | runnable avg {:.1} max {:.0} | D state avg {:.1} max {:.0}",
+        num("load1"),
+        num("load5"),
+        num("load15"),
+        per_cpu(0),
+        per_cpu(1),
+        per_cpu(2),
+        num("runnable_avg"),
+        num("runnable_max"),
+        num("d_state_avg"),
+        num("d_state_max")
+    );
+}
+
+/// Clock speed and temperature over the window, from the summary's `cpu.thermal`.
+fn print_thermal(thermal: &serde_json::Value) {
+    if thermal.is_null() {
+        return;
+    }
+
+    let mut parts = Vec::new();
+    if let (Some(avg), Some(min)) = (thermal["freq_percent_avg"].as_f64(), thermal["freq_percent_min"].as_f64()) {
+        parts.push(format!("clock avg {:.0}% of base (slowest CPU {:.0}%)", avg, min));
+    }
+    if let Some(temp) = thermal["hottest"]["temp_c"].as_f64() {
+        parts.push(format!("hottest {} {:.1} °C", thermal["hottest"]["name"].as_str().unwrap_or("?"), temp));
+    }
+    if let Some(events) = thermal["throttle_events"].as_u64() {
+        parts.push(format!("{} throttle events", events));
+    }
+    if !parts.is_empty() {
+        println!("Thermal: {}", parts.join(" | "));
+    }
+}
+
+/// Interface averages and TCP health over the window, from the summary's `net`.
+fn print_net(net: &serde_json::Value) {
+    if net.is_null() {
+        return;
+    }
+
+    println!("Network (avg):");
+    for i in net["interfaces"].as_array().into_iter().flatten() {
+        let num = |key: &str| i[key].as_f64().unwrap_or(0.0);
+        println!(
+            "• {} – rx {:.1} MB/s (max {:.1}) | tx {:.1} MB/s (max {:.1}) | drops {:.1}/s | errors {:.1}/s",
+            i["name"].as_str().unwrap_or("?"),
+            num("rx_bps_avg") / 1e6,
+            num("rx_bps_max") / 1e6,
+            num("tx_bps_avg") / 1e6,
+            num("tx_bps_max") / 1e6,
+            num("drops_per_sec_avg"),
+            num("errors_per_sec_avg")
+        );
+    }
+    let tcp = &net["tcp"];
+    if !tcp["sockets"].is_null() {
+        println!(
+            "• TCP – retransmits avg {:.2}% (max {:.2}%) | connections max {}",
+            tcp["retrans_percent_avg"].as_f64().unwrap_or(0.0),
+            tcp["retrans_percent_max"].as_f64().unwrap_or(0.0),
+            tcp["connections_max"].as_f64().unwrap_or(0.0)
+        );
+    }
+}
+
+/// Processes that did I/O and exited during the window, from the summary's `io.exited`.
+fn print_exited_io(exited: &serde_json::Value) {
+    let Some(exited) = exited.as_array().filter(|e| !e.is_empty()) else {
+        return;
+    };
+
+    println!("I/O from processes that exited during the window:");
+    for e in exited {
+        let bytes = |key: &str| e[key].as_f64().unwrap_or(0.0) / 1e6;
+        println!(
+            "• {} (PID {}, parent {}) – read {:.1} MB, wrote {:.1} MB",
+            e["name"].as_str().filter(|n| !n.is_empty()).unwrap_or("?"),
+            e["pid"],
+            e["ppid"],
+            bytes("read_bytes"),
+            bytes("write_bytes")
+        );
+    }
+}
+
+/// Context switches and run-queue wait of the CPU offender's threads.
+fn sched_detail(s: &sched::SchedRates) -> String {
+    let mut line = format!(
+        "  context switches: {:.0}/s voluntary, {:.0}/s involuntary",
+        s.voluntary_switches_per_sec, s.involuntary_switches_per_sec
+    );
+    if let (Some(run), Some(wait)) = (s.run_percent, s.wait_percent) {
+        line.push_str(&format!(" | threads ran {:.1}%, waited for a CPU {:.1}%", run, wait));
+    }
+    line
+}
+
+/// Syscall-level view of the I/O offender: cached vs. disk bytes, syscall sizes, blocked time.
+fn io_detail(sample: &io::IoSample) -> String {
+    let d = &sample.detail;
+    let mut line = format!(
+        "  syscalls: read {:.0}/s ({} B/s incl. cache) | write {:.0}/s ({} B/s incl. cache) | cancelled writes {} B/s",
+        d.syscr_per_sec, d.rchar_bps, d.syscw_per_sec, d.wchar_bps, d.cancelled_write_bps
+    );
+    if let Some(delay) = d.blkio_delay_percent {
+        line.push_str(&format!(" | blocked on disk {:.0}%", delay));
+    }
+    if let Some(pattern) = &sample.pattern {
+        line.push_str(&format!("\n  Pattern: {}", pattern));
+    }
+    line
+}
+
+/// What system memory is made of and how hard it is paging, from the summary's `mem` section.
+fn print_mem_composition(mem: &serde_json::Value) {
+    let c = &mem["composition"];
+    if !c.is_null() {
+        let gib = |key: &str| c[key].as_f64().unwrap_or(0.0) / (1024.0 * 1024.0);
+        println!(
+            "Mem composition: anon {:.1} GiB | page cache {:.1} GiB | buffers {:.1} GiB | shmem/tmpfs {:.1} GiB | slab {:.1} GiB reclaimable, {:.1} GiB unreclaimable | kernel stack {:.2} GiB | page tables {:.2} GiB | hugepages {:.1} GiB | swap {:.1}/{:.1} GiB",
+            gib("anon_kb"),
+            gib("page_cache_kb"),
+            gib("buffers_kb"),
+            gib("shmem_kb"),
+            gib("slab_reclaimable_kb"),
+            gib("slab_unreclaimable_kb"),
+            gib("kernel_stack_kb"),
+            gib("page_tables_kb"),
+            gib("hugepages_kb"),
+            gib("swap_used_kb"),
+            gib("swap_total_kb")
+        );
+    }
+
+    let p = &mem["paging"];
+    if !p.is_null() {
+        let mbs = |key: &str| p[key].as_f64().unwrap_or(0.0) / 1e6;
+        println!(
+            "Paging avg: swap in {:.1} MB/s | swap out {:.1} MB/s | major faults {:.1}/s (max {:.1}/s)",
+            mbs("swap_in_bps_avg"),
+            mbs("swap_out_bps_avg"),
+            p["major_faults_avg"].as_f64().unwrap_or(0.0),
+            p["major_faults_max"].as_f64().unwrap_or(0.0)
+        );
+    }
+}
+
+/// Who the OOM killer would pick next and who it already killed, from the summary's `mem.oom`.
+fn print_oom(oom: &serde_json::Value) {
+    let scores: Vec<String> = oom["scores"]
+        .as_array()
+        .map(|a| {
+            a.iter()
+                .map(|s| {
+                    format!(
+                        "{} (PID {}) score {} adj {}",
+                        s["name"].as_str().unwrap_or("?"),
+                        s["pid"],
+                        s["oom_score"],
+                        s["oom_score_adj"]
+                    )
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    if !scores.is_empty() {
+        println!("OOM kill order: {}", scores.join(" | "));
+    }
+
+    if let Some(total) = oom["kill_total"].as_u64() {
+        println!(
+            "OOM kills: {} since boot, {} during this window",
+            total,
+            oom["kills_in_window"].as_u64().unwrap_or(0)
+        );
+    }
+
+    let kills = oom["kills"].as_array().cloned().unwrap_or_default();
+    if !kills.is_empty() {
+        println!("Recent OOM kills (kernel log):");
+        for k in kills.iter().rev() {
+            let mut line = format!("• {} (PID {})", k["name"].as_str().unwrap_or("?"), k["pid"]);
+            if let Some(kb) = k["anon_rss_kb"].as_u64() {
+                line.push_str(&format!(" anon-rss {:.1} MiB", kb as f64 / 1024.0));
+            }
+            if k["cgroup_limit"].as_bool() == Some(true) {
+                line.push_str(" (cgroup memory limit)");
+            }
+            if let Some(ts) = k["uptime_secs"].as_f64() {
+                line.push_str(&format!(" at {:.0}s after boot", ts));
+            }
+            println!("{}", line);
+        }
+    }
+}
+
+/// Findings plus summary and offenders, as printed by `--json` and embedded in the report.
+fn findings_json(
+    findings: &Findings,
+    summary: &serde_json::Value,
+    offenders: &serde_json::Value,
+    disk_scan: Option<&diskscan::ScanReport>,
+) -> serde_json::Value {
+    let mut out = serde_json::to_value(findings).unwrap();
+
+    if let serde_json::Value::Object(ref mut map) = out {
+        map.insert("summary".to_string(), summary.clone());
+        map.insert("offenders".to_string(), offenders.clone());
+        if let Some(scan) = disk_scan {
+            map.insert("disk_scan".to_string(), json!(scan));
         }
+    }
+
+    out
+}
 
+/// Peak pressure per resource over the window, from the summary's `psi` section.
+fn print_psi(psi: &serde_json::Value) {
+    let Some(resources) = psi.as_object() else {
         return;
+    };
+
+    let parts: Vec<String> = resources
+        .iter()
+        .map(|(name, r)| {
+            let mut part = format!("{} some {:.1}%", name, r["some_avg10"].as_f64().unwrap_or(0.0));
+            if let Some(full) = r["full_avg10"].as_f64() {
+                part.push_str(&format!(" full {:.1}%", full));
+            }
+            part
+        })
+        .collect();
+    println!("Pressure max avg10: {}", parts.join(" | "));
+}
+
+/// One line of per-process memory in MiB; PSS/USS/swap only when they were readable.
+fn mem_breakdown(usage: &mem::ProcMem) -> String {
+    let mib = |kb: u64| format!("{:.1} MiB", kb as f64 / 1024.0);
+    let mut parts = vec![format!("RSS {}", mib(usage.rss_kb))];
+    if let Some(pss) = usage.pss_kb {
+        parts.push(format!("PSS {}", mib(pss)));
+    }
+    if let Some(uss) = usage.uss_kb {
+        parts.push(format!("USS {}", mib(uss)));
+    }
+    if let Some(swap) = usage.swap_kb {
+        parts.push(format!("swap {}", mib(swap)));
     }
+    parts.push(format!("anon {}", mib(usage.anon_kb)));
+    parts.push(format!("file {}", mib(usage.file_kb)));
+    format!("  {}", parts.join(" | "))
+}
 
-    match cpu_result.as_ref() {
+fn print_findings(findings: &Findings, summary: &serde_json::Value) {
+    match findings.cpu.as_ref() {
         Some(sample) => {
             println!(
-                "Sustained high CPU usage detected:\n• {} (PID {}) – {:.1}% CPU\n",
-                sample.name, sample.pid, sample.cpu
+                "Sustained high CPU usage detected:\n• {} (PID {}){} – {:.1}% CPU",
+                sample.name,
+                sample.pid,
+                cgroup::suffix(sample.cgroup.as_ref()),
+                sample.cpu
             );
+            if let Some(s) = &sample.sched {
+                println!("{}", sched_detail(s));
+            }
+            for t in &sample.threads {
+                println!("  thread {} (TID {}) – avg {:.1}% CPU, max {:.1}%", t.name, t.tid, t.cpu_avg, t.cpu_max);
+            }
+            println!();
 
             println!("Explanation:");
             println!("{}", explain_process(&sample.name));
@@ -289,12 +908,21 @@ fn main() {
         }
     }
 
-    match mem_result.as_ref() {
+    match findings.mem.as_ref() {
         Some(sample) => {
             println!(
-                "\nSustained high memory usage detected:\n• {} (PID {}) – {:.1}% mem (system {:.1}%)\n",
-                sample.name, sample.pid, sample.mem, sample.used_percent
+                "\nSustained high memory usage detected:\n• {} (PID {}){} – {:.1}% mem (system {:.1}%)",
+                sample.name,
+                sample.pid,
+                cgroup::suffix(sample.cgroup.as_ref()),
+                sample.mem,
+                sample.used_percent
             );
+            println!("{}", mem_breakdown(&sample.usage));
+            if let Some(hint) = &sample.hint {
+                println!("  System memory: {}", hint);
+            }
+            println!();
 
             println!("Explanation:");
             println!("{}", explain_process(&sample.name));
@@ -304,7 +932,18 @@ fn main() {
         }
     }
 
-    match disk_result.as_ref() {
+    if let Some(growth) = findings.mem_growth.as_ref() {
+        println!(
+            "\nSteady memory growth detected:\n• {}{}\n",
+            growth.describe(),
+            cgroup::suffix(growth.cgroup.as_ref())
+        );
+
+        println!("Explanation:");
+        println!("{}", explain_process("mem_growth"));
+    }
+
+    match findings.disk.as_ref() {
         Some(sample) => {
             println!(
                 "\nSustained high disk usage detected:\n• {} mounted on {} – {:.1}% used\n",
@@ -319,12 +958,38 @@ fn main() {
         }
     }
 
-    match io_result.as_ref() {
+    if let Some(forecast) = findings.disk_fill.as_ref() {
+        println!("\nDisk filling up:\n• {}\n", forecast.describe());
+
+        println!("Explanation:");
+        println!("{}", explain_process("disk_fill"));
+    }
+
+    if let Some(sample) = findings.inodes.as_ref() {
+        println!(
+            "\nSustained inode exhaustion detected:\n• {} mounted on {} – {:.1}% of inodes used ({} of {})\n",
+            sample.fs,
+            sample.mount,
+            sample.inodes_used_percent.unwrap_or(0.0),
+            sample.inodes_used,
+            sample.inodes_total
+        );
+
+        println!("Explanation:");
+        println!("{}", explain_process("inodes"));
+    }
+
+    match findings.io.as_ref() {
         Some(sample) => {
             println!(
-                "\nSustained high I/O detected:\n• {} (PID {}) – read {} B/s, write {} B/s\n",
-                sample.name, sample.pid, sample.read_bps, sample.write_bps
+                "\nSustained high I/O detected:\n• {} (PID {}){} – read {} B/s, write {} B/s",
+                sample.name,
+                sample.pid,
+                cgroup::suffix(sample.cgroup.as_ref()),
+                sample.read_bps,
+                sample.write_bps
             );
+            println!("{}\n", io_detail(sample));
 
             println!("Explanation:");
             println!("{}", explain_process("io"));
@@ -334,63 +999,145 @@ fn main() {
         }
     }
 
-    println!("\nSummary ({}s):", duration);
-    println!(
-        "CPU avg {:.1}% | max {:.1}%",
-        summary["cpu"]["avg"].as_f64().unwrap_or(0.0),
-        summary["cpu"]["max"].as_f64().unwrap_or(0.0)
-    );
-    println!(
-        "Mem avg {:.1}% | max {:.1}% | system avg {:.1}% | system max {:.1}%",
-        summary["mem"]["avg"].as_f64().unwrap_or(0.0),
-        summary["mem"]["max"].as_f64().unwrap_or(0.0),
-        summary["mem"]["system_avg"].as_f64().unwrap_or(0.0),
-        summary["mem"]["system_max"].as_f64().unwrap_or(0.0)
-    );
-    println!(
-        "Disk avg {:.1}% | max {:.1}%",
-        summary["disk"]["avg"].as_f64().unwrap_or(0.0),
-        summary["disk"]["max"].as_f64().unwrap_or(0.0)
-    );
+    if let Some(sample) = findings.device.as_ref() {
+        println!(
+            "\nSaturated block device detected:\n• {} – {:.1}% util on average, await {:.1} ms, queue depth {:.2}{}\n",
+            sample.device.name,
+            sample.util_avg,
+            sample.await_avg_ms,
+            sample.device.queue_depth,
+            if sample.device.mounts.is_empty() {
+                String::new()
+            } else {
+                format!(" (backs {})", sample.device.mounts.join(", "))
+            }
+        );
 
-    let cpu_top = top_offenders(&cpu_offenders, 3);
-    if !cpu_top.is_empty() {
-        println!("\nTop CPU offenders:");
-        for row in cpu_top {
+        println!("Explanation:");
+        println!("{}", explain_process("device"));
+    }
+
+    if let Some(c) = findings.sched.as_ref() {
+        println!(
+            "\nScheduler contention detected:\n• {} (PID {}){} – waited {:.1}% of the time for a CPU while running {:.1}%, {:.0} preemptions/s{}\n",
+            c.name,
+            c.pid,
+            cgroup::suffix(c.cgroup.as_ref()),
+            c.wait_percent_avg,
+            c.run_percent_avg,
+            c.involuntary_switches_per_sec_avg,
+            if c.also_contended > 0 { format!(" ({} more processes contended)", c.also_contended) } else { String::new() }
+        );
+
+        println!("Explanation:");
+        println!("{}", explain_process("sched"));
+    }
+
+    if let Some(stuck) = findings.d_state.as_ref() {
+        println!("\nProcesses stuck in uninterruptible sleep (D state):");
+        if let (Some(load), Some(busy)) = (stuck.load1_per_cpu, stuck.cpu_busy) {
+            println!("• load {:.2} per CPU while the CPUs were {:.0}% busy", load, busy);
+        }
+        for t in &stuck.tasks {
             println!(
-                "• {} (PID {}) – sum {:.1} | avg {:.1} | max {:.1}",
-                row.name, row.pid, row.sum, row.avg, row.max
+                "• {} ({}) – in D state in {} samples{}",
+                t.task.name,
+                match t.task.tgid {
+                    Some(tgid) => format!("TID {} of PID {}", t.task.pid, tgid),
+                    None => format!("PID {}", t.task.pid),
+                },
+                t.hits,
+                if t.task.wchan.is_empty() { String::new() } else { format!(", waiting in {}", t.task.wchan) }
             );
+            for frame in t.task.stack.iter().take(8) {
+                println!("    {}", frame);
+            }
         }
+        println!();
+
+        println!("Explanation:");
+        println!("{}", explain_process("d_state"));
     }
 
-    let mem_top = top_offenders(&mem_offenders, 3);
-    if !mem_top.is_empty() {
-        println!("\nTop memory offenders:");
-        for row in mem_top {
-            println!(
-                "• {} (PID {}) – sum {:.1} | avg {:.1} | max {:.1}",
-                row.name, row.pid, row.sum, row.avg, row.max
-            );
+    if let Some(t) = findings.thermal.as_ref() {
+        println!("\nSustained CPU throttling detected ({}):", t.causes.join(", "));
+        if let Some(freq) = t.busy_freq_percent {
+            println!("• busy CPUs ran at {:.0}% of their base clock on average", freq);
+        }
+        if let Some(zone) = &t.hottest {
+            println!("• hottest zone {} reached {:.1} °C", zone.name, zone.temp_c);
         }
+        if t.throttle_events > 0 {
+            println!("• {} thermal throttle events during the window", t.throttle_events);
+        }
+        println!();
+
+        println!("Explanation:");
+        println!("{}", explain_process("thermal"));
     }
 
-    if let Some(path) = args.report.as_ref() {
-        let mut out = json!({});
-        if let Some(c) = cpu_result { if let serde_json::Value::Object(ref mut map) = out { map.insert("cpu".to_string(), serde_json::to_value(&c).unwrap()); } }
-        if let Some(m) = mem_result { if let serde_json::Value::Object(ref mut map) = out { map.insert("mem".to_string(), serde_json::to_value(&m).unwrap()); } }
-        if let Some(d) = disk_result { if let serde_json::Value::Object(ref mut map) = out { map.insert("disk".to_string(), serde_json::to_value(&d).unwrap()); } }
-        if let Some(i) = io_result { if let serde_json::Value::Object(ref mut map) = out { map.insert("io".to_string(), serde_json::to_value(&i).unwrap()); } }
+    match findings.net.as_ref() {
+        Some(finding) => {
+            println!("\nSustained network problem detected ({}):", finding.causes.join(", "));
+            if let Some(i) = &finding.interface {
+                println!(
+                    "• {} – rx {:.1} MB/s, tx {:.1} MB/s{}, {:.0} drops/s, {:.0} errors/s",
+                    i.name,
+                    i.rx_bps as f64 / 1e6,
+                    i.tx_bps as f64 / 1e6,
+                    i.util_percent().map(|u| format!(" ({:.0}% of the link)", u)).unwrap_or_default(),
+                    i.drops_per_sec,
+                    i.errors_per_sec
+                );
+            }
+            if let Some(tcp) = &finding.tcp {
+                println!(
+                    "• TCP – {:.1}% of segments retransmitted ({:.0}/s) | {} connections: {} established, {} syn-sent, {} syn-recv, {} time-wait, {} close-wait",
+                    tcp.retrans_percent,
+                    tcp.retrans_segs_per_sec,
+                    tcp.sockets.connections(),
+                    tcp.sockets.established,
+                    tcp.sockets.syn_sent,
+                    tcp.sockets.syn_recv,
+                    tcp.sockets.time_wait,
+                    tcp.sockets.close_wait
+                );
+            }
+            for p in &finding.processes {
+                println!(
+                    "• {} (PID {}) – {} TCP connections ({} established, {} close-wait)",
+                    p.name,
+                    p.pid,
+                    p.sockets.connections(),
+                    p.sockets.established,
+                    p.sockets.close_wait
+                );
+            }
+            println!();
 
-        if let serde_json::Value::Object(ref mut map) = out {
-            map.insert("summary".to_string(), summary);
-            map.insert("offenders".to_string(), offenders);
+            println!("Explanation:");
+            println!("{}", explain_process("net"));
         }
+        None => {
+            println!("Network looks normal.");
+        }
+    }
 
-        let summary_json = serde_json::to_string_pretty(&out).unwrap();
-        match write_html_report(path, &timeline, &summary_json) {
-            Ok(()) => println!("Wrote HTML report to {}", path),
-            Err(e) => eprintln!("Failed to write report: {}", e),
+    match findings.psi.as_ref() {
+        Some(sample) => {
+            println!(
+                "\nSustained {} pressure detected:\n• {} {} – {:.1}% stalled (threshold {:.1}%)\n",
+                sample.resource, sample.kind, sample.window, sample.value, sample.threshold
+            );
+
+            println!("Explanation:");
+            println!("{}", explain_process("psi"));
+        }
+        None if summary["psi"].is_null() => {
+            println!("Pressure stall information unavailable (kernel without PSI).");
+        }
+        None => {
+            println!("Pressure stalls look normal.");
         }
     }
 }
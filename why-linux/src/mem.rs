@@ -1,95 +1,290 @@
-use std::thread::sleep;
-use std::time::Duration;
-use std::fs;
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
-use serde::Serialize;
+use crate::analysis::{human_duration, steady_growth};
+use crate::cgroup::{self, Cgroup};
+use crate::sampler::{GroupMember, Snapshot};
+use crate::vmstat::VmStat;
+use crate::source::Source;
+
+/// Which per-process memory figure ranks offenders.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum MemMetric {
+    /// Proportional set size: shared pages split between the processes mapping them
+    Pss,
+    /// Unique set size: pages only this process maps (what killing it would free)
+    Uss,
+    /// Resident set size, as `ps %mem` reports it (counts shared pages in full)
+    Rss,
+}
+
+/// Per-process memory in kB, from `/proc/[pid]/smaps_rollup` or, when that is not readable,
+/// `/proc/[pid]/statm`; PSS and USS are only known from smaps.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ProcMem {
+    pub rss_kb: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pss_kb: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub uss_kb: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub swap_kb: Option<u64>,
+    pub anon_kb: u64,
+    /// File-backed and shared memory
+    pub file_kb: u64,
+}
+
+impl ProcMem {
+    /// The figure used for ranking; RSS stands in for PSS/USS after a statm fallback.
+    pub fn kb(&self, metric: MemMetric) -> u64 {
+        match metric {
+            MemMetric::Pss => self.pss_kb.unwrap_or(self.rss_kb),
+            MemMetric::Uss => self.uss_kb.unwrap_or(self.rss_kb),
+            MemMetric::Rss => self.rss_kb,
+        }
+    }
+
+    /// Adds another process's usage, e.g. when folding a group.
+    pub fn add(&mut self, other: &ProcMem) {
+        let sum = |a: Option<u64>, a_rss: u64, b: Option<u64>, b_rss: u64| match (a, b) {
+            (None, None) => None,
+            _ => Some(a.unwrap_or(a_rss) + b.unwrap_or(b_rss)),
+        };
+        self.pss_kb = sum(self.pss_kb, self.rss_kb, other.pss_kb, other.rss_kb);
+        self.uss_kb = sum(self.uss_kb, self.rss_kb, other.uss_kb, other.rss_kb);
+        self.swap_kb = sum(self.swap_kb, 0, other.swap_kb, 0);
+        self.rss_kb += other.rss_kb;
+        self.anon_kb += other.anon_kb;
+        self.file_kb += other.file_kb;
+    }
+}
+
+/// Parses `/proc/[pid]/smaps_rollup`; empty for kernel threads.
+fn parse_smaps_rollup(contents: &str) -> ProcMem {
+    let mut fields = HashMap::new();
+    for line in contents.lines() {
+        let mut parts = line.split_whitespace();
+        if let (Some(key), Some(value)) = (parts.next(), parts.next())
+            && let Ok(kb) = value.parse::<u64>()
+        {
+            fields.insert(key.trim_end_matches(':'), kb);
+        }
+    }
+    let get = |key: &str| fields.get(key).copied().unwrap_or(0);
+
+    ProcMem {
+        rss_kb: get("Rss"),
+        pss_kb: Some(get("Pss")),
+        uss_kb: Some(get("Private_Clean") + get("Private_Dirty") + get("Private_Hugetlb")),
+        swap_kb: Some(get("Swap")),
+        anon_kb: get("Anonymous"),
+        file_kb: get("Rss").saturating_sub(get("Anonymous")),
+    }
+}
+
+/// Parses `/proc/[pid]/statm` (sizes in pages): resident and shared give RSS and the file part.
+fn parse_statm(contents: &str, page_kb: u64) -> Option<ProcMem> {
+    let fields: Vec<u64> = contents.split_whitespace().filter_map(|f| f.parse().ok()).collect();
+    let (resident, shared) = (*fields.get(1)?, *fields.get(2)?);
+
+    Some(ProcMem {
+        rss_kb: resident * page_kb,
+        anon_kb: resident.saturating_sub(shared) * page_kb,
+        file_kb: shared * page_kb,
+        ..ProcMem::default()
+    })
+}
+
+pub fn page_kb() -> u64 {
+    // SAFETY: sysconf has no preconditions
+    let size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+    if size > 0 { size as u64 / 1024 } else { 4 }
+}
+
+/// RSS of one process from statm, which is cheap enough to read for every pid on every tick.
+pub fn read_statm(source: &Source, pid: u32) -> Option<ProcMem> {
+    parse_statm(&source.read_pid(pid, "statm")?, page_kb())
+}
+
+/// PSS/USS breakdown from smaps_rollup. It walks the page tables under the mmap lock and needs
+/// ptrace access, so it is only read for the largest processes.
+pub fn read_smaps_rollup(source: &Source, pid: u32) -> Option<ProcMem> {
+    Some(parse_smaps_rollup(&source.read_pid(pid, "smaps_rollup")?))
+}
+
+/// Re-derives each process's `mem` percent for `metric` from its recorded breakdown, so that
+/// replay honours `--mem-metric`; recordings without a breakdown are left as they are.
+pub fn apply_metric(snapshots: &mut [Snapshot], metric: MemMetric) {
+    for snapshot in snapshots {
+        let Some(total_kb) = snapshot.meminfo.as_ref().map(|m| m.total_kb).filter(|&t| t > 0) else {
+            continue;
+        };
+        for p in snapshot.procs.iter_mut().filter(|p| p.mem_kb.rss_kb > 0) {
+            p.mem = p.mem_kb.kb(metric) as f32 / total_kb as f32 * 100.0;
+        }
+    }
+}
 
 #[derive(Debug, Serialize)]
 pub struct MemSample {
     pub name: String,
     pub pid: u32,
+    /// The `--mem-metric` figure as a percent of MemTotal
     pub mem: f32,
     pub used_percent: f32,
+    #[serde(flatten)]
+    pub usage: ProcMem,
+    /// What the used memory is made of, e.g. "18.0 GiB is in shmem/tmpfs"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hint: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cgroup: Option<Cgroup>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub members: Vec<GroupMember>,
+}
+
+/// System memory composition from `/proc/meminfo`, in kB.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MemInfo {
+    pub total_kb: u64,
+    pub available_kb: u64,
+    pub free_kb: u64,
+    /// Anonymous process memory (AnonPages)
+    pub anon_kb: u64,
+    /// Page cache, including shmem/tmpfs
+    pub cached_kb: u64,
+    pub buffers_kb: u64,
+    /// Shared memory and tmpfs; counted in `cached_kb` but not reclaimable
+    pub shmem_kb: u64,
+    pub slab_reclaimable_kb: u64,
+    pub slab_unreclaimable_kb: u64,
+    pub kernel_stack_kb: u64,
+    pub page_tables_kb: u64,
+    /// Memory reserved for hugetlbfs (HugePages_Total × Hugepagesize)
+    pub hugepages_kb: u64,
+    pub swap_total_kb: u64,
+    pub swap_free_kb: u64,
 }
 
-fn get_system_mem_used_percent() -> Option<f32> {
-    let contents = fs::read_to_string("/proc/meminfo").ok()?;
-    let mut total: Option<f32> = None;
-    let mut available: Option<f32> = None;
+impl MemInfo {
+    pub fn swap_used_kb(&self) -> u64 {
+        self.swap_total_kb.saturating_sub(self.swap_free_kb)
+    }
+
+    /// Page cache that can be dropped, i.e. without shmem/tmpfs.
+    pub fn page_cache_kb(&self) -> u64 {
+        self.cached_kb.saturating_sub(self.shmem_kb)
+    }
+}
 
+/// Parses `/proc/meminfo`; MemTotal and MemAvailable are required.
+fn parse_meminfo(contents: &str) -> Option<MemInfo> {
+    let mut fields = HashMap::new();
     for line in contents.lines() {
-        if line.starts_with("MemTotal:") {
-            let parts: Vec<&str> = line.split_whitespace().collect();
-            total = parts.get(1)?.parse::<f32>().ok();
-        } else if line.starts_with("MemAvailable:") {
-            let parts: Vec<&str> = line.split_whitespace().collect();
-            available = parts.get(1)?.parse::<f32>().ok();
+        let mut parts = line.split_whitespace();
+        if let (Some(key), Some(value)) = (parts.next(), parts.next())
+            && let Ok(value) = value.parse::<u64>()
+        {
+            fields.insert(key.trim_end_matches(':'), value);
         }
     }
+    let get = |key: &str| fields.get(key).copied().unwrap_or(0);
+
+    Some(MemInfo {
+        total_kb: *fields.get("MemTotal")?,
+        available_kb: *fields.get("MemAvailable")?,
+        free_kb: get("MemFree"),
+        anon_kb: get("AnonPages"),
+        cached_kb: get("Cached"),
+        buffers_kb: get("Buffers"),
+        shmem_kb: get("Shmem"),
+        slab_reclaimable_kb: get("SReclaimable"),
+        slab_unreclaimable_kb: get("SUnreclaim"),
+        kernel_stack_kb: get("KernelStack"),
+        page_tables_kb: get("PageTables"),
+        hugepages_kb: get("HugePages_Total") * get("Hugepagesize"),
+        swap_total_kb: get("SwapTotal"),
+        swap_free_kb: get("SwapFree"),
+    })
+}
 
-    let total = total?;
-    let available = available?;
+pub fn read_meminfo(source: &Source) -> Option<MemInfo> {
+    parse_meminfo(&source.read("meminfo")?)
+}
 
+/// System memory used percent from (MemTotal, MemAvailable).
+pub fn used_percent(total: f32, available: f32) -> f32 {
     // meminfo values are in kB
     let used = total - available;
-    Some((used / total) * 100.0)
+    (used / total) * 100.0
 }
 
-pub fn get_top_mem_excluding(exclude_pid: Option<u32>) -> Option<MemSample> {
-    let output = std::process::Command::new("ps")
-        .args(["-eo", "pid,comm,%mem", "--sort=-%mem"])
-        .output()
-        .ok()?;
-
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let mut lines = stdout.lines();
-    lines.next(); // skip header
+/// Explains used memory: swap thrashing first, else the largest non-reclaimable category.
+pub fn composition_hint(info: &MemInfo, paging: Option<&VmStat>) -> Option<String> {
+    const THRASHING_BPS: u64 = 10 * 1024 * 1024;
 
-    for line in lines {
-        let mut parts = line.split_whitespace();
-        let pid: u32 = parts.next()?.parse().ok()?;
-        let name = parts.next()?.to_string();
-        let mem: f32 = parts.next()?.parse().ok()?;
-
-        if exclude_pid.is_some_and(|p| p == pid) {
-            continue;
+    if let Some(vm) = paging {
+        let swap_bps = vm.swap_in_bps + vm.swap_out_bps;
+        if swap_bps >= THRASHING_BPS {
+            return Some(format!("the system is thrashing swap at {:.1} MB/s", swap_bps as f64 / 1e6));
         }
+    }
 
-        // determine system usage too
-        let used_percent = get_system_mem_used_percent().unwrap_or(mem);
+    let categories = [
+        ("anonymous process memory", info.anon_kb),
+        ("shmem/tmpfs", info.shmem_kb),
+        ("unreclaimable slab", info.slab_unreclaimable_kb),
+        ("hugepages", info.hugepages_kb),
+        ("page tables", info.page_tables_kb),
+        ("kernel stacks", info.kernel_stack_kb),
+    ];
+    let (what, kb) = categories.iter().max_by_key(|(_, kb)| *kb)?;
+    (*kb > 0).then(|| format!("{:.1} GiB is in {}", *kb as f64 / (1024.0 * 1024.0), what))
+}
 
-        return Some(MemSample {
-            name,
-            pid,
-            mem,
-            used_percent,
-        });
-    }
+/// Returns the process using the most memory (by the sampled `--mem-metric`) in a snapshot.
+///
+/// When smaps was read for some processes, only those are ranked, so RSS never stands in for a
+/// PSS/USS it would be compared against.
+pub fn get_top_mem(snapshot: &Snapshot) -> Option<MemSample> {
+    let smaps_read = snapshot.procs.iter().any(|p| p.mem_kb.pss_kb.is_some());
+    let top = snapshot
+        .procs
+        .iter()
+        .filter(|p| !smaps_read || p.mem_kb.pss_kb.is_some())
+        .max_by(|a, b| a.mem.partial_cmp(&b.mem).unwrap_or(std::cmp::Ordering::Equal))?;
 
-    None
+    Some(MemSample {
+        name: top.name.clone(),
+        pid: top.pid,
+        mem: top.mem,
+        used_percent: snapshot.mem_used_percent.unwrap_or(top.mem),
+        usage: top.mem_kb.clone(),
+        hint: snapshot.meminfo.as_ref().and_then(|info| composition_hint(info, snapshot.vmstat.as_ref())),
+        cgroup: cgroup::resolve_opt(&top.cgroup),
+        members: top.members.clone(),
+    })
 }
 
+#[allow(clippy::collapsible_if)]
 pub fn detect_sustained_high_mem(
+    snapshots: &[Snapshot],
     threshold: f32,
-    samples: usize,
     min_hits: usize,
-    interval_secs: u64,
-    exclude_pid: Option<u32>,
 ) -> Option<MemSample> {
     let mut hits = 0;
     let mut last_sample = None;
 
-    for _ in 0..samples {
-        if let Some(sys_used) = get_system_mem_used_percent() {
+    for snapshot in snapshots {
+        if let Some(sys_used) = snapshot.mem_used_percent {
             if sys_used > threshold {
-                if let Some(sample) = get_top_mem_excluding(exclude_pid) {
+                if let Some(sample) = get_top_mem(snapshot) {
                     hits += 1;
                     last_sample = Some(sample);
                 }
             }
         }
-
-        sleep(Duration::from_secs(interval_secs));
     }
 
     if hits >= min_hits {
@@ -98,3 +293,219 @@ pub fn detect_sustained_high_mem(
         None
     }
 }
+
+/// A process whose memory grew steadily across the window.
+#[derive(Debug, Serialize)]
+pub struct MemGrowth {
+    pub name: String,
+    pub pid: u32,
+    /// Fitted growth of the `--mem-metric` figure
+    pub rate_mb_per_min: f32,
+    pub start_kb: u64,
+    pub end_kb: u64,
+    pub duration_secs: u64,
+    /// Seconds until MemAvailable would run out at this rate
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub oom_eta_secs: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cgroup: Option<Cgroup>,
+}
+
+impl MemGrowth {
+    /// "name (PID n) – +12.0 MB/min for 10m, out of memory in ~2h 5m at this rate"
+    pub fn describe(&self) -> String {
+        let mut line = format!(
+            "{} (PID {}) – +{:.1} MB/min for {}",
+            self.name,
+            self.pid,
+            self.rate_mb_per_min,
+            human_duration(self.duration_secs)
+        );
+        if let Some(eta) = self.oom_eta_secs {
+            line.push_str(&format!(", out of memory in ~{} at this rate", human_duration(eta)));
+        }
+        line
+    }
+}
+
+/// Flags the fastest-growing process whose memory rose steadily (mostly non-decreasing steps and
+/// a near-linear fit) over at least `min_hits` samples at `min_rate_mb_per_min` or more.
+pub fn detect_mem_growth(
+    snapshots: &[Snapshot],
+    metric: MemMetric,
+    min_rate_mb_per_min: f32,
+    min_hits: usize,
+) -> Option<MemGrowth> {
+    // (ts, metric kB, RSS kB, metric read from smaps rather than standing in with RSS)
+    let mut samples: HashMap<u32, Vec<(u64, u64, u64, bool)>> = HashMap::new();
+    for snapshot in snapshots {
+        for p in &snapshot.procs {
+            let precise = metric == MemMetric::Rss || p.mem_kb.pss_kb.is_some();
+            samples.entry(p.pid).or_default().push((snapshot.ts, p.mem_kb.kb(metric), p.mem_kb.rss_kb, precise));
+        }
+    }
+    // smaps is only read for the largest processes, so one that moved in or out of that set
+    // would jump between PSS and RSS; fall back to RSS for the whole series then
+    let series: HashMap<u32, Vec<(u64, u64)>> = samples
+        .into_iter()
+        .map(|(pid, points)| {
+            let precise = points.iter().all(|p| p.3);
+            (pid, points.into_iter().map(|(ts, kb, rss, _)| (ts, if precise { kb } else { rss })).collect())
+        })
+        .collect();
+
+    let mut best: Option<(u32, f64)> = None;
+    for (pid, points) in &series {
+        if points.len() < min_hits.max(3) {
+            continue;
+        }
+
+        let xy: Vec<(f64, f64)> = points.iter().map(|&(ts, kb)| (ts as f64, kb as f64)).collect();
+        let Some(kb_per_sec) = steady_growth(&xy) else {
+            continue;
+        };
+        if kb_per_sec * 60.0 / 1024.0 < min_rate_mb_per_min as f64 {
+            continue;
+        }
+
+        if best.is_none_or(|(_, rate)| kb_per_sec > rate) {
+            best = Some((*pid, kb_per_sec));
+        }
+    }
+
+    let (pid, kb_per_sec) = best?;
+    let points = &series[&pid];
+    let latest = snapshots.iter().rev().find_map(|s| s.procs.iter().find(|p| p.pid == pid))?;
+    let last = snapshots.last()?;
+    let available = last.meminfo.as_ref().map(|m| m.available_kb).or(last.mem_available_kb);
+
+    Some(MemGrowth {
+        name: latest.name.clone(),
+        pid,
+        rate_mb_per_min: (kb_per_sec * 60.0 / 1024.0) as f32,
+        start_kb: points[0].1,
+        end_kb: points[points.len() - 1].1,
+        duration_secs: points[points.len() - 1].0.saturating_sub(points[0].0),
+        oom_eta_secs: available.map(|kb| (kb as f64 / kb_per_sec) as u64),
+        cgroup: cgroup::resolve_opt(&latest.cgroup),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::fake::FakeProc;
+
+    #[test]
+    fn reads_meminfo() {
+        let proc = FakeProc::new();
+        proc.file("meminfo", "MemTotal:       1000 kB\nMemFree:         100 kB\nMemAvailable:    250 kB\n");
+
+        let info = read_meminfo(&proc.source()).unwrap();
+        assert_eq!(used_percent(info.total_kb as f32, info.available_kb as f32), 75.0);
+    }
+
+    #[test]
+    fn explains_used_memory_by_its_largest_part() {
+        let info = parse_meminfo(
+            "MemTotal:       32000000 kB\nMemAvailable:    1600000 kB\nCached:         20000000 kB\n\
+             Shmem:          18874368 kB\nAnonPages:       9000000 kB\nSUnreclaim:       300000 kB\n\
+             HugePages_Total:       2\nHugepagesize:       2048 kB\nSwapTotal:       1000 kB\nSwapFree:         400 kB\n",
+        )
+        .unwrap();
+        assert_eq!((info.page_cache_kb(), info.hugepages_kb, info.swap_used_kb()), (1125632, 4096, 600));
+        assert_eq!(composition_hint(&info, None).as_deref(), Some("18.0 GiB is in shmem/tmpfs"));
+
+        let thrashing = VmStat {
+            swap_in_bps: 25_000_000,
+            swap_out_bps: 15_000_000,
+            ..VmStat::default()
+        };
+        assert_eq!(
+            composition_hint(&info, Some(&thrashing)).as_deref(),
+            Some("the system is thrashing swap at 40.0 MB/s")
+        );
+    }
+
+    #[test]
+    fn flags_steady_growth_with_oom_eta() {
+        use crate::sampler::{ProcSample, proc_sample};
+
+        let with_rss = |pid, rss_kb| ProcSample {
+            mem_kb: ProcMem { rss_kb, ..ProcMem::default() },
+            ..proc_sample(pid, 1, &format!("p{}", pid))
+        };
+        // pid 1 leaks 1 MiB every 5s; pid 2 is large but flat
+        let snapshots: Vec<Snapshot> = (0..10u64)
+            .map(|i| Snapshot {
+                ts: 1000 + i * 5,
+                procs: vec![with_rss(1, 10_000 + i * 1024), with_rss(2, 500_000)],
+                mem_used_percent: Some(50.0),
+                meminfo: Some(MemInfo {
+                    available_kb: 12 * 1024,
+                    ..MemInfo::default()
+                }),
+                ..Default::default()
+            })
+            .collect();
+
+        let growth = detect_mem_growth(&snapshots, MemMetric::Rss, 1.0, 5).unwrap();
+        assert_eq!(growth.pid, 1);
+        assert!((growth.rate_mb_per_min - 12.0).abs() < 0.01);
+        assert_eq!((growth.duration_secs, growth.oom_eta_secs), (45, Some(60)));
+        assert!(detect_mem_growth(&snapshots, MemMetric::Rss, 20.0, 5).is_none());
+
+        // recordings from before the meminfo breakdown only carry mem_available_kb
+        let old: Snapshot = serde_json::from_str(r#"{"ts":1045,"procs":[],"mem_used_percent":50.0,"mounts":[],"psi":null,"mem_available_kb":12288}"#).unwrap();
+        let mut replayed = snapshots.clone();
+        *replayed.last_mut().unwrap() = Snapshot { procs: snapshots[9].procs.clone(), ..old };
+        assert_eq!(detect_mem_growth(&replayed, MemMetric::Rss, 1.0, 5).unwrap().oom_eta_secs, Some(60));
+    }
+
+    #[test]
+    fn top_mem_ranks_only_processes_with_smaps() {
+        use crate::sampler::ProcSample;
+
+        let smaps = ProcSample {
+            pid: 1,
+            mem: 20.0,
+            mem_kb: ProcMem { rss_kb: 400, pss_kb: Some(200), ..ProcMem::default() },
+            ..Default::default()
+        };
+        let rss_only = ProcSample { pid: 2, mem: 30.0, mem_kb: ProcMem { rss_kb: 300, ..ProcMem::default() }, ..Default::default() };
+        let mut snapshot = Snapshot { procs: vec![smaps, rss_only], ..Default::default() };
+        assert_eq!(get_top_mem(&snapshot).unwrap().pid, 1);
+
+        snapshot.procs[0].mem_kb.pss_kb = None;
+        assert_eq!(get_top_mem(&snapshot).unwrap().pid, 2);
+    }
+
+    #[test]
+    fn reads_smaps_rollup_and_statm() {
+        let proc = FakeProc::new();
+        proc.file(
+            "10/smaps_rollup",
+            "00400000-7ffd1000 ---p 00000000 00:00 0                          [rollup]\n\
+             Rss:                1000 kB\n\
+             Pss:                 600 kB\n\
+             Shared_Clean:        500 kB\n\
+             Shared_Dirty:          0 kB\n\
+             Private_Clean:       100 kB\n\
+             Private_Dirty:       400 kB\n\
+             Anonymous:           450 kB\n\
+             Swap:                 20 kB\n",
+        )
+        .file("11/statm", "5000 300 100 10 0 200 0\n");
+        let source = proc.source();
+
+        let smaps = read_smaps_rollup(&source, 10).unwrap();
+        assert_eq!((smaps.rss_kb, smaps.pss_kb, smaps.uss_kb, smaps.swap_kb), (1000, Some(600), Some(500), Some(20)));
+        assert_eq!((smaps.anon_kb, smaps.file_kb), (450, 550));
+        assert_eq!(smaps.kb(MemMetric::Uss), 500);
+
+        let statm = parse_statm("5000 300 100 10 0 200 0\n", 4).unwrap();
+        assert_eq!((statm.rss_kb, statm.anon_kb, statm.file_kb, statm.pss_kb), (1200, 800, 400, None));
+        assert_eq!(statm.kb(MemMetric::Pss), 1200);
+        assert!(read_statm(&source, 11).is_some() && read_smaps_rollup(&source, 11).is_none());
+    }
+}
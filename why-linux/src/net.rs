@@ -0,0 +1,583 @@
+// Network activity: per-interface traffic from /proc/net/dev, TCP retransmits from
+// /proc/net/snmp, socket states from /proc/net/tcp{,6}, and which processes own the sockets.
+use crate::sampler::{ProcSample, Snapshot};
+use crate::source::Source;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+
+/// One interface's traffic over a tick.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct InterfaceSample {
+    pub name: String,
+    pub rx_bps: u64,
+    pub tx_bps: u64,
+    pub rx_pps: f32,
+    pub tx_pps: f32,
+    /// Packets dropped per second, rx and tx together
+    pub drops_per_sec: f32,
+    /// Receive and transmit errors per second
+    pub errors_per_sec: f32,
+    /// Link speed in bytes/sec from sysfs; None where the driver does not report one (most VMs)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub speed_bps: Option<u64>,
+    /// veth, bridge, tunnel and other software interfaces (under /sys/devices/virtual/net)
+    #[serde(default, rename = "virtual", skip_serializing_if = "std::ops::Not::not")]
+    pub is_virtual: bool,
+}
+
+impl InterfaceSample {
+    /// Busier direction as a percent of the link speed, when that is known.
+    pub fn util_percent(&self) -> Option<f32> {
+        self.speed_bps.filter(|&s| s > 0).map(|s| self.rx_bps.max(self.tx_bps) as f32 / s as f32 * 100.0)
+    }
+}
+
+/// TCP sockets by state. TIME_WAIT sockets belong to no process, so they only show up system-wide.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct SocketStates {
+    pub established: u32,
+    pub syn_sent: u32,
+    pub syn_recv: u32,
+    /// FIN_WAIT1, FIN_WAIT2, CLOSING and LAST_ACK
+    pub closing: u32,
+    pub time_wait: u32,
+    pub close_wait: u32,
+    pub listen: u32,
+}
+
+impl SocketStates {
+    fn count(&mut self, state: u8) {
+        match state {
+            0x01 => self.established += 1,
+            0x02 => self.syn_sent += 1,
+            0x03 => self.syn_recv += 1,
+            0x06 => self.time_wait += 1,
+            0x08 => self.close_wait += 1,
+            0x0A => self.listen += 1,
+            _ => self.closing += 1,
+        }
+    }
+
+    /// Every socket except listeners: the connections themselves.
+    pub fn connections(&self) -> u32 {
+        self.established + self.syn_sent + self.syn_recv + self.closing + self.time_wait + self.close_wait
+    }
+}
+
+/// System-wide TCP health over a tick.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TcpStats {
+    pub out_segs_per_sec: f32,
+    pub retrans_segs_per_sec: f32,
+    /// Retransmitted segments as a percent of segments sent
+    pub retrans_percent: f32,
+    pub sockets: SocketStates,
+}
+
+/// TCP sockets held open by one process.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProcSockets {
+    pub pid: u32,
+    pub name: String,
+    #[serde(flatten)]
+    pub sockets: SocketStates,
+}
+
+/// Everything network-related captured during one tick.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NetSample {
+    /// Loopback is left out; it never saturates an uplink
+    pub interfaces: Vec<InterfaceSample>,
+    pub tcp: Option<TcpStats>,
+    /// Processes holding the most TCP connections
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub processes: Vec<ProcSockets>,
+}
+
+/// Processes reported in `NetSample::processes`.
+const TOP_SOCKET_PROCS: usize = 5;
+/// Busiest processes by CPU and by I/O whose fds are scanned for sockets on ordinary ticks.
+const SOCKET_CANDIDATES: usize = 16;
+/// Every this many ticks (and on the first) every process's fds are scanned, to find socket
+/// holders that use little CPU or I/O; they stay candidates while they hold connections.
+const FULL_SOCKET_SCAN: u64 = 30;
+/// Fewer segments than this per second make the retransmit percentage meaningless.
+const MIN_SEGS_PER_SEC: f32 = 100.0;
+
+/// Reads a `/proc/net` file in init's network namespace, falling back to our own.
+fn read_net(source: &Source, file: &str) -> Option<String> {
+    source.read(&format!("1/net/{}", file)).or_else(|| source.read(&format!("net/{}", file)))
+}
+
+/// The cumulative counters of one `/proc/net/dev` line.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct DevCounters {
+    rx_bytes: u64,
+    rx_packets: u64,
+    rx_errs: u64,
+    rx_drop: u64,
+    tx_bytes: u64,
+    tx_packets: u64,
+    tx_errs: u64,
+    tx_drop: u64,
+}
+
+fn parse_net_dev(contents: &str) -> Vec<(String, DevCounters)> {
+    contents
+        .lines()
+        .filter_map(|line| {
+            let (name, rest) = line.split_once(':')?;
+            let f: Vec<u64> = rest.split_whitespace().filter_map(|v| v.parse().ok()).collect();
+            if f.len() < 12 {
+                return None;
+            }
+            let counters = DevCounters {
+                rx_bytes: f[0],
+                rx_packets: f[1],
+                rx_errs: f[2],
+                rx_drop: f[3],
+                tx_bytes: f[8],
+                tx_packets: f[9],
+                tx_errs: f[10],
+                tx_drop: f[11],
+            };
+            Some((name.trim().to_string(), counters))
+        })
+        .collect()
+}
+
+/// (OutSegs, RetransSegs) from the `Tcp:` header/value line pair of `/proc/net/snmp`.
+fn parse_snmp_tcp(contents: &str) -> Option<(u64, u64)> {
+    let mut tcp = contents.lines().filter(|l| l.starts_with("Tcp:"));
+    let header = tcp.next()?;
+    let values = tcp.next()?;
+    let fields: HashMap<&str, u64> = header
+        .split_whitespace()
+        .zip(values.split_whitespace())
+        .filter_map(|(k, v)| Some((k, v.parse().ok()?)))
+        .collect();
+    Some((*fields.get("OutSegs")?, *fields.get("RetransSegs")?))
+}
+
+/// Counts a `/proc/net/tcp` or `tcp6` table by state and maps owned sockets' inodes to their
+/// state (TIME_WAIT and other orphaned sockets have inode 0).
+fn parse_tcp_table(contents: &str, states: &mut SocketStates, inodes: &mut HashMap<u64, u8>) {
+    for line in contents.lines().skip(1) {
+        let f: Vec<&str> = line.split_whitespace().collect();
+        let (Some(state), Some(inode)) = (f.get(3), f.get(9)) else {
+            continue;
+        };
+        if let (Ok(state), Ok(inode)) = (u8::from_str_radix(state, 16), inode.parse::<u64>()) {
+            states.count(state);
+            if inode != 0 {
+                inodes.insert(inode, state);
+            }
+        }
+    }
+}
+
+/// (link speed in bytes/sec, virtual) of an interface from `/sys/class/net/<name>`.
+fn read_link(source: &Source, name: &str) -> (Option<u64>, bool) {
+    // speed is in Mbit/s, and -1 or unreadable while the link is down or for virtual devices
+    let speed = fs::read_to_string(source.mount_path("/sys/class/net").join(name).join("speed"))
+        .ok()
+        .and_then(|s| s.trim().parse::<i64>().ok())
+        .filter(|&mbit| mbit > 0)
+        .map(|mbit| mbit as u64 * 1_000_000 / 8);
+    let is_virtual = source.mount_path("/sys/devices/virtual/net").join(name).exists();
+    (speed, is_virtual)
+}
+
+/// Processes whose fds are scanned on an ordinary tick: the busiest by CPU and by I/O, plus the
+/// socket holders reported last tick.
+fn socket_candidates<'a>(procs: &'a [ProcSample], owners: &HashSet<u32>) -> Vec<&'a ProcSample> {
+    let mut by_cpu: Vec<&ProcSample> = procs.iter().collect();
+    by_cpu.sort_by(|a, b| b.cpu.total_cmp(&a.cpu));
+    let mut by_io: Vec<&ProcSample> = procs.iter().collect();
+    by_io.sort_by_key(|p| std::cmp::Reverse(p.read_bps.saturating_add(p.write_bps)));
+
+    let mut pids: HashSet<u32> = owners.clone();
+    pids.extend(by_cpu.iter().take(SOCKET_CANDIDATES).map(|p| p.pid));
+    pids.extend(by_io.iter().take(SOCKET_CANDIDATES).map(|p| p.pid));
+    procs.iter().filter(|p| pids.contains(&p.pid)).collect()
+}
+
+/// Counts the TCP sockets among a process's open fds (`socket:[inode]` links).
+fn proc_sockets(source: &Source, pid: u32, tcp: &HashMap<u64, u8>) -> SocketStates {
+    let mut states = SocketStates::default();
+    let Ok(fds) = fs::read_dir(source.pid_path(pid, "fd")) else {
+        return states;
+    };
+    for fd in fds.flatten() {
+        let Ok(target) = fs::read_link(fd.path()) else {
+            continue;
+        };
+        let inode = target
+            .to_str()
+            .and_then(|t| t.strip_prefix("socket:["))
+            .and_then(|t| t.strip_suffix(']'))
+            .and_then(|t| t.parse::<u64>().ok());
+        if let Some(state) = inode.and_then(|i| tcp.get(&i)) {
+            states.count(*state);
+        }
+    }
+    states
+}
+
+/// Computes interface and retransmit rates from deltas between calls.
+pub struct NetSampler {
+    last_dev: HashMap<String, DevCounters>,
+    last_tcp: Option<(u64, u64)>,
+    /// Pids reported as socket holders in the previous tick
+    owners: HashSet<u32>,
+    ticks: u64,
+}
+
+impl NetSampler {
+    pub fn new(source: &Source) -> Self {
+        NetSampler {
+            last_dev: read_net(source, "dev").map(|c| parse_net_dev(&c).into_iter().collect()).unwrap_or_default(),
+            last_tcp: read_net(source, "snmp").and_then(|c| parse_snmp_tcp(&c)),
+            owners: HashSet::new(),
+            ticks: 0,
+        }
+    }
+
+    /// `None` when `/proc/net/dev` is unreadable; socket owners are looked up among `procs`,
+    /// all of them only every `FULL_SOCKET_SCAN` ticks since reading every fd link is costly.
+    pub fn sample(&mut self, source: &Source, elapsed_secs: f64, procs: &[ProcSample]) -> Option<NetSample> {
+        let elapsed_secs = elapsed_secs.max(f64::EPSILON);
+        let rate = |now: u64, before: u64| now.saturating_sub(before) as f64 / elapsed_secs;
+
+        let mut interfaces = Vec::new();
+        let mut current = HashMap::new();
+        for (name, now) in parse_net_dev(&read_net(source, "dev")?) {
+            if name != "lo"
+                && let Some(before) = self.last_dev.get(&name)
+            {
+                let (speed_bps, is_virtual) = read_link(source, &name);
+                interfaces.push(InterfaceSample {
+                    name: name.clone(),
+                    rx_bps: rate(now.rx_bytes, before.rx_bytes) as u64,
+                    tx_bps: rate(now.tx_bytes, before.tx_bytes) as u64,
+                    rx_pps: rate(now.rx_packets, before.rx_packets) as f32,
+                    tx_pps: rate(now.tx_packets, before.tx_packets) as f32,
+                    drops_per_sec: (rate(now.rx_drop, before.rx_drop) + rate(now.tx_drop, before.tx_drop)) as f32,
+                    errors_per_sec: (rate(now.rx_errs, before.rx_errs) + rate(now.tx_errs, before.tx_errs)) as f32,
+                    speed_bps,
+                    is_virtual,
+                });
+            }
+            current.insert(name, now);
+        }
+        self.last_dev = current;
+
+        let mut sockets = SocketStates::default();
+        let mut table = HashMap::new();
+        for file in ["tcp", "tcp6"] {
+            if let Some(contents) = read_net(source, file) {
+                parse_tcp_table(&contents, &mut sockets, &mut table);
+            }
+        }
+
+        let snmp = read_net(source, "snmp").and_then(|c| parse_snmp_tcp(&c));
+        let tcp = match (snmp, self.last_tcp) {
+            (Some((out, retrans)), Some((last_out, last_retrans))) => {
+                let out_segs_per_sec = rate(out, last_out) as f32;
+                let retrans_segs_per_sec = rate(retrans, last_retrans) as f32;
+                Some(TcpStats {
+                    out_segs_per_sec,
+                    retrans_segs_per_sec,
+                    retrans_percent: if out_segs_per_sec > 0.0 { retrans_segs_per_sec / out_segs_per_sec * 100.0 } else { 0.0 },
+                    sockets,
+                })
+            }
+            _ => None,
+        };
+        self.last_tcp = snmp;
+
+        let candidates: Vec<&ProcSample> = if self.ticks.is_multiple_of(FULL_SOCKET_SCAN) {
+            procs.iter().collect()
+        } else {
+            socket_candidates(procs, &self.owners)
+        };
+        self.ticks += 1;
+        let mut processes: Vec<ProcSockets> = if table.is_empty() {
+            Vec::new()
+        } else {
+            candidates
+                .into_iter()
+                .map(|p| ProcSockets { pid: p.pid, name: p.name.clone(), sockets: proc_sockets(source, p.pid, &table) })
+                .filter(|p| p.sockets.connections() > 0)
+                .collect()
+        };
+        processes.sort_by_key(|p| std::cmp::Reverse(p.sockets.connections()));
+        processes.truncate(TOP_SOCKET_PROCS);
+        self.owners = processes.iter().map(|p| p.pid).collect();
+
+        Some(NetSample { interfaces, tcp, processes })
+    }
+}
+
+/// A network problem that held for most of the window.
+#[derive(Debug, Serialize)]
+pub struct NetFinding {
+    /// Which of "throughput", "retransmits" and "connections" were sustained
+    pub causes: Vec<&'static str>,
+    /// The busiest interface in the latest tick that hit the throughput threshold
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub interface: Option<InterfaceSample>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tcp: Option<TcpStats>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub processes: Vec<ProcSockets>,
+}
+
+#[derive(Debug, Clone)]
+pub struct NetThresholds {
+    /// Receive/transmit bytes/sec considered high on interfaces of unknown speed
+    pub rx_bps: u64,
+    pub tx_bps: u64,
+    /// Percent of the link speed considered saturated, where sysfs reports one
+    pub util_percent: f32,
+    /// Also check veth, bridges and tunnels, whose traffic is counted again on the uplink
+    pub include_virtual: bool,
+    /// Percent of sent TCP segments retransmitted
+    pub retrans_percent: f32,
+    /// TCP connections (every state but LISTEN)
+    pub connections: u32,
+}
+
+impl NetThresholds {
+    fn saturated(&self, i: &InterfaceSample) -> bool {
+        if i.is_virtual && !self.include_virtual {
+            return false;
+        }
+        match i.util_percent() {
+            Some(util) => util >= self.util_percent,
+            None => i.rx_bps >= self.rx_bps || i.tx_bps >= self.tx_bps,
+        }
+    }
+}
+
+/// Flags a saturated interface, TCP retransmits above the threshold, or too many TCP
+/// connections, each in at least `min_hits` ticks.
+pub fn detect_sustained_network(snapshots: &[Snapshot], t: &NetThresholds, min_hits: usize) -> Option<NetFinding> {
+    let mut hits = [0usize; 3];
+    let mut busiest: Option<&InterfaceSample> = None;
+    let mut last: Option<&NetSample> = None;
+
+    for net in snapshots.iter().filter_map(|s| s.net.as_ref()) {
+        let saturated = net
+            .interfaces
+            .iter()
+            .filter(|i| t.saturated(i))
+            .max_by_key(|i| i.rx_bps.saturating_add(i.tx_bps));
+        if let Some(i) = saturated {
+            hits[0] += 1;
+            busiest = Some(i);
+        }
+        if let Some(tcp) = &net.tcp {
+            if tcp.out_segs_per_sec >= MIN_SEGS_PER_SEC && tcp.retrans_percent >= t.retrans_percent {
+                hits[1] += 1;
+            }
+            if tcp.sockets.connections() >= t.connections {
+                hits[2] += 1;
+            }
+        }
+        last = Some(net);
+    }
+
+    let causes: Vec<&'static str> = ["throughput", "retransmits", "connections"]
+        .into_iter()
+        .zip(hits)
+        .filter(|(_, n)| *n >= min_hits)
+        .map(|(cause, _)| cause)
+        .collect();
+    if causes.is_empty() {
+        return None;
+    }
+
+    let last = last?;
+    Some(NetFinding {
+        interface: busiest.filter(|_| causes.contains(&"throughput")).cloned(),
+        tcp: last.tcp.clone(),
+        processes: last.processes.clone(),
+        causes,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sampler::proc_sample;
+    use crate::source::fake::FakeProc;
+    use std::path::Path;
+
+    const DEV_HEADER: &str = "Inter-|   Receive                                                |  Transmit\n face |bytes    packets errs drop fifo frame compressed multicast|bytes    packets errs drop fifo colls carrier compressed\n";
+
+    fn dev_line(name: &str, rx: u64, tx: u64, drops: u64) -> String {
+        format!("{:>6}: {} 10 0 {} 0 0 0 0 {} 10 0 0 0 0 0 0\n", name, rx, drops, tx)
+    }
+
+    fn snmp(out: u64, retrans: u64) -> String {
+        format!(
+            "Tcp: RtoAlgorithm RtoMin RtoMax MaxConn ActiveOpens PassiveOpens AttemptFails EstabResets CurrEstab InSegs OutSegs RetransSegs InErrs OutRsts InCsumErrors\nTcp: 1 200 120000 -1 0 0 0 0 2 0 {} {} 0 0 0\n",
+            out, retrans
+        )
+    }
+
+    fn tcp_line(state: &str, inode: u64) -> String {
+        format!(
+            "   0: 0100007F:1F90 0100007F:C350 {} 00000000:00000000 00:00000000 00000000  1000        0 {} 1 0000000000000000 20 4 30 10 -1\n",
+            state, inode
+        )
+    }
+
+    #[test]
+    fn samples_interfaces_retransmits_and_socket_owners() {
+        let proc = FakeProc::new();
+        proc.file("net/dev", &format!("{}{}{}", DEV_HEADER, dev_line("lo", 0, 0, 0), dev_line("eth0", 1000, 0, 0)))
+            .file("net/snmp", &snmp(1000, 10));
+        let source = proc.source();
+        let mut sampler = NetSampler::new(&source);
+
+        proc.file("net/dev", &format!("{}{}{}", DEV_HEADER, dev_line("lo", 9000, 9000, 0), dev_line("eth0", 5000, 2000, 4)))
+            .file("net/snmp", &snmp(3000, 110))
+            .file(
+                "net/tcp",
+                &format!("  sl  local_address rem_address   st ...\n{}{}{}", tcp_line("0A", 100), tcp_line("01", 101), tcp_line("06", 0) + &tcp_line("06", 0)),
+            )
+            .file("net/tcp6", &format!("  sl  local_address ...\n{}", tcp_line("01", 102)))
+            .symlink("40/fd/3", Path::new("socket:[100]"))
+            .symlink("40/fd/4", Path::new("socket:[101]"))
+            .symlink("40/fd/5", Path::new("socket:[102]"))
+            .symlink("40/fd/6", Path::new("/dev/null"));
+        let net = sampler.sample(&source, 2.0, &[proc_sample(40, 1, "nginx")]).unwrap();
+
+        assert_eq!(net.interfaces.len(), 1);
+        let eth0 = &net.interfaces[0];
+        assert_eq!((eth0.rx_bps, eth0.tx_bps, eth0.drops_per_sec), (2000, 1000, 2.0));
+        assert_eq!((eth0.speed_bps, eth0.is_virtual), (None, false));
+
+        let tcp = net.tcp.unwrap();
+        assert_eq!((tcp.out_segs_per_sec, tcp.retrans_percent), (1000.0, 5.0));
+        assert_eq!((tcp.sockets.established, tcp.sockets.listen, tcp.sockets.time_wait), (2, 1, 2));
+
+        assert_eq!(net.processes.len(), 1);
+        assert_eq!((net.processes[0].sockets.established, net.processes[0].sockets.listen), (2, 1));
+    }
+
+    #[test]
+    fn reads_link_speed_and_virtual_interfaces_from_sysfs() {
+        let proc = FakeProc::new();
+        proc.file("1/root/sys/class/net/eth0/speed", "10000\n")
+            .file("1/root/sys/class/net/veth1/speed", "-1\n")
+            .file("1/root/sys/devices/virtual/net/veth1/type", "1\n");
+        let source = proc.source();
+
+        assert_eq!(read_link(&source, "eth0"), (Some(1_250_000_000), false));
+        assert_eq!(read_link(&source, "veth1"), (None, true));
+    }
+
+    #[test]
+    fn idle_socket_holders_are_found_by_full_scans_and_then_followed() {
+        let proc = FakeProc::new();
+        proc.file("net/dev", &format!("{}{}", DEV_HEADER, dev_line("eth0", 0, 0, 0)))
+            .file("net/tcp", &format!("  sl  local_address ...\n{}", tcp_line("01", 100)))
+            .symlink("40/fd/3", Path::new("socket:[100]"));
+        let source = proc.source();
+        let mut sampler = NetSampler::new(&source);
+        // the idle holder is far outside the CPU and I/O candidates
+        let mut procs: Vec<ProcSample> = (100..140).map(|pid| ProcSample { cpu: 1.0, read_bps: 10, ..proc_sample(pid, 1, "busy") }).collect();
+        procs.push(proc_sample(40, 1, "redis"));
+
+        for _ in 0..3 {
+            let net = sampler.sample(&source, 1.0, &procs).unwrap();
+            assert_eq!(net.processes.iter().map(|p| p.pid).collect::<Vec<_>>(), vec![40]);
+        }
+
+        // a new idle holder waits for the next full scan
+        proc.file("net/tcp", &format!("  sl  local_address ...\n{}{}", tcp_line("01", 100), tcp_line("01", 101)))
+            .symlink("41/fd/3", Path::new("socket:[101]"));
+        procs.push(proc_sample(41, 1, "memcached"));
+        for tick in 3..=FULL_SOCKET_SCAN {
+            let net = sampler.sample(&source, 1.0, &procs).unwrap();
+            assert_eq!(net.processes.iter().any(|p| p.pid == 41), tick == FULL_SOCKET_SCAN);
+        }
+    }
+
+    fn iface(name: &str, rx_bps: u64, speed_bps: Option<u64>, is_virtual: bool) -> InterfaceSample {
+        InterfaceSample {
+            name: name.to_string(),
+            rx_bps,
+            speed_bps,
+            is_virtual,
+            ..Default::default()
+        }
+    }
+
+    fn tick(interfaces: Vec<InterfaceSample>, out_segs_per_sec: f32, retrans_percent: f32, established: u32) -> Snapshot {
+        Snapshot {
+            net: Some(NetSample {
+                interfaces,
+                tcp: Some(TcpStats {
+                    out_segs_per_sec,
+                    retrans_segs_per_sec: out_segs_per_sec * retrans_percent / 100.0,
+                    retrans_percent,
+                    sockets: SocketStates { established, ..Default::default() },
+                }),
+                processes: Vec::new(),
+            }),
+            ..Default::default()
+        }
+    }
+
+    const THRESHOLDS: NetThresholds = NetThresholds {
+        rx_bps: 100_000_000,
+        tx_bps: 100_000_000,
+        util_percent: 80.0,
+        include_virtual: false,
+        retrans_percent: 5.0,
+        connections: 1000,
+    };
+
+    #[test]
+    fn throughput_is_judged_against_link_speed_and_skips_virtual_interfaces() {
+        const GBIT: u64 = 125_000_000;
+        // eth0 runs a 1 Gbit/s link at 90% in 2 of 3 ticks; docker0 moves more bytes but is virtual
+        let snapshots: Vec<Snapshot> = [112_500_000, 112_500_000, 10_000_000]
+            .into_iter()
+            .map(|rx| tick(vec![iface("eth0", rx, Some(GBIT), false), iface("docker0", 500_000_000, None, true)], 0.0, 0.0, 10))
+            .collect();
+
+        let finding = detect_sustained_network(&snapshots, &THRESHOLDS, 2).unwrap();
+        assert_eq!(finding.causes, vec!["throughput"]);
+        assert_eq!(finding.interface.map(|i| i.name), Some("eth0".to_string()));
+        assert!(detect_sustained_network(&snapshots, &THRESHOLDS, 3).is_none());
+
+        // 12 MB/s is far under the fixed 100 MB/s threshold, but fills 96% of a 100 Mbit/s (12.5 MB/s) link
+        let slow_link = vec![tick(vec![iface("eth0", 12_000_000, Some(12_500_000), false)], 0.0, 0.0, 10); 3];
+        assert!(detect_sustained_network(&slow_link, &THRESHOLDS, 3).is_some());
+
+        let with_virtual = NetThresholds { include_virtual: true, ..THRESHOLDS };
+        let finding = detect_sustained_network(&snapshots, &with_virtual, 3).unwrap();
+        assert_eq!(finding.interface.map(|i| i.name), Some("docker0".to_string()));
+    }
+
+    #[test]
+    fn retransmits_need_enough_segments_and_connections_their_own_threshold() {
+        // 20% retransmits of a trickle says nothing about the network
+        let trickle = vec![tick(Vec::new(), MIN_SEGS_PER_SEC / 2.0, 20.0, 10); 3];
+        assert!(detect_sustained_network(&trickle, &THRESHOLDS, 2).is_none());
+
+        let lossy = vec![
+            tick(Vec::new(), 1000.0, 6.0, 1500),
+            tick(Vec::new(), 1000.0, 2.0, 1500),
+            tick(Vec::new(), 1000.0, 8.0, 999),
+        ];
+        assert_eq!(detect_sustained_network(&lossy, &THRESHOLDS, 2).unwrap().causes, vec!["retransmits", "connections"]);
+        assert!(detect_sustained_network(&lossy, &THRESHOLDS, 3).is_none());
+    }
+}
@@ -0,0 +1,239 @@
+// OOM killer: who the kernel would pick next (oom_score) and who it already killed (kernel log).
+use crate::sampler::ProcSample;
+use crate::source::Source;
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::{ErrorKind, Read, Seek, SeekFrom};
+use std::os::unix::fs::OpenOptionsExt;
+
+/// Kernel log read when `--kmsg-file` is not given.
+pub const DEFAULT_KMSG: &str = "/dev/kmsg";
+
+/// Recent kills kept for the report.
+const MAX_KILLS: usize = 10;
+
+/// OOM-killer badness of one process; the highest `oom_score` is killed first.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OomScore {
+    pub pid: u32,
+    pub name: String,
+    pub oom_score: i32,
+    /// -1000 exempts the process, +1000 makes it the first choice
+    pub oom_score_adj: i32,
+}
+
+/// Reads `oom_score`/`oom_score_adj` for the `limit` largest processes by memory, in the order
+/// the OOM killer would pick them.
+pub fn read_scores(source: &Source, procs: &[ProcSample], limit: usize) -> Vec<OomScore> {
+    let mut by_mem: Vec<&ProcSample> = procs.iter().collect();
+    by_mem.sort_by(|a, b| b.mem.partial_cmp(&a.mem).unwrap_or(std::cmp::Ordering::Equal));
+
+    let mut scores: Vec<OomScore> = by_mem
+        .into_iter()
+        .take(limit)
+        .filter_map(|p| {
+            let read = |file: &str| source.read_pid(p.pid, file)?.trim().parse::<i32>().ok();
+            Some(OomScore {
+                pid: p.pid,
+                name: p.name.clone(),
+                oom_score: read("oom_score")?,
+                oom_score_adj: read("oom_score_adj").unwrap_or(0),
+            })
+        })
+        .collect();
+    scores.sort_by_key(|s| std::cmp::Reverse(s.oom_score));
+    scores
+}
+
+/// One "Killed process" record from the kernel log.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OomKill {
+    pub pid: u32,
+    pub name: String,
+    /// Kernel timestamp (seconds since boot), when the log line carries one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub uptime_secs: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub anon_rss_kb: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub oom_score_adj: Option<i32>,
+    /// Set for a cgroup memory limit rather than a system-wide OOM
+    pub cgroup_limit: bool,
+}
+
+impl OomKill {
+    /// One-line description for continuous mode.
+    pub fn describe(&self) -> String {
+        let mut line = format!("{} (PID {}) killed by the OOM killer", self.name, self.pid);
+        if self.cgroup_limit {
+            line.push_str(" at its cgroup memory limit");
+        }
+        if let Some(kb) = self.anon_rss_kb {
+            line.push_str(&format!(", anon-rss {:.1} MiB", kb as f64 / 1024.0));
+        }
+        line
+    }
+}
+
+/// Parses one kernel log line in `/dev/kmsg` ("6,1234,5678901234,-;...") or dmesg
+/// ("[ 5678.901234] ...") format.
+fn parse_kill(line: &str) -> Option<OomKill> {
+    let (uptime_secs, message) = if let Some((header, message)) = line.split_once(';')
+        && !header.contains(' ')
+    {
+        let usec: Option<f64> = header.split(',').nth(2).and_then(|t| t.parse().ok());
+        (usec.map(|u| u / 1e6), message)
+    } else if let Some(rest) = line.trim_start().strip_prefix('[')
+        && let Some((ts, message)) = rest.split_once(']')
+    {
+        (ts.trim().parse().ok(), message)
+    } else {
+        (None, line)
+    };
+
+    let (before, rest) = message.split_once("Killed process ")?;
+    let (pid, rest) = rest.split_once(' ')?;
+    let name = rest.strip_prefix('(')?.split_once(')')?.0;
+    let field = |key: &str| {
+        rest.split([' ', ',']).find_map(|f| f.strip_prefix(key)).map(|v| v.trim_end_matches("kB"))
+    };
+
+    Some(OomKill {
+        pid: pid.parse().ok()?,
+        name: name.to_string(),
+        uptime_secs,
+        anon_rss_kb: field("anon-rss:").and_then(|v| v.parse().ok()),
+        oom_score_adj: field("oom_score_adj:").and_then(|v| v.parse().ok()),
+        cgroup_limit: before.contains("Memory cgroup"),
+    })
+}
+
+fn open_dev_kmsg(path: &str) -> std::io::Result<File> {
+    OpenOptions::new().read(true).custom_flags(libc::O_NONBLOCK).open(path)
+}
+
+/// Every `/dev/kmsg` record after the file's cursor: each read returns one record, EAGAIN marks
+/// the end.
+fn drain_dev_kmsg(file: &mut File) -> std::io::Result<String> {
+    let mut out = String::new();
+    let mut buf = vec![0u8; 8192];
+
+    loop {
+        match file.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => {
+                out.push_str(&String::from_utf8_lossy(&buf[..n]));
+                if !out.ends_with('\n') {
+                    out.push('\n');
+                }
+            }
+            // EPIPE: records were overwritten while reading; keep going from the next one
+            Err(e) if e.raw_os_error() == Some(libc::EPIPE) => continue,
+            Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+            Err(e) => return Err(e),
+        }
+    }
+
+    Ok(out)
+}
+
+/// The most recent OOM kills in a kernel log; `/dev/kmsg` needs CAP_SYSLOG (or
+/// `kernel.dmesg_restrict=0`), so an unreadable log simply yields none.
+pub fn read_kills(path: &str) -> Vec<OomKill> {
+    let contents = if path == DEFAULT_KMSG {
+        open_dev_kmsg(path).and_then(|mut f| drain_dev_kmsg(&mut f)).ok()
+    } else {
+        std::fs::read_to_string(path).ok()
+    };
+
+    let kills: Vec<OomKill> = contents.unwrap_or_default().lines().filter_map(parse_kill).collect();
+    kills[kills.len().saturating_sub(MAX_KILLS)..].to_vec()
+}
+
+/// Follows a kernel log from the moment it is created, for the continuous modes: an open
+/// `/dev/kmsg` keeps its own cursor, a saved log is followed by byte offset like `tail -f`.
+pub struct KillWatcher {
+    path: String,
+    kmsg: Option<File>,
+    offset: u64,
+}
+
+impl KillWatcher {
+    /// Skips the history already in the log; `read_kills` reports that.
+    pub fn new(path: &str) -> Self {
+        let mut watcher = KillWatcher { path: path.to_string(), kmsg: None, offset: 0 };
+        if path == DEFAULT_KMSG {
+            watcher.kmsg = open_dev_kmsg(path).ok();
+        }
+        watcher.poll();
+        watcher
+    }
+
+    /// Kills logged since the previous call.
+    pub fn poll(&mut self) -> Vec<OomKill> {
+        let contents = match self.kmsg.as_mut() {
+            Some(file) => drain_dev_kmsg(file).ok(),
+            None if self.path == DEFAULT_KMSG => None,
+            None => self.read_appended(),
+        };
+        contents.unwrap_or_default().lines().filter_map(parse_kill).collect()
+    }
+
+    /// Whole lines appended to a saved log since the last read; a shorter file was rotated.
+    fn read_appended(&mut self) -> Option<String> {
+        let mut file = File::open(&self.path).ok()?;
+        if file.metadata().ok()?.len() < self.offset {
+            self.offset = 0;
+        }
+        file.seek(SeekFrom::Start(self.offset)).ok()?;
+        let mut buf = Vec::new();
+        file.read_to_end(&mut buf).ok()?;
+
+        let complete = buf.iter().rposition(|&b| b == b'\n').map_or(0, |i| i + 1);
+        self.offset += complete as u64;
+        Some(String::from_utf8_lossy(&buf[..complete]).into_owned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::fake::FakeProc;
+
+    #[test]
+    fn parses_kills_from_kmsg_and_dmesg_lines() {
+        let kmsg = "3,1042,5678901234,-;Out of memory: Killed process 4242 (stress) total-vm:2150000kB, \
+                    anon-rss:2097152kB, file-rss:4kB, shmem-rss:0kB, UID:1000 pgtables:4200kB oom_score_adj:0";
+        let kill = parse_kill(kmsg).unwrap();
+        assert_eq!((kill.pid, kill.name.as_str(), kill.anon_rss_kb, kill.oom_score_adj), (4242, "stress", Some(2097152), Some(0)));
+        assert_eq!((kill.uptime_secs, kill.cgroup_limit), (Some(5678.901234), false));
+
+        let dmesg = "[  812.503311] Memory cgroup out of memory: Killed process 977 (java) total-vm:100kB, anon-rss:50kB";
+        let kill = parse_kill(dmesg).unwrap();
+        assert_eq!((kill.pid, kill.name.as_str(), kill.uptime_secs, kill.cgroup_limit), (977, "java", Some(812.503311), true));
+
+        assert!(parse_kill("[  1.0] usb 1-1: new high-speed USB device").is_none());
+    }
+
+    #[test]
+    fn watcher_reports_only_kills_appended_after_it_started() {
+        let log = FakeProc::new();
+        let line = |pid: u32| format!("[  9.0] Out of memory: Killed process {} (stress) anon-rss:1024kB\n", pid);
+        log.file("kern.log", &line(1));
+
+        let mut watcher = KillWatcher::new(log.path("kern.log").to_str().unwrap());
+        assert!(watcher.poll().is_empty());
+
+        // a half-written line waits for its newline
+        let partial = line(2);
+        log.file("kern.log", &format!("{}{}", line(1), &partial[..20]));
+        assert!(watcher.poll().is_empty());
+        log.file("kern.log", &format!("{}{}{}", line(1), partial, line(3)));
+        assert_eq!(watcher.poll().iter().map(|k| k.pid).collect::<Vec<_>>(), vec![2, 3]);
+        assert!(watcher.poll().is_empty());
+
+        // rotated
+        log.file("kern.log", &line(4));
+        assert_eq!(watcher.poll().iter().map(|k| k.pid).collect::<Vec<_>>(), vec![4]);
+    }
+}
@@ -0,0 +1,234 @@
+// Pressure Stall Information: how much of the time tasks were stalled waiting on CPU, memory or IO.
+use serde::{Deserialize, Serialize};
+
+use std::fs;
+use std::path::Path;
+
+use crate::sampler::Snapshot;
+use crate::source::Source;
+
+/// One `some`/`full` line of a pressure file.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PsiLine {
+    pub avg10: f32,
+    pub avg60: f32,
+    pub avg300: f32,
+    /// Cumulative stall time in microseconds
+    pub total: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PsiResource {
+    pub some: PsiLine,
+    /// Absent for CPU on kernels before 5.13
+    pub full: Option<PsiLine>,
+}
+
+/// System-wide pressure; `None` resources are missing from this kernel.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Psi {
+    pub cpu: Option<PsiResource>,
+    pub memory: Option<PsiResource>,
+    pub io: Option<PsiResource>,
+}
+
+impl Psi {
+    pub fn resources(&self) -> [(&'static str, Option<&PsiResource>); 3] {
+        [
+            ("cpu", self.cpu.as_ref()),
+            ("memory", self.memory.as_ref()),
+            ("io", self.io.as_ref()),
+        ]
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PsiThresholds {
+    pub some_avg10: f32,
+    pub some_avg60: f32,
+    pub full_avg10: f32,
+    pub full_avg60: f32,
+}
+
+/// The stall that tripped the detector.
+#[derive(Debug, Serialize)]
+pub struct PsiSample {
+    pub resource: String,
+    /// "some" (at least one task stalled) or "full" (all non-idle tasks stalled)
+    pub kind: String,
+    pub window: String,
+    pub value: f32,
+    pub threshold: f32,
+}
+
+fn parse_line(rest: &str) -> Option<PsiLine> {
+    let mut line = PsiLine::default();
+
+    for field in rest.split_whitespace() {
+        let (key, value) = field.split_once('=')?;
+        match key {
+            "avg10" => line.avg10 = value.parse().ok()?,
+            "avg60" => line.avg60 = value.parse().ok()?,
+            "avg300" => line.avg300 = value.parse().ok()?,
+            "total" => line.total = value.parse().ok()?,
+            _ => {}
+        }
+    }
+
+    Some(line)
+}
+
+/// Parses a pressure file (`/proc/pressure/*` or a cgroup's `*.pressure`).
+pub fn parse_pressure(contents: &str) -> Option<PsiResource> {
+    let mut some = None;
+    let mut full = None;
+
+    for line in contents.lines() {
+        if let Some(rest) = line.strip_prefix("some ") {
+            some = parse_line(rest);
+        } else if let Some(rest) = line.strip_prefix("full ") {
+            full = parse_line(rest);
+        }
+    }
+
+    Some(PsiResource { some: some?, full })
+}
+
+/// Reads `/proc/pressure/{cpu,memory,io}`; `None` when the kernel lacks PSI (or it is disabled).
+pub fn read_psi(source: &Source) -> Option<Psi> {
+    collect(|name| source.read(&format!("pressure/{}", name)))
+}
+
+/// Reads a cgroup v2 directory's `{cpu,memory,io}.pressure`; `None` when none are present.
+pub fn read_cgroup_psi(dir: &Path) -> Option<Psi> {
+    collect(|name| fs::read_to_string(dir.join(format!("{}.pressure", name))).ok())
+}
+
+fn collect(read: impl Fn(&str) -> Option<String>) -> Option<Psi> {
+    let parse = |name: &str| read(name).and_then(|c| parse_pressure(&c));
+    let psi = Psi {
+        cpu: parse("cpu"),
+        memory: parse("memory"),
+        io: parse("io"),
+    };
+
+    if psi.cpu.is_none() && psi.memory.is_none() && psi.io.is_none() {
+        None
+    } else {
+        Some(psi)
+    }
+}
+
+/// Worst threshold breach in one snapshot, measured as value / threshold.
+fn worst_breach(psi: &Psi, t: &PsiThresholds) -> Option<PsiSample> {
+    let mut worst: Option<(f32, PsiSample)> = None;
+
+    for (resource, res) in psi.resources() {
+        let Some(res) = res else {
+            continue;
+        };
+
+        let mut checks = vec![
+            ("some", "avg10", res.some.avg10, t.some_avg10),
+            ("some", "avg60", res.some.avg60, t.some_avg60),
+        ];
+        if let Some(full) = &res.full {
+            checks.push(("full", "avg10", full.avg10, t.full_avg10));
+            checks.push(("full", "avg60", full.avg60, t.full_avg60));
+        }
+
+        for (kind, window, value, threshold) in checks {
+            if value <= threshold {
+                continue;
+            }
+
+            let ratio = value / threshold.max(f32::EPSILON);
+            if worst.as_ref().is_none_or(|(r, _)| ratio > *r) {
+                worst = Some((
+                    ratio,
+                    PsiSample {
+                        resource: resource.to_string(),
+                        kind: kind.to_string(),
+                        window: window.to_string(),
+                        value,
+                        threshold,
+                    },
+                ));
+            }
+        }
+    }
+
+    worst.map(|(_, sample)| sample)
+}
+
+pub fn detect_sustained_psi(
+    snapshots: &[Snapshot],
+    thresholds: &PsiThresholds,
+    min_hits: usize,
+) -> Option<PsiSample> {
+    let mut hits = 0;
+    let mut last = None;
+
+    for snapshot in snapshots {
+        if let Some(psi) = &snapshot.psi
+            && let Some(sample) = worst_breach(psi, thresholds)
+        {
+            hits += 1;
+            last = Some(sample);
+        }
+    }
+
+    if hits >= min_hits {
+        last
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::fake::FakeProc;
+
+    const MEMORY: &str = "some avg10=35.50 avg60=12.00 avg300=3.10 total=123456\n\
+                          full avg10=22.00 avg60=4.00 avg300=1.00 total=65432\n";
+
+    fn thresholds() -> PsiThresholds {
+        PsiThresholds {
+            some_avg10: 25.0,
+            some_avg60: 10.0,
+            full_avg10: 10.0,
+            full_avg60: 5.0,
+        }
+    }
+
+    #[test]
+    fn parses_some_and_full() {
+        let res = parse_pressure(MEMORY).unwrap();
+        assert_eq!(res.some.avg10, 35.5);
+        assert_eq!(res.some.total, 123456);
+        assert_eq!(res.full.unwrap().avg10, 22.0);
+    }
+
+    #[test]
+    fn missing_pressure_dir_means_unavailable() {
+        let proc = FakeProc::new();
+        proc.file("meminfo", "");
+        assert!(read_psi(&proc.source()).is_none());
+
+        proc.file("pressure/memory", MEMORY);
+        let psi = read_psi(&proc.source()).unwrap();
+        assert!(psi.cpu.is_none() && psi.memory.is_some());
+    }
+
+    #[test]
+    fn reports_the_worst_breach() {
+        let psi = Psi {
+            memory: parse_pressure(MEMORY),
+            ..Psi::default()
+        };
+
+        let sample = worst_breach(&psi, &thresholds()).unwrap();
+        assert_eq!((sample.resource.as_str(), sample.kind.as_str(), sample.window.as_str()), ("memory", "full", "avg10"));
+    }
+}
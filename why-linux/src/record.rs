@@ -0,0 +1,43 @@
+// Newline-delimited snapshot recording, so detection can be rerun offline with other thresholds.
+use crate::sampler::Snapshot;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+
+pub struct Recorder {
+    out: BufWriter<File>,
+}
+
+impl Recorder {
+    pub fn create(path: &str) -> io::Result<Self> {
+        Ok(Recorder {
+            out: BufWriter::new(File::create(path)?),
+        })
+    }
+
+    /// Appends one snapshot as a single JSON line; flushed so a killed `--watch` keeps its data.
+    pub fn write(&mut self, snapshot: &Snapshot) -> io::Result<()> {
+        serde_json::to_writer(&mut self.out, snapshot)?;
+        self.out.write_all(b"\n")?;
+        self.out.flush()
+    }
+}
+
+/// Reads every snapshot from a recording; blank lines are ignored.
+pub fn load(path: &str) -> io::Result<Vec<Snapshot>> {
+    let reader = BufReader::new(File::open(path)?);
+    let mut snapshots = Vec::new();
+
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let snapshot = serde_json::from_str(&line).map_err(|e| {
+            io::Error::new(io::ErrorKind::InvalidData, format!("line {}: {}", i + 1, e))
+        })?;
+        snapshots.push(snapshot);
+    }
+
+    Ok(snapshots)
+}
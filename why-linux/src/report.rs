@@ -2,6 +2,8 @@
 use crate::cpu::CpuSample;
 use crate::mem::MemSample;
 use crate::disk::DiskSample;
+use crate::sampler::Snapshot;
+use crate::{cpu, disk, mem};
 use serde::Serialize;
 use std::fs::File;
 use std::io::Write;
@@ -15,11 +17,29 @@ pub struct TimelineSample {
     pub disk: Option<DiskSample>,
 }
 
+impl From<&Snapshot> for TimelineSample {
+    fn from(snapshot: &Snapshot) -> Self {
+        TimelineSample {
+            ts: snapshot.ts,
+            cpu: cpu::get_top_cpu(snapshot),
+            mem: mem::get_top_mem(snapshot),
+            disk: disk::get_top_mount(snapshot),
+        }
+    }
+}
+
+/// Makes JSON safe to embed in a `<script>` block: file, process and cgroup names are picked by
+/// local users, and a literal `</script>` in one would end the block.
+fn script_safe(json: &str) -> String {
+    json.replace('<', "\\u003c").replace('>', "\\u003e").replace('&', "\\u0026")
+}
+
 pub fn write_html_report(path: &str, samples: &[TimelineSample], summary_json: &str) -> std::io::Result<()> {
     let mut f = File::create(path)?;
 
-    let samples_json = serde_json::to_string_pretty(samples).unwrap_or_else(|_| "[]".to_string());
+    let samples_json = script_safe(&serde_json::to_string_pretty(samples).unwrap_or_else(|_| "[]".to_string()));
     // assume summary_json is valid JSON
+    let summary_json = script_safe(summary_json);
     let ts = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
 
     let html = format!(r##"<!doctype html>
@@ -60,6 +80,32 @@ pub fn write_html_report(path: &str, samples: &[TimelineSample], summary_json: &
   <h3>Summary</h3>
   <div id="summary-cards"></div>
 
+  <h3>Memory growth</h3>
+  <div id="mem-growth"></div>
+
+  <h3>Mounts</h3>
+  <div id="mounts"></div>
+
+  <h3>Disk scan</h3>
+  <div id="disk-scan"></div>
+
+  <h3>Block devices</h3>
+  <div id="devices"></div>
+
+  <h3>Network</h3>
+  <div id="net"></div>
+
+  <h3>I/O from exited processes</h3>
+  <div id="exitedIo"></div>
+
+  <h3>CPU cores</h3>
+  <div id="cores"></div>
+  <div id="load"></div>
+  <div id="thermal"></div>
+
+  <h3>Hot threads</h3>
+  <div id="threads"></div>
+
   <h3>Top offenders</h3>
   <div id="offenders"></div>
 
@@ -99,20 +145,92 @@ pub fn write_html_report(path: &str, samples: &[TimelineSample], summary_json: &
     const memSeries = samples.map(s=> s.mem ? s.mem.mem : 0);
     const diskSeries = samples.map(s=> s.disk ? s.disk.used_percent : 0);
 
+    // names and paths come from the monitored system and must not be parsed as markup
+    function esc(v) {{
+      return String(v ?? '').replace(/[&<>"']/g, c => ({{ '&': '&amp;', '<': '&lt;', '>': '&gt;', '"': '&quot;', "'": '&#39;' }})[c]);
+    }}
+
     function fmt(v) {{
       return (typeof v === 'number' && isFinite(v)) ? v.toFixed(1) : '0.0';
     }}
 
+    function renderPsi(psi) {{
+      if (!psi) return 'unavailable';
+      return Object.keys(psi).map(function(name) {{
+        const r = psi[name];
+        return name + ' some ' + fmt(r.some_avg10) + '%' + (r.full_avg10 !== undefined ? ' full ' + fmt(r.full_avg10) + '%' : '');
+      }}).join(' | ');
+    }}
+
+    function renderMemComposition(mem) {{
+      const c = mem.composition;
+      const p = mem.paging;
+      const gib = kb => fmt((kb ?? 0) / 1048576) + ' GiB';
+      let out = '';
+      if (c) {{
+        out += '<div class="row"><strong>Memory composition</strong>: anon ' + gib(c.anon_kb) + ' | page cache ' + gib(c.page_cache_kb) +
+          ' | buffers ' + gib(c.buffers_kb) + ' | shmem/tmpfs ' + gib(c.shmem_kb) +
+          ' | slab ' + gib(c.slab_reclaimable_kb) + ' reclaimable, ' + gib(c.slab_unreclaimable_kb) + ' unreclaimable' +
+          ' | kernel stack ' + gib(c.kernel_stack_kb) + ' | page tables ' + gib(c.page_tables_kb) +
+          ' | hugepages ' + gib(c.hugepages_kb) + ' | swap ' + gib(c.swap_used_kb) + ' of ' + gib(c.swap_total_kb) + '</div>';
+      }}
+      if (p) {{
+        out += '<div class="row"><strong>Paging</strong>: swap in ' + fmt(p.swap_in_bps_avg / 1e6) + ' MB/s (max ' + fmt(p.swap_in_bps_max / 1e6) + ')' +
+          ' | swap out ' + fmt(p.swap_out_bps_avg / 1e6) + ' MB/s (max ' + fmt(p.swap_out_bps_max / 1e6) + ')' +
+          ' | major faults ' + fmt(p.major_faults_avg) + '/s (max ' + fmt(p.major_faults_max) + ')</div>';
+      }}
+      const oom = mem.oom;
+      if (oom) {{
+        const scores = (oom.scores || []).map(s => esc(s.name) + ' (PID ' + s.pid + ') score ' + s.oom_score + ' adj ' + s.oom_score_adj).join(' | ');
+        if (scores) out += '<div class="row"><strong>OOM kill order</strong>: ' + scores + '</div>';
+        if (oom.kill_total !== null && oom.kill_total !== undefined) {{
+          out += '<div class="row"><strong>OOM kills</strong>: ' + oom.kill_total + ' since boot, ' + (oom.kills_in_window || 0) + ' during this window</div>';
+        }}
+        const kills = (oom.kills || []).slice().reverse().map(k =>
+          '<tr><td>' + esc(k.name) + '</td><td>' + k.pid + '</td><td>' + (k.anon_rss_kb !== undefined ? fmt(k.anon_rss_kb / 1024) : '') + '</td>' +
+          '<td>' + (k.cgroup_limit ? 'cgroup limit' : 'system') + '</td><td>' + (k.uptime_secs !== undefined ? fmt(k.uptime_secs) : '') + '</td></tr>'
+        ).join('');
+        if (kills) {{
+          out += '<div class="row"><strong>Recent OOM kills</strong><table>' +
+            '<thead><tr><th>Name</th><th>PID</th><th>Anon RSS (MiB)</th><th>Scope</th><th>Seconds after boot</th></tr></thead>' +
+            '<tbody>' + kills + '</tbody></table></div>';
+        }}
+      }}
+      return out;
+    }}
+
     function renderSummary() {{
       const el = document.getElementById('summary-cards');
       if (!el || !summary) return;
       const cpu = summary.cpu || Object();
       const mem = summary.mem || Object();
       const disk = summary.disk || Object();
+      const st = cpu.states || Object();
       el.innerHTML =
         '<div class="row"><strong>CPU</strong>: avg ' + fmt(cpu.avg) + '% | max ' + fmt(cpu.max) + '%</div>' +
+        '<div class="row"><strong>CPU states</strong>: user ' + fmt(st.user) + '% | system ' + fmt(st.system) + '% | iowait ' + fmt(st.iowait) + '% | irq ' + fmt(st.irq) + '% | softirq ' + fmt(st.softirq) + '% | steal ' + fmt(st.steal) + '% | guest ' + fmt(st.guest) + '% | idle ' + fmt(st.idle) + '%</div>' +
         '<div class="row"><strong>Memory</strong>: avg ' + fmt(mem.avg) + '% | max ' + fmt(mem.max) + '% | system avg ' + fmt(mem.system_avg) + '% | system max ' + fmt(mem.system_max) + '%</div>' +
-        '<div class="row"><strong>Disk</strong>: avg ' + fmt(disk.avg) + '% | max ' + fmt(disk.max) + '%</div>';
+        renderMemComposition(mem) +
+        '<div class="row"><strong>Disk</strong>: avg ' + fmt(disk.avg) + '% | max ' + fmt(disk.max) + '%</div>' +
+        '<div class="row"><strong>Pressure (max avg10)</strong>: ' + renderPsi(summary.psi) + '</div>';
+    }}
+
+    function duration(secs) {{
+      if (secs < 60) return secs + 's';
+      if (secs < 3600) return Math.floor(secs / 60) + 'm';
+      if (secs < 86400) return Math.floor(secs / 3600) + 'h ' + Math.floor(secs % 3600 / 60) + 'm';
+      return Math.floor(secs / 86400) + 'd ' + Math.floor(secs % 86400 / 3600) + 'h';
+    }}
+
+    function renderGrowth() {{
+      const el = document.getElementById('mem-growth');
+      if (!el) return;
+      const g = data.mem_growth;
+      if (!g) {{ el.innerHTML = '<div class="row">no steady growth detected</div>'; return; }}
+      el.innerHTML =
+        '<div class="row"><strong>' + esc(g.name) + '</strong> (PID ' + g.pid + ') +' + fmt(g.rate_mb_per_min) + ' MB/min for ' + duration(g.duration_secs) +
+        ' (' + fmt(g.start_kb / 1024) + ' → ' + fmt(g.end_kb / 1024) + ' MiB)' +
+        (g.oom_eta_secs !== undefined ? '; out of memory in ~' + duration(g.oom_eta_secs) + ' at this rate' : '') + '</div>';
     }}
 
     function renderOffenders() {{
@@ -120,12 +238,18 @@ pub fn write_html_report(path: &str, samples: &[TimelineSample], summary_json: &
       if (!el) return;
       const cpu = offenders.cpu || [];
       const mem = offenders.mem || [];
+      const cgroups = offenders.cgroups || [];
+      function cgroupLabel(c) {{
+        if (!c) return '';
+        return esc(c.container ? 'container ' + c.container : (c.unit || c.slice || c.path));
+      }}
       function rows(title, items) {{
         if (!items.length) return '<div class="row"><strong>' + title + '</strong>: none</div>';
         const list = items.map(i =>
           '<tr>' +
-            '<td>' + i.name + '</td>' +
+            '<td>' + esc(i.name) + '</td>' +
             '<td>' + i.pid + '</td>' +
+            '<td>' + cgroupLabel(i.cgroup) + '</td>' +
             '<td>' + fmt(i.sum ?? 0) + '</td>' +
             '<td>' + fmt(i.avg ?? 0) + '</td>' +
             '<td>' + fmt(i.max ?? 0) + '</td>' +
@@ -133,12 +257,165 @@ pub fn write_html_report(path: &str, samples: &[TimelineSample], summary_json: &
         ).join('');
         return '<div class="row"><strong>' + title + '</strong>' +
           '<table>' +
-            '<thead><tr><th>Name</th><th>PID</th><th>Sum</th><th>Avg</th><th>Max</th></tr></thead>' +
+            '<thead><tr><th>Name</th><th>PID</th><th>Cgroup</th><th>Sum</th><th>Avg</th><th>Max</th></tr></thead>' +
             '<tbody>' + list + '</tbody>' +
           '</table>' +
         '</div>';
       }}
-      el.innerHTML = rows('CPU offenders', cpu) + rows('Memory offenders', mem);
+      let cgroupRows = '';
+      if (cgroups.length) {{
+        const list = cgroups.map(c =>
+          '<tr>' +
+            '<td title="' + esc(c.path) + '">' + cgroupLabel(c) + '</td>' +
+            '<td>' + fmt(c.cpu_avg ?? 0) + '</td>' +
+            '<td>' + fmt(c.cpu_max ?? 0) + '</td>' +
+            '<td>' + fmt((c.mem_max_bytes ?? 0) / 1048576) + '</td>' +
+            '<td>' + (c.read_bps_avg ?? 0) + '</td>' +
+            '<td>' + (c.write_bps_avg ?? 0) + '</td>' +
+            '<td>' + Object.entries(c.pressure_max || Object()).map(([r, v]) => esc(r) + ' ' + fmt(v) + '%').join(', ') + '</td>' +
+          '</tr>'
+        ).join('');
+        cgroupRows = '<div class="row"><strong>Cgroups</strong>' +
+          '<table>' +
+            '<thead><tr><th>Cgroup</th><th>CPU avg</th><th>CPU max</th><th>Mem max (MiB)</th><th>Read B/s</th><th>Write B/s</th><th>Pressure (some avg10 max)</th></tr></thead>' +
+            '<tbody>' + list + '</tbody>' +
+          '</table>' +
+        '</div>';
+      }}
+      el.innerHTML = rows('CPU offenders', cpu) + rows('Memory offenders', mem) + cgroupRows;
+    }}
+
+    function renderMounts() {{
+      const el = document.getElementById('mounts');
+      if (!el) return;
+      const mounts = (summary.disk || Object()).mounts || [];
+      if (!mounts.length) {{ el.innerHTML = '<div class="row">none</div>'; return; }}
+      const f = data.disk_fill;
+      const forecast = f
+        ? '<div class="row"><strong>Filling up</strong>: ' + esc(f.mount) + ' (' + esc(f.fs) + ') ' + fmt(f.used_percent) + '% used, +' +
+          fmt(f.fill_bytes_per_sec * 60 / 1e6) + ' MB/min, full in ~' + duration(f.full_in_secs) + '</div>'
+        : '';
+      const gib = b => fmt((b ?? 0) / 1073741824);
+      const rows = mounts.map(m =>
+        '<tr><td>' + esc(m.mount) + '</td><td>' + esc(m.fs) + '</td><td>' + esc(m.fstype) + (m.read_only ? ' (ro)' : '') + '</td>' +
+        '<td>' + fmt(m.used_percent) + '%</td><td>' + gib(m.avail_bytes) + '</td><td>' + gib(m.total_bytes) + '</td>' +
+        '<td>' + (m.inodes_used_percent !== null && m.inodes_used_percent !== undefined ? fmt(m.inodes_used_percent) + '%' : '') + '</td></tr>'
+      ).join('');
+      el.innerHTML = forecast + '<table><thead><tr><th>Mount</th><th>Source</th><th>Type</th><th>Used</th><th>Free (GiB)</th><th>Size (GiB)</th><th>Inodes</th></tr></thead>' +
+        '<tbody>' + rows + '</tbody></table>';
+    }}
+
+    function renderDiskScan() {{
+      const el = document.getElementById('disk-scan');
+      if (!el) return;
+      const scan = data.disk_scan;
+      if (!scan) {{ el.innerHTML = '<div class="row">not run (use --disk-scan)</div>'; return; }}
+      const size = b => b >= 1073741824 ? fmt(b / 1073741824) + ' GiB' : fmt(b / 1048576) + ' MiB';
+      function table(title, items, cols) {{
+        if (!items || !items.length) return '';
+        const head = cols.map(c => '<th>' + c[0] + '</th>').join('');
+        const body = items.map(i => '<tr>' + cols.map(c => '<td>' + c[1](i) + '</td>').join('') + '</tr>').join('');
+        return '<div class="row"><strong>' + title + '</strong><table><thead><tr>' + head + '</tr></thead><tbody>' + body + '</tbody></table></div>';
+      }}
+      const pathCols = [['Path', i => esc(i.path)], ['Size', i => size(i.bytes)]];
+      el.innerHTML =
+        '<div class="row">' + esc(scan.mount) + (scan.truncated ? ' (stopped early, sizes are lower bounds)' : '') + '</div>' +
+        table('Largest directories', scan.largest_dirs, pathCols) +
+        table('Largest files', scan.largest_files, pathCols) +
+        table('Recently grown files (last 24h)', scan.recently_grown, pathCols) +
+        table('Known culprits', scan.culprits, [['What', c => esc(c.what)], ['Path', c => esc(c.path)], ['Size', c => size(c.bytes)]]) +
+        table('Deleted but still open', scan.deleted_open, [['Path', d => esc(d.path)], ['Process', d => esc(d.name) + ' (PID ' + d.pid + ')'], ['Size', d => size(d.bytes)]]);
+    }}
+
+    function renderDevices() {{
+      const el = document.getElementById('devices');
+      if (!el) return;
+      const devices = summary.devices || [];
+      if (!devices.length) {{ el.innerHTML = '<div class="row">none</div>'; return; }}
+      const rows = devices.map(d =>
+        '<tr><td>' + esc(d.name) + '</td><td>' + esc((d.mounts || []).join(', ')) + '</td><td>' + fmt(d.util_avg) + '%</td><td>' + fmt(d.util_max) + '%</td>' +
+        '<td>' + fmt(d.await_ms) + '</td><td>' + fmt(d.queue_depth) + '</td><td>' + fmt(d.read_iops + d.write_iops) + '</td>' +
+        '<td>' + fmt((d.read_bps + d.write_bps) / 1e6) + '</td></tr>'
+      ).join('');
+      el.innerHTML = '<table><thead><tr><th>Device</th><th>Mounts</th><th>Util avg</th><th>Util max</th><th>Await (ms)</th><th>Queue</th><th>IOPS</th><th>MB/s</th></tr></thead>' +
+        '<tbody>' + rows + '</tbody></table>';
+    }}
+
+    function renderLoad() {{
+      const el = document.getElementById('load');
+      if (!el) return;
+      const l = summary.cpu && summary.cpu.load;
+      if (!l) {{ el.innerHTML = ''; return; }}
+      el.innerHTML = '<div class="row">Load avg ' + fmt(l.load1) + ' ' + fmt(l.load5) + ' ' + fmt(l.load15) +
+        ' (per CPU ' + l.per_cpu.map(fmt).join(' ') + ') | runnable avg ' + fmt(l.runnable_avg) + ' max ' + l.runnable_max +
+        ' | D state avg ' + fmt(l.d_state_avg) + ' max ' + l.d_state_max + '</div>';
+    }}
+
+    function renderThermal() {{
+      const el = document.getElementById('thermal');
+      if (!el) return;
+      const t = summary.cpu && summary.cpu.thermal;
+      if (!t) {{ el.innerHTML = ''; return; }}
+      const parts = [];
+      if (t.freq_percent_avg != null) parts.push('Clock avg ' + fmt(t.freq_percent_avg) + '% of base (slowest CPU ' + fmt(t.freq_percent_min) + '%)');
+      if (t.hottest) parts.push('hottest ' + esc(t.hottest.name) + ' ' + fmt(t.hottest.temp_c) + ' °C');
+      if (t.throttle_events != null) parts.push(t.throttle_events + ' throttle events');
+      el.innerHTML = '<div class="row">' + parts.join(' | ') + '</div>';
+    }}
+
+    function renderNet() {{
+      const el = document.getElementById('net');
+      if (!el) return;
+      const net = summary.net;
+      if (!net) {{ el.innerHTML = '<div class="row">unavailable</div>'; return; }}
+      const rows = (net.interfaces || []).map(i =>
+        '<tr><td>' + esc(i.name) + '</td><td>' + fmt(i.rx_bps_avg / 1e6) + '</td><td>' + fmt(i.rx_bps_max / 1e6) + '</td>' +
+        '<td>' + fmt(i.tx_bps_avg / 1e6) + '</td><td>' + fmt(i.tx_bps_max / 1e6) + '</td>' +
+        '<td>' + fmt(i.drops_per_sec_avg) + '</td><td>' + fmt(i.errors_per_sec_avg) + '</td></tr>'
+      ).join('');
+      const tcp = net.tcp || {{}};
+      el.innerHTML = '<table><thead><tr><th>Interface</th><th>rx MB/s avg</th><th>rx max</th><th>tx MB/s avg</th><th>tx max</th><th>Drops/s</th><th>Errors/s</th></tr></thead>' +
+        '<tbody>' + rows + '</tbody></table>' +
+        '<div class="row">TCP retransmits avg ' + fmt(tcp.retrans_percent_avg) + '% (max ' + fmt(tcp.retrans_percent_max) + '%), connections max ' + (tcp.connections_max ?? 0) + '</div>';
+    }}
+
+    function renderExitedIo() {{
+      const el = document.getElementById('exitedIo');
+      if (!el) return;
+      const exited = (summary.io && summary.io.exited) || [];
+      if (!exited.length) {{ el.innerHTML = '<div class="row">none</div>'; return; }}
+      const rows = exited.map(e =>
+        '<tr><td>' + esc(e.name || '?') + '</td><td>' + e.pid + '</td><td>' + e.ppid + '</td>' +
+        '<td>' + fmt(e.read_bytes / 1e6) + '</td><td>' + fmt(e.write_bytes / 1e6) + '</td></tr>'
+      ).join('');
+      el.innerHTML = '<table><thead><tr><th>Name</th><th>PID</th><th>Parent</th><th>Read (MB)</th><th>Written (MB)</th></tr></thead>' +
+        '<tbody>' + rows + '</tbody></table>';
+    }}
+
+    function renderThreads() {{
+      const el = document.getElementById('threads');
+      if (!el) return;
+      const cpu = data.cpu;
+      const threads = (cpu && cpu.threads) || [];
+      if (!threads.length) {{ el.innerHTML = '<div class="row">none (sampled with --threads when CPU usage is high)</div>'; return; }}
+      const rows = threads.map(t =>
+        '<tr><td>' + esc(t.name) + '</td><td>' + t.tid + '</td><td>' + fmt(t.cpu_avg) + '%</td><td>' + fmt(t.cpu_max) + '%</td></tr>'
+      ).join('');
+      el.innerHTML = '<div class="row"><strong>' + esc(cpu.name) + '</strong> (PID ' + cpu.pid + ')</div>' +
+        '<table><thead><tr><th>Thread</th><th>TID</th><th>Avg CPU</th><th>Max CPU</th></tr></thead>' +
+        '<tbody>' + rows + '</tbody></table>';
+    }}
+
+    function renderCores() {{
+      const el = document.getElementById('cores');
+      if (!el) return;
+      const cores = (summary.cpu || Object()).cores || [];
+      if (!cores.length) {{ el.innerHTML = '<div class="row">none</div>'; return; }}
+      const list = cores.map(c =>
+        '<tr><td>' + esc(c.cpu) + '</td><td>' + fmt(c.avg) + '%</td><td>' + fmt(c.max) + '%</td></tr>'
+      ).join('');
+      el.innerHTML = '<table><thead><tr><th>Core</th><th>Avg busy</th><th>Max busy</th></tr></thead>' +
+        '<tbody>' + list + '</tbody></table>';
     }}
 
     document.addEventListener('DOMContentLoaded', function() {{
@@ -146,7 +423,17 @@ pub fn write_html_report(path: &str, samples: &[TimelineSample], summary_json: &
       sparkline(memSeries, document.getElementById('mem'));
       sparkline(diskSeries, document.getElementById('disk'));
       renderSummary();
+      renderCores();
       renderOffenders();
+      renderMounts();
+      renderDevices();
+      renderNet();
+      renderLoad();
+      renderThermal();
+      renderThreads();
+      renderExitedIo();
+      renderDiskScan();
+      renderGrowth();
       document.getElementById('summary').textContent = JSON.stringify(data, null, 2);
     }});
   </script>
@@ -156,3 +443,16 @@ pub fn write_html_report(path: &str, samples: &[TimelineSample], summary_json: &
     f.write_all(html.as_bytes())?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn embedded_json_cannot_close_the_script_block() {
+        let json = serde_json::json!({ "path": "/tmp/</script><script>alert(1)</script>&" }).to_string();
+        let safe = script_safe(&json);
+        assert!(!safe.contains('<') && !safe.contains('&'));
+        assert_eq!(serde_json::from_str::<serde_json::Value>(&safe).unwrap()["path"], "/tmp/</script><script>alert(1)</script>&");
+    }
+}
@@ -0,0 +1,364 @@
+// Single-pass sampling engine: one full system snapshot per tick, shared by every detector,
+// the offender tables and the report timeline.
+use crate::blockdev::{BlockDevSampler, DeviceSample};
+use crate::cgroup::{self, CgroupSample, CgroupSampler};
+use crate::cpu::{CpuSampler, CpuStates, ThreadCpu};
+use crate::disk::{self, DiskFilter, DiskSample};
+use crate::io::{ExitedIo, IoDetail, IoSampler};
+use crate::load::{self, BlockedTask, LoadSample};
+use crate::mem::{self, MemInfo, MemMetric, ProcMem};
+use crate::net::{NetSample, NetSampler};
+use crate::oom::{self, KillWatcher, OomKill, OomScore};
+use crate::psi::{self, Psi};
+use crate::sched::{SchedRates, SchedSampler};
+use crate::source::Source;
+use crate::thermal::{ThermalSample, ThermalSampler};
+use crate::vmstat::{VmStat, VmStatSampler};
+use serde::{Deserialize, Serialize};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+/// One process as seen during a single tick; with `--group-by` it stands for a whole group.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProcSample {
+    pub pid: u32,
+    pub ppid: u32,
+    #[serde(default)]
+    pub pgrp: u32,
+    #[serde(default)]
+    pub sid: u32,
+    #[serde(default)]
+    pub uid: u32,
+    #[serde(default)]
+    pub cgroup: String,
+    pub name: String,
+    /// Scheduler state from `/proc/[pid]/stat` (R, S, D, ...); NUL in old recordings
+    #[serde(default)]
+    pub state: char,
+    /// CPU percent over the tick (100 = one core)
+    pub cpu: f32,
+    /// The `--mem-metric` figure (PSS by default) as a percent of MemTotal
+    pub mem: f32,
+    /// RSS/PSS/USS/swap breakdown behind `mem`
+    #[serde(default)]
+    pub mem_kb: ProcMem,
+    pub read_bps: u64,
+    pub write_bps: u64,
+    /// Context-switch rates and run-queue wait summed over threads; only for the busiest processes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sched: Option<SchedRates>,
+    /// Syscall and page-cache I/O rates and block-I/O delay behind `read_bps`/`write_bps`
+    #[serde(default)]
+    pub io: IoDetail,
+    /// Processes folded into this row by `--group-by`; empty for a single process
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub members: Vec<GroupMember>,
+    /// Hottest threads, only for the busiest processes with `--threads`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub threads: Vec<ThreadCpu>,
+}
+
+/// A sleeping process leading its own process group and session, for tests to fill in further.
+#[cfg(test)]
+pub fn proc_sample(pid: u32, ppid: u32, name: &str) -> ProcSample {
+    ProcSample {
+        pid,
+        ppid,
+        pgrp: pid,
+        sid: pid,
+        name: name.to_string(),
+        state: 'S',
+        ..Default::default()
+    }
+}
+
+/// One process inside a grouped row.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GroupMember {
+    pub pid: u32,
+    pub name: String,
+    pub cpu: f32,
+    pub mem: f32,
+    pub read_bps: u64,
+    pub write_bps: u64,
+}
+
+/// Fields we use from `/proc/[pid]/status`.
+#[derive(Debug, Default, PartialEq)]
+pub struct ProcStatus {
+    /// VmSwap in kB; absent for kernel threads
+    pub swap_kb: Option<u64>,
+    /// Real uid
+    pub uid: u32,
+    pub voluntary_ctxt_switches: u64,
+    pub nonvoluntary_ctxt_switches: u64,
+}
+
+pub fn read_status(source: &Source, pid: u32) -> Option<ProcStatus> {
+    parse_status(&source.read_pid(pid, "status")?)
+}
+
+/// Parses a process or thread `status` file.
+pub fn parse_status(contents: &str) -> Option<ProcStatus> {
+    let mut status = ProcStatus::default();
+
+    for line in contents.lines() {
+        if let Some(rest) = line.strip_prefix("VmSwap:") {
+            status.swap_kb = Some(rest.split_whitespace().next()?.parse().ok()?);
+        } else if let Some(rest) = line.strip_prefix("Uid:") {
+            status.uid = rest.split_whitespace().next()?.parse().ok()?;
+        } else if let Some(rest) = line.strip_prefix("voluntary_ctxt_switches:") {
+            status.voluntary_ctxt_switches = rest.trim().parse().ok()?;
+        } else if let Some(rest) = line.strip_prefix("nonvoluntary_ctxt_switches:") {
+            status.nonvoluntary_ctxt_switches = rest.trim().parse().ok()?;
+        }
+    }
+
+    Some(status)
+}
+
+/// Everything captured during one tick.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub ts: u64,
+    pub procs: Vec<ProcSample>,
+    pub mem_used_percent: Option<f32>,
+    /// MemAvailable in kB; kept next to `meminfo` so recordings made before it still replay
+    #[serde(default)]
+    pub mem_available_kb: Option<u64>,
+    /// Memory composition; None when /proc/meminfo is unreadable
+    #[serde(default)]
+    pub meminfo: Option<MemInfo>,
+    /// Swap and major-fault rates over the tick
+    #[serde(default)]
+    pub vmstat: Option<VmStat>,
+    pub mounts: Vec<DiskSample>,
+    /// Interface traffic, TCP health and socket owners; None when /proc/net/dev is unreadable
+    #[serde(default)]
+    pub net: Option<NetSample>,
+    /// Block devices from /proc/diskstats, mapped to the mounts they back
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub devices: Vec<DeviceSample>,
+    /// Aggregate `cpu` first, then one entry per core
+    #[serde(default)]
+    pub cpu_states: Vec<CpuStates>,
+    /// None when the kernel has no PSI
+    pub psi: Option<Psi>,
+    /// None when /proc/loadavg is unreadable
+    #[serde(default)]
+    pub load: Option<LoadSample>,
+    /// Processes in uninterruptible sleep, with wchan and kernel stack
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub blocked: Vec<BlockedTask>,
+    /// Temperatures, clock speed and throttle events; None when sysfs exposes none of them
+    #[serde(default)]
+    pub thermal: Option<ThermalSample>,
+    /// OOM-killer scores of the largest processes
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub oom_scores: Vec<OomScore>,
+    /// OOM kills the kernel logged during the tick, only followed in the live modes
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub oom_kills: Vec<OomKill>,
+    /// Leaf cgroup usage, only sampled with `--rank-cgroups`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cgroups: Vec<CgroupSample>,
+    /// Processes that exited since the previous tick, with the I/O they did while watched
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exited_io: Vec<ExitedIo>,
+}
+
+/// Largest processes by RSS whose PSS/USS are read from smaps_rollup each tick.
+const SMAPS_PROCS: usize = 20;
+
+/// Busiest processes whose threads' scheduler counters are read each tick.
+const SCHED_PROCS: usize = 5;
+
+/// Largest processes whose OOM scores are read each tick.
+const OOM_SCORES: usize = 5;
+
+pub struct Sampler {
+    source: Source,
+    exclude_pid: Option<u32>,
+    cpu: CpuSampler,
+    io: IoSampler,
+    vmstat: VmStatSampler,
+    devices: BlockDevSampler,
+    net: NetSampler,
+    thermal: ThermalSampler,
+    sched: SchedSampler,
+    cgroups: Option<CgroupSampler>,
+    kills: Option<KillWatcher>,
+    mem_metric: MemMetric,
+    disk_filter: DiskFilter,
+    last_tick: Instant,
+}
+
+impl Sampler {
+    /// Creates a sampler primed with the current counters; rates in the first `tick()`
+    /// cover the time since this call.
+    pub fn new(source: Source, exclude_pid: Option<u32>) -> Self {
+        Sampler {
+            cpu: CpuSampler::new(&source),
+            io: IoSampler::new(&source),
+            vmstat: VmStatSampler::new(&source),
+            devices: BlockDevSampler::new(&source),
+            net: NetSampler::new(&source),
+            thermal: ThermalSampler::new(&source),
+            sched: SchedSampler::new(),
+            cgroups: None,
+            kills: None,
+            mem_metric: MemMetric::Pss,
+            disk_filter: DiskFilter::default(),
+            source,
+            exclude_pid,
+            last_tick: Instant::now(),
+        }
+    }
+
+    /// Ranks memory by this metric instead of PSS.
+    pub fn with_mem_metric(mut self, metric: MemMetric) -> Self {
+        self.mem_metric = metric;
+        self
+    }
+
+    /// Only samples mounts allowed by `--disk-include`/`--disk-exclude`.
+    pub fn with_disk_filter(mut self, filter: DiskFilter) -> Self {
+        self.disk_filter = filter;
+        self
+    }
+
+    /// Also reports the `n` hottest threads of the busiest processes on each tick.
+    pub fn with_threads(mut self, n: usize) -> Self {
+        self.cpu = self.cpu.with_threads(&self.source, n);
+        self
+    }
+
+    /// Also samples cgroup v2 accounting for every leaf cgroup on each tick.
+    pub fn with_cgroups(mut self) -> Self {
+        self.cgroups = Some(CgroupSampler::new(&self.source));
+        self
+    }
+
+    /// Also reports the OOM kills appended to this kernel log on each tick.
+    pub fn with_kills(mut self, kmsg: &str) -> Self {
+        self.kills = Some(KillWatcher::new(kmsg));
+        self
+    }
+
+    pub fn tick(&mut self) -> Snapshot {
+        let ts = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let elapsed = self.last_tick.elapsed().as_secs_f64();
+        self.last_tick = Instant::now();
+
+        let meminfo = mem::read_meminfo(&self.source);
+        let mem_total_kb = meminfo.as_ref().map(|m| m.total_kb as f32).unwrap_or(0.0);
+        let cpu = self.cpu.sample(&self.source);
+        let mut io = self.io.sample(&self.source, elapsed, &cpu.procs);
+
+        let mut procs = cpu
+            .procs
+            .into_iter()
+            .filter(|p| self.exclude_pid.is_none_or(|e| e != p.pid))
+            .map(|p| {
+                let status = read_status(&self.source, p.pid).unwrap_or_default();
+                let mut mem_kb = mem::read_statm(&self.source, p.pid).unwrap_or_default();
+                mem_kb.swap_kb = status.swap_kb;
+                let rate = io.rates.remove(&p.pid).unwrap_or_default();
+                let detail = IoDetail { blkio_delay_percent: p.blkio_delay_percent, ..rate.detail };
+
+                ProcSample {
+                    pid: p.pid,
+                    ppid: p.ppid,
+                    pgrp: p.pgrp,
+                    sid: p.sid,
+                    uid: status.uid,
+                    cgroup: cgroup::read_cgroup(&self.source, p.pid).unwrap_or_default(),
+                    name: p.name,
+                    state: p.state,
+                    cpu: p.cpu,
+                    mem: 0.0,
+                    mem_kb,
+                    read_bps: rate.read_bps,
+                    write_bps: rate.write_bps,
+                    io: detail,
+                    sched: None,
+                    members: Vec::new(),
+                    threads: p.threads,
+                }
+            })
+            .collect::<Vec<_>>();
+        // PSS/USS cost a page-table walk per process, so RSS ranking skips smaps altogether
+        if self.mem_metric != MemMetric::Rss {
+            let mut largest: Vec<&mut ProcSample> = procs.iter_mut().collect();
+            largest.sort_by_key(|p| std::cmp::Reverse(p.mem_kb.rss_kb));
+            for p in largest.into_iter().take(SMAPS_PROCS) {
+                if let Some(mut smaps) = mem::read_smaps_rollup(&self.source, p.pid) {
+                    smaps.swap_kb = smaps.swap_kb.or(p.mem_kb.swap_kb);
+                    p.mem_kb = smaps;
+                }
+            }
+        }
+        for p in &mut procs {
+            let used_kb = p.mem_kb.kb(self.mem_metric) as f32;
+            p.mem = if mem_total_kb > 0.0 { used_kb / mem_total_kb * 100.0 } else { 0.0 };
+        }
+
+        let mut busiest: Vec<&ProcSample> = procs.iter().collect();
+        busiest.sort_by(|a, b| b.cpu.total_cmp(&a.cpu));
+        let pids: Vec<u32> = busiest.iter().take(SCHED_PROCS).map(|p| p.pid).collect();
+        let mut sched = self.sched.sample(&self.source, elapsed, &pids);
+        for p in &mut procs {
+            p.sched = sched.remove(&p.pid);
+        }
+        let oom_scores = oom::read_scores(&self.source, &procs, OOM_SCORES);
+        let net = self.net.sample(&self.source, elapsed, &procs);
+        let load = load::read_load(&self.source, &procs);
+        let blocked = load::read_blocked(&self.source, &procs, load.as_ref());
+        let mounts = disk::get_mount_usage(&self.source, &self.disk_filter);
+        let mut devices = self.devices.sample(&self.source, elapsed);
+        for device in &mut devices {
+            device.mounts = mounts.iter().filter(|m| m.dev == device.dev).map(|m| m.mount.clone()).collect();
+        }
+
+        Snapshot {
+            ts,
+            procs,
+            mem_used_percent: meminfo.as_ref().map(|m| mem::used_percent(m.total_kb as f32, m.available_kb as f32)),
+            mem_available_kb: meminfo.as_ref().map(|m| m.available_kb),
+            meminfo,
+            vmstat: self.vmstat.sample(&self.source, elapsed),
+            oom_scores,
+            mounts,
+            devices,
+            net,
+            cpu_states: cpu.states,
+            psi: psi::read_psi(&self.source),
+            thermal: self.thermal.sample(&self.source),
+            load,
+            blocked,
+            cgroups: self.cgroups.as_mut().map(|c| c.sample(elapsed)).unwrap_or_default(),
+            oom_kills: self.kills.as_mut().map(KillWatcher::poll).unwrap_or_default(),
+            exited_io: io.exited,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::fake::FakeProc;
+
+    #[test]
+    fn reads_swap_and_uid_from_status() {
+        let proc = FakeProc::new();
+        proc.file("5/status", "Name:\tfoo\nUid:\t1000\t1000\t1000\t1000\nVmRSS:\t     123 kB\nVmSwap:\t      7 kB\n")
+            .file("6/status", "Name:\tkthreadd\nUid:\t0\t0\t0\t0\n");
+        let source = proc.source();
+
+        assert_eq!(read_status(&source, 5), Some(ProcStatus { swap_kb: Some(7), uid: 1000, ..Default::default() }));
+        assert_eq!(read_status(&source, 6), Some(ProcStatus { swap_kb: None, uid: 0, ..Default::default() }));
+        assert_eq!(read_status(&source, 7), None);
+    }
+}
@@ -0,0 +1,264 @@
+// Scheduler view of the busiest processes: context-switch rates from /proc/[pid]/task/*/status
+// and run-queue wait from /proc/[pid]/task/*/schedstat, summed over all threads. A process that
+// waits for a CPU longer than it runs is starved by oversubscription, which its %CPU alone does
+// not show.
+use crate::cgroup::{self, Cgroup};
+use crate::sampler::{self, ProcSample, Snapshot};
+use crate::source::Source;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+
+/// Per-second scheduler activity of a process's threads over a tick.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct SchedRates {
+    /// Switches where a thread gave up the CPU itself (blocking on I/O, locks, sleep)
+    pub voluntary_switches_per_sec: f32,
+    /// Switches where the scheduler preempted a thread
+    pub involuntary_switches_per_sec: f32,
+    /// Time on a CPU, in percent of one CPU; None without schedstat
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub run_percent: Option<f32>,
+    /// Time runnable but waiting on a run queue, summed over threads, in percent of the tick
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wait_percent: Option<f32>,
+}
+
+impl SchedRates {
+    /// Folds another process into a group total.
+    pub fn add(&mut self, other: &SchedRates) {
+        let sum = |a: Option<f32>, b: Option<f32>| match (a, b) {
+            (Some(a), Some(b)) => Some(a + b),
+            (a, b) => a.or(b),
+        };
+        self.voluntary_switches_per_sec += other.voluntary_switches_per_sec;
+        self.involuntary_switches_per_sec += other.involuntary_switches_per_sec;
+        self.run_percent = sum(self.run_percent, other.run_percent);
+        self.wait_percent = sum(self.wait_percent, other.wait_percent);
+    }
+}
+
+/// Cumulative counters of one thread behind `SchedRates`.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct SchedCounters {
+    voluntary: u64,
+    involuntary: u64,
+    /// (time on CPU, time waiting on a run queue) in ns
+    schedstat: Option<(u64, u64)>,
+}
+
+/// (run ns, wait ns) from `schedstat`: "run_ns wait_ns timeslices".
+fn parse_schedstat(contents: &str) -> Option<(u64, u64)> {
+    let mut f = contents.split_whitespace();
+    Some((f.next()?.parse().ok()?, f.next()?.parse().ok()?))
+}
+
+fn read_thread(source: &Source, pid: u32, tid: u32) -> Option<SchedCounters> {
+    let status = sampler::parse_status(&source.read_pid(pid, &format!("task/{}/status", tid))?)?;
+    Some(SchedCounters {
+        voluntary: status.voluntary_ctxt_switches,
+        involuntary: status.nonvoluntary_ctxt_switches,
+        schedstat: source.read_pid(pid, &format!("task/{}/schedstat", tid)).and_then(|s| parse_schedstat(&s)),
+    })
+}
+
+/// Counters of every thread of `pid`, by tid.
+fn read_threads(source: &Source, pid: u32) -> HashMap<u32, SchedCounters> {
+    let Ok(dir) = fs::read_dir(source.pid_path(pid, "task")) else {
+        return HashMap::new();
+    };
+    dir.flatten()
+        .filter_map(|e| e.file_name().to_str()?.parse::<u32>().ok())
+        .filter_map(|tid| Some((tid, read_thread(source, pid, tid)?)))
+        .collect()
+}
+
+/// Computes scheduler rates of the busiest processes from per-thread deltas between ticks.
+///
+/// `/proc/[pid]/status` and `/proc/[pid]/schedstat` only describe the main thread, which barely
+/// runs in a JVM, database or browser, so every thread under `task/` is read and summed.
+pub struct SchedSampler {
+    last: HashMap<u32, HashMap<u32, SchedCounters>>,
+}
+
+impl SchedSampler {
+    pub fn new() -> Self {
+        SchedSampler { last: HashMap::new() }
+    }
+
+    /// Rates for each of `pids` since the previous call. A pid is measured from the call after
+    /// it is first passed in, since its threads need a baseline; threads started in between are
+    /// measured from zero and threads that exited drop out.
+    pub fn sample(&mut self, source: &Source, elapsed_secs: f64, pids: &[u32]) -> HashMap<u32, SchedRates> {
+        let elapsed_secs = elapsed_secs.max(f64::EPSILON);
+        let rate = |delta: u64| (delta as f64 / elapsed_secs) as f32;
+        let percent = |delta: u64| (delta as f64 / (elapsed_secs * 1e9) * 100.0) as f32;
+
+        let mut last = HashMap::new();
+        let mut rates = HashMap::new();
+        for &pid in pids {
+            let now = read_threads(source, pid);
+            if let Some(before) = self.last.get(&pid) {
+                let (mut voluntary, mut involuntary) = (0, 0);
+                let mut schedstat: Option<(u64, u64)> = None;
+                for (tid, cur) in &now {
+                    let prev = before.get(tid).copied().unwrap_or_default();
+                    voluntary += cur.voluntary.saturating_sub(prev.voluntary);
+                    involuntary += cur.involuntary.saturating_sub(prev.involuntary);
+                    if let Some((run, wait)) = cur.schedstat {
+                        let (run0, wait0) = prev.schedstat.unwrap_or((0, 0));
+                        let (r, w) = schedstat.unwrap_or((0, 0));
+                        schedstat = Some((r + run.saturating_sub(run0), w + wait.saturating_sub(wait0)));
+                    }
+                }
+                rates.insert(
+                    pid,
+                    SchedRates {
+                        voluntary_switches_per_sec: rate(voluntary),
+                        involuntary_switches_per_sec: rate(involuntary),
+                        run_percent: schedstat.map(|(run, _)| percent(run)),
+                        wait_percent: schedstat.map(|(_, wait)| percent(wait)),
+                    },
+                );
+            }
+            last.insert(pid, now);
+        }
+        self.last = last;
+        rates
+    }
+}
+
+/// Below this CPU percent a process barely runs, and its wait/run ratio is noise.
+const MIN_RUN_PERCENT: f32 = 5.0;
+
+/// A process that spent more time waiting for a CPU than running on one.
+#[derive(Debug, Serialize)]
+pub struct Contention {
+    pub name: String,
+    pub pid: u32,
+    pub run_percent_avg: f32,
+    pub wait_percent_avg: f32,
+    pub involuntary_switches_per_sec_avg: f32,
+    /// Other processes that were contended in at least `min_hits` ticks too
+    pub also_contended: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cgroup: Option<Cgroup>,
+}
+
+/// Flags the process whose run-queue wait exceeded `wait_ratio` times its run time in at least
+/// `min_hits` ticks, preferring the one that waited longest.
+pub fn detect_scheduler_contention(snapshots: &[Snapshot], wait_ratio: f32, min_hits: usize) -> Option<Contention> {
+    let mut per_pid: HashMap<u32, (usize, Vec<&ProcSample>)> = HashMap::new();
+    for p in snapshots.iter().flat_map(|s| &s.procs) {
+        let Some(SchedRates { run_percent: Some(run), wait_percent: Some(wait), .. }) = p.sched else {
+            continue;
+        };
+        let entry = per_pid.entry(p.pid).or_default();
+        entry.1.push(p);
+        if run >= MIN_RUN_PERCENT && wait > run * wait_ratio {
+            entry.0 += 1;
+        }
+    }
+
+    let contended: Vec<&Vec<&ProcSample>> =
+        per_pid.values().filter(|(hits, _)| *hits >= min_hits).map(|(_, samples)| samples).collect();
+    let avg = |samples: &[&ProcSample], f: fn(&ProcSample) -> f32| {
+        samples.iter().map(|p| f(p)).sum::<f32>() / samples.len() as f32
+    };
+    let worst = contended
+        .iter()
+        .max_by(|a, b| {
+            let wait = |s: &[&ProcSample]| avg(s, |p| p.sched.and_then(|s| s.wait_percent).unwrap_or(0.0));
+            wait(a).total_cmp(&wait(b))
+        })?;
+    let last = worst.last()?;
+
+    Some(Contention {
+        name: last.name.clone(),
+        pid: last.pid,
+        run_percent_avg: avg(worst, |p| p.sched.and_then(|s| s.run_percent).unwrap_or(0.0)),
+        wait_percent_avg: avg(worst, |p| p.sched.and_then(|s| s.wait_percent).unwrap_or(0.0)),
+        involuntary_switches_per_sec_avg: avg(worst, |p| p.sched.map_or(0.0, |s| s.involuntary_switches_per_sec)),
+        also_contended: contended.len() - 1,
+        cgroup: cgroup::resolve_opt(&last.cgroup),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sampler::proc_sample;
+    use crate::source::fake::FakeProc;
+
+    fn thread(proc: &FakeProc, tid: u32, voluntary: u64, involuntary: u64, run_ns: u64, wait_ns: u64) {
+        proc.file(
+            &format!("9/task/{}/status", tid),
+            &format!(
+                "Name:\tjava\nUid:\t0\t0\t0\t0\nvoluntary_ctxt_switches:\t{}\nnonvoluntary_ctxt_switches:\t{}\n",
+                voluntary, involuntary
+            ),
+        )
+        .file(&format!("9/task/{}/schedstat", tid), &format!("{} {} 40\n", run_ns, wait_ns));
+    }
+
+    #[test]
+    fn sums_switch_rates_and_run_queue_wait_over_threads() {
+        let proc = FakeProc::new();
+        thread(&proc, 9, 100, 10, 1_000_000_000, 500_000_000);
+        thread(&proc, 10, 0, 0, 0, 0);
+        let source = proc.source();
+        let mut sched = SchedSampler::new();
+        assert!(sched.sample(&source, 1.0, &[9]).is_empty());
+
+        // over 2s the idle main thread is untouched; worker 10 runs 0.5s and waits 1.5s, and
+        // worker 11 starts and runs 0.5s with 0.5s of wait
+        thread(&proc, 10, 40, 400, 500_000_000, 1_500_000_000);
+        thread(&proc, 11, 0, 0, 500_000_000, 500_000_000);
+        let rates = sched.sample(&source, 2.0, &[9])[&9];
+
+        assert_eq!((rates.voluntary_switches_per_sec, rates.involuntary_switches_per_sec), (20.0, 200.0));
+        assert_eq!((rates.run_percent, rates.wait_percent), (Some(50.0), Some(100.0)));
+    }
+
+    fn proc(pid: u32, name: &str, run: f32, wait: f32) -> ProcSample {
+        ProcSample {
+            sched: Some(SchedRates {
+                voluntary_switches_per_sec: 0.0,
+                involuntary_switches_per_sec: wait * 10.0,
+                run_percent: Some(run),
+                wait_percent: Some(wait),
+            }),
+            ..proc_sample(pid, 1, name)
+        }
+    }
+
+    #[test]
+    fn flags_the_longest_waiter_that_was_contended_in_min_hits_ticks() {
+        // make waits 3x its run time in 2 of 3 ticks; gcc waits longer but only once; the
+        // idle daemon's wait dwarfs its run time, which is too small to judge
+        let ticks = [(30.0, 90.0, 20.0, 200.0), (30.0, 90.0, 20.0, 10.0), (30.0, 20.0, 20.0, 10.0)];
+        let snapshots: Vec<Snapshot> = ticks
+            .iter()
+            .map(|&(make_run, make_wait, gcc_run, gcc_wait)| Snapshot {
+                procs: vec![
+                    proc(10, "make", make_run, make_wait),
+                    proc(20, "gcc", gcc_run, gcc_wait),
+                    proc(30, "daemon", MIN_RUN_PERCENT / 2.0, 50.0),
+                ],
+                ..Default::default()
+            })
+            .collect();
+
+        let contention = detect_scheduler_contention(&snapshots, 1.0, 2).unwrap();
+        assert_eq!((contention.pid, contention.also_contended), (10, 0));
+        assert_eq!((contention.run_percent_avg, contention.wait_percent_avg), (30.0, 200.0 / 3.0));
+
+        // with one hit enough, gcc's longer average wait wins over make
+        let contention = detect_scheduler_contention(&snapshots, 1.0, 1).unwrap();
+        assert_eq!((contention.name.as_str(), contention.also_contended), ("gcc", 1));
+
+        // make's 3x wait does not clear a 4x ratio
+        assert!(detect_scheduler_contention(&snapshots, 4.0, 2).is_none());
+        assert!(detect_scheduler_contention(&snapshots, 1.0, 3).is_none());
+    }
+}
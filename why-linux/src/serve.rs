@@ -0,0 +1,575 @@
+// OpenMetrics exporter: a background thread samples into a rolling window and the listener
+// serves the latest rendering on /metrics.
+use crate::analysis::{self, Thresholds};
+use crate::group::Grouper;
+use crate::record::Recorder;
+use crate::sampler::{Sampler, Snapshot};
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread::sleep;
+use std::time::Duration;
+
+const CONTENT_TYPE: &str = "application/openmetrics-text; version=1.0.0; charset=utf-8";
+
+/// Number of processes exported for the per-process IO gauges.
+const TOP_IO: usize = 5;
+/// Busiest processes exported with scheduler gauges.
+const TOP_CPU: usize = 5;
+
+/// Number of cgroups exported with `--rank-cgroups`.
+const TOP_CGROUPS: usize = 5;
+
+fn escape_label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+fn gauge(out: &mut String, name: &str, help: &str, samples: &[(String, f64)]) {
+    let _ = writeln!(out, "# TYPE {} gauge", name);
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    for (labels, value) in samples {
+        if labels.is_empty() {
+            let _ = writeln!(out, "{} {}", name, value);
+        } else {
+            let _ = writeln!(out, "{}{{{}}} {}", name, labels, value);
+        }
+    }
+}
+
+fn counter(out: &mut String, name: &str, help: &str, value: u64) {
+    let _ = writeln!(out, "# TYPE {} counter", name);
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "{}_total {}", name, value);
+}
+
+fn single(out: &mut String, name: &str, help: &str, value: f64) {
+    gauge(out, name, help, &[(String::new(), value)]);
+}
+
+/// Renders the window as an OpenMetrics exposition; `oom_kills` counts the kernel-logged kills since the server started.
+pub fn render(snapshots: &[Snapshot], thresholds: &Thresholds, oom_kills: u64) -> String {
+    let mut out = String::new();
+    let summary = analysis::summary(snapshots);
+    let findings = analysis::detect(snapshots, thresholds);
+    let stat = |section: &str, key: &str| summary[section][key].as_f64().unwrap_or(0.0);
+
+    single(
+        &mut out,
+        "why_linux_cpu_top_percent_avg",
+        "Average CPU percent of the busiest process per tick.",
+        stat("cpu", "avg"),
+    );
+    single(
+        &mut out,
+        "why_linux_cpu_top_percent_max",
+        "Maximum CPU percent of the busiest process per tick.",
+        stat("cpu", "max"),
+    );
+    single(
+        &mut out,
+        "why_linux_mem_top_percent_avg",
+        "Average memory percent of the largest process per tick.",
+        stat("mem", "avg"),
+    );
+    single(
+        &mut out,
+        "why_linux_mem_top_percent_max",
+        "Maximum memory percent of the largest process per tick.",
+        stat("mem", "max"),
+    );
+    single(
+        &mut out,
+        "why_linux_mem_system_used_percent_avg",
+        "Average system memory used percent.",
+        stat("mem", "system_avg"),
+    );
+    single(
+        &mut out,
+        "why_linux_mem_system_used_percent_max",
+        "Maximum system memory used percent.",
+        stat("mem", "system_max"),
+    );
+
+    let latest = snapshots.last();
+
+    let mut cpu_procs: Vec<_> = latest.map(|s| s.procs.iter().collect()).unwrap_or_default();
+    cpu_procs.sort_by(|a, b| b.cpu.total_cmp(&a.cpu));
+    cpu_procs.truncate(TOP_CPU);
+    let proc_label = |p: &crate::sampler::ProcSample| format!("pid=\"{}\",name=\"{}\"", p.pid, escape_label(&p.name));
+    let waits: Vec<_> = cpu_procs
+        .iter()
+        .filter_map(|p| p.sched?.wait_percent.map(|w| (proc_label(p), w as f64)))
+        .collect();
+    gauge(
+        &mut out,
+        "why_linux_sched_wait_percent",
+        "Run-queue wait of the busiest processes' threads over the last tick, in percent of one CPU.",
+        &waits,
+    );
+    let preemptions: Vec<_> = cpu_procs
+        .iter()
+        .filter_map(|p| Some((proc_label(p), p.sched?.involuntary_switches_per_sec as f64)))
+        .collect();
+    gauge(
+        &mut out,
+        "why_linux_involuntary_context_switches_per_second",
+        "Preemptions per second of the busiest processes' threads.",
+        &preemptions,
+    );
+
+    if let Some(m) = latest.and_then(|s| s.meminfo.as_ref()) {
+        let kinds = [
+            ("anon", m.anon_kb),
+            ("page_cache", m.page_cache_kb()),
+            ("buffers", m.buffers_kb),
+            ("shmem", m.shmem_kb),
+            ("slab_reclaimable", m.slab_reclaimable_kb),
+            ("slab_unreclaimable", m.slab_unreclaimable_kb),
+            ("kernel_stack", m.kernel_stack_kb),
+            ("page_tables", m.page_tables_kb),
+            ("hugepages", m.hugepages_kb),
+            ("swap_used", m.swap_used_kb()),
+        ];
+        let samples: Vec<_> = kinds.iter().map(|(k, kb)| (format!("kind=\"{}\"", k), (*kb * 1024) as f64)).collect();
+        gauge(&mut out, "why_linux_mem_bytes", "System memory by kind from /proc/meminfo.", &samples);
+    }
+    if let Some(v) = latest.and_then(|s| s.vmstat.as_ref()) {
+        gauge(
+            &mut out,
+            "why_linux_swap_bytes_per_second",
+            "Swap traffic over the last tick.",
+            &[
+                ("direction=\"in\"".to_string(), v.swap_in_bps as f64),
+                ("direction=\"out\"".to_string(), v.swap_out_bps as f64),
+            ],
+        );
+        single(
+            &mut out,
+            "why_linux_major_faults_per_second",
+            "Major page faults per second over the last tick.",
+            v.major_faults_per_sec as f64,
+        );
+    }
+    counter(
+        &mut out,
+        "why_linux_oom_kills",
+        "OOM kills read from the kernel log since the exporter started.",
+        oom_kills,
+    );
+
+    let mounts: Vec<_> = latest.map(|s| s.mounts.iter().collect()).unwrap_or_default();
+    let mount_label = |m: &crate::disk::DiskSample| {
+        format!(
+            "mount=\"{}\",fs=\"{}\",fstype=\"{}\"",
+            escape_label(&m.mount),
+            escape_label(&m.fs),
+            escape_label(&m.fstype)
+        )
+    };
+    let per_mount = |f: fn(&crate::disk::DiskSample) -> Option<f64>| -> Vec<(String, f64)> {
+        mounts.iter().filter_map(|m| Some((mount_label(m), f(m)?))).collect()
+    };
+    gauge(
+        &mut out,
+        "why_linux_disk_used_percent",
+        "Filesystem used percent per mount.",
+        &per_mount(|m| Some(m.used_percent as f64)),
+    );
+    gauge(
+        &mut out,
+        "why_linux_disk_avail_bytes",
+        "Bytes available to unprivileged users per mount.",
+        &per_mount(|m| Some(m.avail_bytes as f64)),
+    );
+    gauge(
+        &mut out,
+        "why_linux_disk_inodes_used_percent",
+        "Inode used percent per mount (filesystems with a fixed inode table).",
+        &per_mount(|m| m.inodes_used_percent.map(f64::from)),
+    );
+
+    if let Some(f) = &findings.disk_fill {
+        gauge(
+            &mut out,
+            "why_linux_disk_full_in_seconds",
+            "Projected seconds until the soonest-filling mount is full.",
+            &[(format!("mount=\"{}\"", escape_label(&f.mount)), f.full_in_secs as f64)],
+        );
+    }
+
+    let mut io_procs: Vec<_> = latest.map(|s| s.procs.iter().collect()).unwrap_or_default();
+    io_procs.sort_by_key(|p| std::cmp::Reverse(p.read_bps.saturating_add(p.write_bps)));
+    io_procs.retain(|p| p.read_bps > 0 || p.write_bps > 0);
+    io_procs.truncate(TOP_IO);
+    let label = |pid: u32, name: &str| format!("pid=\"{}\",name=\"{}\"", pid, escape_label(name));
+    let reads: Vec<_> = io_procs.iter().map(|p| (label(p.pid, &p.name), p.read_bps as f64)).collect();
+    let writes: Vec<_> = io_procs.iter().map(|p| (label(p.pid, &p.name), p.write_bps as f64)).collect();
+    gauge(
+        &mut out,
+        "why_linux_io_read_bytes_per_second",
+        "Read bytes/sec of the top I/O processes.",
+        &reads,
+    );
+    gauge(
+        &mut out,
+        "why_linux_io_write_bytes_per_second",
+        "Write bytes/sec of the top I/O processes.",
+        &writes,
+    );
+    let syscalls: Vec<_> = io_procs
+        .iter()
+        .map(|p| (label(p.pid, &p.name), (p.io.syscr_per_sec + p.io.syscw_per_sec) as f64))
+        .collect();
+    gauge(
+        &mut out,
+        "why_linux_io_syscalls_per_second",
+        "Read plus write syscalls/sec of the top I/O processes.",
+        &syscalls,
+    );
+    let delays: Vec<_> = io_procs
+        .iter()
+        .filter_map(|p| p.io.blkio_delay_percent.map(|d| (label(p.pid, &p.name), d as f64)))
+        .collect();
+    gauge(
+        &mut out,
+        "why_linux_io_blkio_delay_percent",
+        "Percent of the last tick the top I/O processes spent waiting for block I/O.",
+        &delays,
+    );
+
+    let cgroups = analysis::top_cgroups(snapshots, TOP_CGROUPS);
+    if !cgroups.is_empty() {
+        let label = |path: &str| format!("cgroup=\"{}\"", escape_label(path));
+        let cpu: Vec<_> = cgroups.iter().map(|c| (label(&c.cgroup.path), c.cpu_avg as f64)).collect();
+        let mem: Vec<_> = cgroups.iter().map(|c| (label(&c.cgroup.path), c.mem_max_bytes as f64)).collect();
+        gauge(
+            &mut out,
+            "why_linux_cgroup_cpu_percent_avg",
+            "Average CPU percent of the busiest leaf cgroups over the window.",
+            &cpu,
+        );
+        gauge(
+            &mut out,
+            "why_linux_cgroup_memory_bytes_max",
+            "Maximum memory.current of the busiest leaf cgroups over the window.",
+            &mem,
+        );
+        let pressure: Vec<_> = cgroups
+            .iter()
+            .flat_map(|c| {
+                c.pressure_max.iter().map(|(resource, peak)| {
+                    (format!("{},resource=\"{}\"", label(&c.cgroup.path), resource), *peak as f64)
+                })
+            })
+            .collect();
+        if !pressure.is_empty() {
+            gauge(
+                &mut out,
+                "why_linux_cgroup_pressure_some_avg10_max",
+                "Peak some avg10 of the busiest leaf cgroups' own pressure files over the window.",
+                &pressure,
+            );
+        }
+    }
+
+    let devices: Vec<_> = latest.map(|s| s.devices.iter().collect()).unwrap_or_default();
+    let per_device = |f: fn(&crate::blockdev::DeviceSample) -> f64| -> Vec<(String, f64)> {
+        devices.iter().map(|d| (format!("device=\"{}\"", escape_label(&d.name)), f(d))).collect()
+    };
+    gauge(
+        &mut out,
+        "why_linux_device_util_percent",
+        "Percent of the last tick the block device had I/O in flight.",
+        &per_device(|d| d.util_percent as f64),
+    );
+    gauge(
+        &mut out,
+        "why_linux_device_await_milliseconds",
+        "Average time per completed request over the last tick.",
+        &per_device(|d| d.await_ms as f64),
+    );
+    gauge(
+        &mut out,
+        "why_linux_device_queue_depth",
+        "Average requests in flight over the last tick.",
+        &per_device(|d| d.queue_depth as f64),
+    );
+    gauge(
+        &mut out,
+        "why_linux_device_iops",
+        "Completed reads plus writes per second over the last tick.",
+        &per_device(|d| (d.read_iops + d.write_iops) as f64),
+    );
+    gauge(
+        &mut out,
+        "why_linux_device_bytes_per_second",
+        "Bytes read plus written per second over the last tick.",
+        &per_device(|d| (d.read_bps + d.write_bps) as f64),
+    );
+
+    if let Some((snapshot, l)) = latest.and_then(|s| Some((s, s.load.as_ref()?))) {
+        let cpus = crate::load::cpus(snapshot).unwrap_or(1) as f64;
+        gauge(
+            &mut out,
+            "why_linux_load_per_cpu",
+            "Load average divided by the number of CPUs.",
+            &[
+                ("window=\"1m\"".to_string(), l.load1 as f64 / cpus),
+                ("window=\"5m\"".to_string(), l.load5 as f64 / cpus),
+                ("window=\"15m\"".to_string(), l.load15 as f64 / cpus),
+            ],
+        );
+        gauge(
+            &mut out,
+            "why_linux_tasks",
+            "Runnable tasks and processes in uninterruptible sleep at the last tick.",
+            &[
+                ("state=\"runnable\"".to_string(), l.runnable as f64),
+                ("state=\"uninterruptible\"".to_string(), l.uninterruptible as f64),
+            ],
+        );
+    }
+
+    if let Some(thermal) = latest.and_then(|s| s.thermal.as_ref()) {
+        let zones: Vec<_> = thermal
+            .zones
+            .iter()
+            .enumerate()
+            .map(|(i, z)| (format!("zone=\"{}\",type=\"{}\"", i, escape_label(&z.name)), z.temp_c as f64))
+            .collect();
+        gauge(&mut out, "why_linux_thermal_zone_celsius", "Thermal zone temperatures.", &zones);
+        if let Some(freq) = thermal.freq_percent {
+            single(
+                &mut out,
+                "why_linux_cpu_frequency_percent",
+                "Average current CPU clock as a percent of the base (else maximum) clock.",
+                freq as f64,
+            );
+        }
+        if let Some(events) = thermal.throttle_events {
+            single(
+                &mut out,
+                "why_linux_thermal_throttle_events",
+                "Core and package thermal throttle events during the last tick.",
+                events as f64,
+            );
+        }
+    }
+
+    if let Some(net) = latest.and_then(|s| s.net.as_ref()) {
+        let per_interface = |f: fn(&crate::net::InterfaceSample) -> f64| -> Vec<(String, f64)> {
+            net.interfaces.iter().map(|i| (format!("interface=\"{}\"", escape_label(&i.name)), f(i))).collect()
+        };
+        gauge(
+            &mut out,
+            "why_linux_net_rx_bytes_per_second",
+            "Bytes received per second over the last tick.",
+            &per_interface(|i| i.rx_bps as f64),
+        );
+        gauge(
+            &mut out,
+            "why_linux_net_tx_bytes_per_second",
+            "Bytes transmitted per second over the last tick.",
+            &per_interface(|i| i.tx_bps as f64),
+        );
+        gauge(
+            &mut out,
+            "why_linux_net_drops_per_second",
+            "Packets dropped per second over the last tick.",
+            &per_interface(|i| i.drops_per_sec as f64),
+        );
+        gauge(
+            &mut out,
+            "why_linux_net_errors_per_second",
+            "Receive and transmit errors per second over the last tick.",
+            &per_interface(|i| i.errors_per_sec as f64),
+        );
+
+        if let Some(tcp) = &net.tcp {
+            single(
+                &mut out,
+                "why_linux_tcp_retransmits_per_second",
+                "TCP segments retransmitted per second over the last tick.",
+                tcp.retrans_segs_per_sec as f64,
+            );
+            let s = &tcp.sockets;
+            let state = |name: &str, n: u32| (format!("state=\"{}\"", name), n as f64);
+            gauge(
+                &mut out,
+                "why_linux_tcp_sockets",
+                "TCP sockets by state.",
+                &[
+                    state("established", s.established),
+                    state("syn_sent", s.syn_sent),
+                    state("syn_recv", s.syn_recv),
+                    state("closing", s.closing),
+                    state("time_wait", s.time_wait),
+                    state("close_wait", s.close_wait),
+                    state("listen", s.listen),
+                ],
+            );
+        }
+
+        let owners: Vec<_> = net
+            .processes
+            .iter()
+            .map(|p| (label(p.pid, &p.name), p.sockets.connections() as f64))
+            .collect();
+        gauge(
+            &mut out,
+            "why_linux_process_tcp_connections",
+            "TCP connections held by the processes with the most.",
+            &owners,
+        );
+    }
+
+    let verdict = |name: &str, hit: bool| (format!("detector=\"{}\"", name), if hit { 1.0 } else { 0.0 });
+    gauge(
+        &mut out,
+        "why_linux_detection_active",
+        "1 when the sustained detector fires over the current window.",
+        &[
+            verdict("cpu", findings.cpu.is_some()),
+            verdict("mem", findings.mem.is_some()),
+            verdict("mem_growth", findings.mem_growth.is_some()),
+            verdict("disk", findings.disk.is_some()),
+            verdict("disk_fill", findings.disk_fill.is_some()),
+            verdict("inodes", findings.inodes.is_some()),
+            verdict("io", findings.io.is_some()),
+            verdict("device", findings.device.is_some()),
+            verdict("net", findings.net.is_some()),
+            verdict("thermal", findings.thermal.is_some()),
+            verdict("d_state", findings.d_state.is_some()),
+            verdict("sched", findings.sched.is_some()),
+            verdict("psi", findings.psi.is_some()),
+        ],
+    );
+
+    let mut pressure = Vec::new();
+    if let Some(psi) = latest.and_then(|s| s.psi.as_ref()) {
+        for (resource, res) in psi.resources() {
+            let Some(res) = res else {
+                continue;
+            };
+            let lines = [("some", Some(&res.some)), ("full", res.full.as_ref())];
+            for (kind, line) in lines {
+                if let Some(line) = line {
+                    for (window, value) in [("avg10", line.avg10), ("avg60", line.avg60)] {
+                        let labels = format!("resource=\"{}\",kind=\"{}\",window=\"{}\"", resource, kind, window);
+                        pressure.push((labels, value as f64));
+                    }
+                }
+            }
+        }
+    }
+    gauge(
+        &mut out,
+        "why_linux_pressure_percent",
+        "Pressure stall percent from /proc/pressure.",
+        &pressure,
+    );
+
+    single(
+        &mut out,
+        "why_linux_window_samples",
+        "Snapshots currently in the rolling window.",
+        snapshots.len() as f64,
+    );
+
+    out.push_str("# EOF\n");
+    out
+}
+
+/// How long a client may take to send its request or read the response.
+const IO_TIMEOUT: Duration = Duration::from_secs(10);
+
+fn handle(stream: TcpStream, body: &str) -> std::io::Result<()> {
+    stream.set_read_timeout(Some(IO_TIMEOUT))?;
+    stream.set_write_timeout(Some(IO_TIMEOUT))?;
+    let mut reader = BufReader::new(&stream);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+
+    // drain headers so clients don't see a reset before reading the response
+    let mut line = String::new();
+    while reader.read_line(&mut line)? > 2 {
+        line.clear();
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or("");
+    let path = parts.next().unwrap_or("");
+
+    let mut stream = &stream;
+    if method == "GET" && (path == "/metrics" || path.starts_with("/metrics?")) {
+        write!(
+            stream,
+            "HTTP/1.1 200 OK\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            CONTENT_TYPE,
+            body.len(),
+            body
+        )
+    } else {
+        let msg = "not found; try /metrics\n";
+        write!(
+            stream,
+            "HTTP/1.1 404 Not Found\r\nContent-Type: text/plain\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            msg.len(),
+            msg
+        )
+    }
+}
+
+/// Binds `listen` and serves /metrics forever; sampling runs on a background thread.
+pub fn run(
+    listen: &str,
+    mut sampler: Sampler,
+    grouper: Grouper,
+    interval_secs: u64,
+    window: usize,
+    thresholds: Thresholds,
+    mut recorder: Option<Recorder>,
+) -> std::io::Result<()> {
+    let listener = TcpListener::bind(listen)?;
+    let body = Arc::new(Mutex::new(render(&[], &thresholds, 0)));
+
+    let shared = Arc::clone(&body);
+    std::thread::spawn(move || {
+        let mut snapshots: VecDeque<Snapshot> = VecDeque::with_capacity(window);
+        let mut oom_kills = 0;
+        loop {
+            sleep(Duration::from_secs(interval_secs));
+
+            if snapshots.len() == window {
+                snapshots.pop_front();
+            }
+            let snapshot = sampler.tick();
+            if let Some(r) = recorder.as_mut()
+                && let Err(e) = r.write(&snapshot)
+            {
+                eprintln!("Failed to record sample: {}", e);
+            }
+            oom_kills += snapshot.oom_kills.len() as u64;
+            snapshots.push_back(grouper.apply(&snapshot));
+
+            let rendered = render(snapshots.make_contiguous(), &thresholds, oom_kills);
+            if let Ok(mut b) = shared.lock() {
+                *b = rendered;
+            }
+        }
+    });
+
+    // one short-lived thread per connection, so a silent client cannot stall other scrapes
+    for stream in listener.incoming().flatten() {
+        let current = body.lock().map(|b| b.clone()).unwrap_or_default();
+        std::thread::spawn(move || {
+            if let Err(e) = handle(stream, &current) {
+                eprintln!("metrics request failed: {}", e);
+            }
+        });
+    }
+
+    Ok(())
+}
@@ -0,0 +1,172 @@
+// Where collectors read kernel state from. Pointing `proc_root` at e.g. /host/proc lets the tool
+// inspect a host from a sidecar container, and lets tests run against a fake /proc tree.
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const DEFAULT_PROC_ROOT: &str = "/proc";
+
+#[derive(Debug, Clone)]
+pub struct Source {
+    proc_root: PathBuf,
+}
+
+impl Default for Source {
+    fn default() -> Self {
+        Source::new(DEFAULT_PROC_ROOT)
+    }
+}
+
+impl Source {
+    pub fn new(proc_root: impl Into<PathBuf>) -> Self {
+        Source {
+            proc_root: proc_root.into(),
+        }
+    }
+
+    /// Reads a file relative to the proc root, e.g. `read("meminfo")`.
+    pub fn read(&self, rel: &str) -> Option<String> {
+        fs::read_to_string(self.proc_root.join(rel)).ok()
+    }
+
+    /// Reads `/proc/[pid]/<file>`.
+    pub fn read_pid(&self, pid: u32, file: &str) -> Option<String> {
+        fs::read_to_string(self.proc_root.join(pid.to_string()).join(file)).ok()
+    }
+
+    /// Path of `/proc/[pid]/<rel>`, for entries that are not plain files (e.g. `fd`).
+    pub fn pid_path(&self, pid: u32, rel: &str) -> PathBuf {
+        self.proc_root.join(pid.to_string()).join(rel)
+    }
+
+    pub fn pids(&self) -> Vec<u32> {
+        let mut v = Vec::new();
+        if let Ok(entries) = fs::read_dir(&self.proc_root) {
+            for e in entries.flatten() {
+                if let Ok(name) = e.file_name().into_string()
+                    && let Ok(pid) = name.parse::<u32>()
+                {
+                    v.push(pid);
+                }
+            }
+        }
+        v
+    }
+
+    /// This process's pid as numbered by the proc mount, which under `--proc-root /host/proc`
+    /// differs from `std::process::id()` in a container; None when we are not visible there.
+    pub fn self_pid(&self) -> Option<u32> {
+        fs::read_link(self.proc_root.join("self")).ok()?.to_str()?.parse().ok()
+    }
+
+    /// Mount table (`mountinfo` format): our own with the default root, init's with an alternative
+    /// root, matching where `mount_path` reaches the mounts.
+    pub fn mountinfo(&self) -> Option<String> {
+        if self.proc_root == Path::new(DEFAULT_PROC_ROOT) {
+            self.read("self/mountinfo")
+        } else {
+            self.read("1/mountinfo")
+        }
+    }
+
+    /// Path at which a mount point from `mountinfo()` can be reached from this process.
+    ///
+    /// With the default root that is the mount point itself; with an alternative root it goes
+    /// through init's root (`<proc_root>/1/root/...`), which requires ptrace access to pid 1.
+    pub fn mount_path(&self, mount: &str) -> PathBuf {
+        if self.proc_root == Path::new(DEFAULT_PROC_ROOT) {
+            PathBuf::from(mount)
+        } else {
+            self.proc_root.join("1/root").join(mount.trim_start_matches('/'))
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod fake {
+    use super::Source;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    static NEXT: AtomicUsize = AtomicUsize::new(0);
+
+    /// A throwaway /proc tree under the system temp dir, removed on drop.
+    pub struct FakeProc {
+        root: PathBuf,
+    }
+
+    impl FakeProc {
+        pub fn new() -> Self {
+            let root = std::env::temp_dir().join(format!(
+                "why-linux-test-{}-{}",
+                std::process::id(),
+                NEXT.fetch_add(1, Ordering::SeqCst)
+            ));
+            fs::create_dir_all(&root).unwrap();
+            FakeProc { root }
+        }
+
+        pub fn file(&self, rel: &str, contents: &str) -> &Self {
+            let path = self.root.join(rel);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+            self
+        }
+
+        /// Creates a symlink, e.g. a `[pid]/fd/N` entry pointing at `target`.
+        pub fn symlink(&self, rel: &str, target: &Path) -> &Self {
+            let path = self.root.join(rel);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::os::unix::fs::symlink(target, path).unwrap();
+            self
+        }
+
+        /// Absolute path of an entry in the fake tree.
+        pub fn path(&self, rel: &str) -> PathBuf {
+            self.root.join(rel)
+        }
+
+        pub fn source(&self) -> Source {
+            Source::new(&self.root)
+        }
+    }
+
+    impl Drop for FakeProc {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.root);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::fake::FakeProc;
+    use super::*;
+
+    #[test]
+    fn pids_lists_only_numeric_entries() {
+        let proc = FakeProc::new();
+        proc.file("1/stat", "").file("42/stat", "").file("self/stat", "").file("meminfo", "");
+
+        let mut pids = proc.source().pids();
+        pids.sort();
+        assert_eq!(pids, vec![1, 42]);
+    }
+
+    #[test]
+    fn self_pid_follows_the_proc_mount_self_link() {
+        let proc = FakeProc::new();
+        assert_eq!(proc.source().self_pid(), None);
+        proc.symlink("self", Path::new("4242"));
+        assert_eq!(proc.source().self_pid(), Some(4242));
+    }
+
+    #[test]
+    fn mount_path_goes_through_init_root_for_alternative_roots() {
+        assert_eq!(Source::default().mount_path("/var"), PathBuf::from("/var"));
+        assert_eq!(
+            Source::new("/host/proc").mount_path("/var/lib"),
+            PathBuf::from("/host/proc/1/root/var/lib")
+        );
+    }
+}
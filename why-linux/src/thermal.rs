@@ -0,0 +1,353 @@
+// Thermal and frequency throttling from sysfs: thermal zone temperatures, current vs. maximum
+// CPU frequency, and the thermal_throttle event counters (x86).
+use crate::sampler::Snapshot;
+use crate::source::Source;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+/// One thermal zone's temperature.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThermalZone {
+    /// The zone's `type`, e.g. x86_pkg_temp or acpitz
+    pub name: String,
+    pub temp_c: f32,
+}
+
+/// One CPU's current clock as a percent of its reference clock.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CpuFreq {
+    /// Named as in `/proc/stat`, e.g. cpu3
+    pub cpu: String,
+    pub percent: f32,
+}
+
+/// Temperatures, clock speed and throttling over one tick; fields are None when the machine
+/// does not expose them (common in VMs).
+///
+/// Clock percents are scaling_cur_freq against `base_frequency` where the driver exposes it
+/// (intel_pstate), else against cpuinfo_max_freq, so turbo can exceed 100.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ThermalSample {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub zones: Vec<ThermalZone>,
+    /// Average clock percent over all CPUs
+    pub freq_percent: Option<f32>,
+    /// The slowest CPU's clock percent
+    pub min_freq_percent: Option<f32>,
+    /// Per-CPU clock percents, matched against the per-core busy time by the detector
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cpus: Vec<CpuFreq>,
+    /// New core and package throttle events during the tick
+    pub throttle_events: Option<u64>,
+}
+
+impl ThermalSample {
+    pub fn hottest(&self) -> Option<&ThermalZone> {
+        self.zones.iter().max_by(|a, b| a.temp_c.total_cmp(&b.temp_c))
+    }
+}
+
+fn read_u64(path: &Path) -> Option<u64> {
+    fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+/// Entries of a sysfs directory whose names start with `prefix`, e.g. `thermal_zone`.
+fn entries(dir: &Path, prefix: &str) -> Vec<std::path::PathBuf> {
+    let mut paths: Vec<_> = fs::read_dir(dir)
+        .map(|d| {
+            d.flatten()
+                .filter(|e| e.file_name().to_str().is_some_and(|n| n.starts_with(prefix)))
+                .map(|e| e.path())
+                .collect()
+        })
+        .unwrap_or_default();
+    paths.sort();
+    paths
+}
+
+fn read_zones(source: &Source) -> Vec<ThermalZone> {
+    entries(&source.mount_path("/sys/class/thermal"), "thermal_zone")
+        .into_iter()
+        .filter_map(|zone| {
+            // millidegrees Celsius
+            let temp = fs::read_to_string(zone.join("temp")).ok()?.trim().parse::<i64>().ok()?;
+            let name = fs::read_to_string(zone.join("type")).map(|t| t.trim().to_string()).unwrap_or_default();
+            Some(ThermalZone { name, temp_c: temp as f32 / 1000.0 })
+        })
+        .collect()
+}
+
+/// Per-CPU clock percents, and the summed throttle counters (None without any).
+fn read_cpus(source: &Source) -> (Vec<CpuFreq>, Option<u64>) {
+    let mut freqs = Vec::new();
+    let mut throttles: Option<u64> = None;
+    let mut packages = HashSet::new();
+
+    for cpu in entries(&source.mount_path("/sys/devices/system/cpu"), "cpu") {
+        // skip cpufreq/, cpuidle/ and friends
+        let Some(n) = cpu.file_name().and_then(|n| n.to_str()?.strip_prefix("cpu")?.parse::<u32>().ok()) else {
+            continue;
+        };
+
+        let cpufreq = cpu.join("cpufreq");
+        let reference = read_u64(&cpufreq.join("base_frequency")).or_else(|| read_u64(&cpufreq.join("cpuinfo_max_freq")));
+        if let (Some(cur), Some(reference)) = (read_u64(&cpufreq.join("scaling_cur_freq")), reference)
+            && reference > 0
+        {
+            freqs.push(CpuFreq { cpu: format!("cpu{}", n), percent: cur as f32 / reference as f32 * 100.0 });
+        }
+
+        // the package counter is repeated on every CPU of the package, so count it once per package
+        let throttle = cpu.join("thermal_throttle");
+        let mut add = |counter: &str| {
+            if let Some(n) = read_u64(&throttle.join(counter)) {
+                throttles = Some(throttles.unwrap_or(0) + n);
+            }
+        };
+        add("core_throttle_count");
+        if packages.insert(read_u64(&cpu.join("topology/physical_package_id"))) {
+            add("package_throttle_count");
+        }
+    }
+
+    (freqs, throttles)
+}
+
+/// Reads thermal state each tick; throttle events are deltas between calls.
+pub struct ThermalSampler {
+    last_throttles: Option<u64>,
+}
+
+impl ThermalSampler {
+    pub fn new(source: &Source) -> Self {
+        ThermalSampler { last_throttles: read_cpus(source).1 }
+    }
+
+    /// `None` when sysfs exposes neither thermal zones, cpufreq nor throttle counters.
+    pub fn sample(&mut self, source: &Source) -> Option<ThermalSample> {
+        let zones = read_zones(source);
+        let (freqs, throttles) = read_cpus(source);
+        let throttle_events = match (throttles, self.last_throttles) {
+            (Some(now), Some(before)) => Some(now.saturating_sub(before)),
+            _ => None,
+        };
+        self.last_throttles = throttles;
+
+        if zones.is_empty() && freqs.is_empty() && throttles.is_none() {
+            return None;
+        }
+
+        Some(ThermalSample {
+            zones,
+            freq_percent: (!freqs.is_empty()).then(|| freqs.iter().map(|f| f.percent).sum::<f32>() / freqs.len() as f32),
+            min_freq_percent: freqs.iter().map(|f| f.percent).reduce(f32::min),
+            cpus: freqs,
+            throttle_events,
+        })
+    }
+}
+
+/// CPU busy percent above which a low clock means throttling rather than power saving.
+const BUSY_PERCENT: f32 = 50.0;
+
+/// Average clock percent of the CPUs that were busy during the tick; idle cores clock down to
+/// save power and would otherwise pass for throttling. Records without per-CPU clocks fall back
+/// to the all-CPU average when the machine as a whole was busy.
+fn busy_freq(snapshot: &Snapshot, thermal: &ThermalSample) -> Option<f32> {
+    if thermal.cpus.is_empty() {
+        let busy = snapshot.cpu_states.first().is_some_and(|c| c.busy() >= BUSY_PERCENT);
+        return thermal.freq_percent.filter(|_| busy);
+    }
+
+    let busy: Vec<f32> = thermal
+        .cpus
+        .iter()
+        .filter(|f| snapshot.cpu_states.iter().skip(1).any(|c| c.cpu == f.cpu && c.busy() >= BUSY_PERCENT))
+        .map(|f| f.percent)
+        .collect();
+    (!busy.is_empty()).then(|| busy.iter().sum::<f32>() / busy.len() as f32)
+}
+
+/// Throttling that held for most of the window.
+#[derive(Debug, Serialize)]
+pub struct Throttling {
+    /// Which of "throttle_events", "low_frequency" and "high_temperature" were sustained
+    pub causes: Vec<&'static str>,
+    /// Average clock of the busy CPUs, as a percent of their base (else maximum) clock
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub busy_freq_percent: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hottest: Option<ThermalZone>,
+    pub throttle_events: u64,
+}
+
+/// Flags throttle counters rising, busy CPUs running below `freq_percent` of their base clock, or a
+/// zone at or above `temp_c`, each in at least `min_hits` ticks.
+pub fn detect_throttling(snapshots: &[Snapshot], freq_percent: f32, temp_c: f32, min_hits: usize) -> Option<Throttling> {
+    let mut hits = [0usize; 3];
+    let mut busy_freqs = Vec::new();
+    let mut hottest: Option<&ThermalZone> = None;
+    let mut throttle_events = 0;
+
+    for snapshot in snapshots {
+        let Some(thermal) = &snapshot.thermal else {
+            continue;
+        };
+        let events = thermal.throttle_events.unwrap_or(0);
+        throttle_events += events;
+        if events > 0 {
+            hits[0] += 1;
+        }
+
+        if let Some(freq) = busy_freq(snapshot, thermal) {
+            busy_freqs.push(freq);
+            if freq < freq_percent {
+                hits[1] += 1;
+            }
+        }
+
+        if let Some(zone) = thermal.hottest() {
+            if zone.temp_c >= temp_c {
+                hits[2] += 1;
+            }
+            if hottest.is_none_or(|h| zone.temp_c > h.temp_c) {
+                hottest = Some(zone);
+            }
+        }
+    }
+
+    let causes: Vec<&'static str> = ["throttle_events", "low_frequency", "high_temperature"]
+        .into_iter()
+        .zip(hits)
+        .filter(|(_, n)| *n >= min_hits)
+        .map(|(cause, _)| cause)
+        .collect();
+    if causes.is_empty() {
+        return None;
+    }
+
+    Some(Throttling {
+        causes,
+        busy_freq_percent: (!busy_freqs.is_empty()).then(|| busy_freqs.iter().sum::<f32>() / busy_freqs.len() as f32),
+        hottest: hottest.cloned(),
+        throttle_events,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cpu::CpuStates;
+    use crate::source::fake::FakeProc;
+
+    const SYS: &str = "1/root/sys";
+
+    fn cpu(proc: &FakeProc, n: u32, cur_khz: u64, max_khz: u64, throttles: u64) {
+        let dir = format!("{}/devices/system/cpu/cpu{}", SYS, n);
+        proc.file(&format!("{}/cpufreq/scaling_cur_freq", dir), &format!("{}\n", cur_khz))
+            .file(&format!("{}/cpufreq/cpuinfo_max_freq", dir), &format!("{}\n", max_khz))
+            .file(&format!("{}/thermal_throttle/core_throttle_count", dir), &format!("{}\n", throttles));
+    }
+
+    /// Puts a CPU in `package`, whose shared counter every CPU of the package repeats.
+    fn package(proc: &FakeProc, n: u32, package: u32, throttles: u64) {
+        let dir = format!("{}/devices/system/cpu/cpu{}", SYS, n);
+        proc.file(&format!("{}/topology/physical_package_id", dir), &format!("{}\n", package))
+            .file(&format!("{}/thermal_throttle/package_throttle_count", dir), &format!("{}\n", throttles));
+    }
+
+    fn states(cpu: &str, idle: f32) -> CpuStates {
+        CpuStates { cpu: cpu.to_string(), idle, ..Default::default() }
+    }
+
+    fn snapshot(cpus: &[(f32, f32)], events: u64, temp_c: f32) -> Snapshot {
+        let mut cpu_states = vec![states("cpu", 50.0)];
+        cpu_states.extend(cpus.iter().enumerate().map(|(i, (idle, _))| states(&format!("cpu{}", i), *idle)));
+        let freqs: Vec<CpuFreq> =
+            cpus.iter().enumerate().map(|(i, (_, percent))| CpuFreq { cpu: format!("cpu{}", i), percent: *percent }).collect();
+        Snapshot {
+            cpu_states,
+            thermal: Some(ThermalSample {
+                zones: vec![ThermalZone { name: "x86_pkg_temp".to_string(), temp_c }],
+                freq_percent: Some(freqs.iter().map(|f| f.percent).sum::<f32>() / freqs.len() as f32),
+                min_freq_percent: freqs.iter().map(|f| f.percent).reduce(f32::min),
+                cpus: freqs,
+                throttle_events: Some(events),
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn reads_zones_frequency_and_throttle_events_from_sysfs() {
+        let proc = FakeProc::new();
+        proc.file(&format!("{}/class/thermal/thermal_zone0/type", SYS), "x86_pkg_temp\n")
+            .file(&format!("{}/class/thermal/thermal_zone0/temp", SYS), "97000\n")
+            .file(&format!("{}/class/thermal/thermal_zone1/type", SYS), "acpitz\n")
+            .file(&format!("{}/class/thermal/thermal_zone1/temp", SYS), "45500\n")
+            .file(&format!("{}/devices/system/cpu/online", SYS), "0-1\n");
+        cpu(&proc, 0, 3_000_000, 3_000_000, 10);
+        cpu(&proc, 1, 3_000_000, 4_000_000, 4);
+        proc.file(&format!("{}/devices/system/cpu/cpu1/cpufreq/base_frequency", SYS), "3000000\n");
+        package(&proc, 0, 0, 100);
+        package(&proc, 1, 0, 100);
+        let source = proc.source();
+        let mut sampler = ThermalSampler::new(&source);
+
+        cpu(&proc, 0, 1_200_000, 3_000_000, 15);
+        cpu(&proc, 1, 1_800_000, 4_000_000, 6);
+        package(&proc, 0, 0, 103);
+        package(&proc, 1, 0, 103);
+        let thermal = sampler.sample(&source).unwrap();
+
+        let hottest = thermal.hottest().unwrap();
+        assert_eq!((hottest.name.as_str(), hottest.temp_c), ("x86_pkg_temp", 97.0));
+        // cpu1 is measured against its 3 GHz base clock, not the 4 GHz turbo maximum
+        assert_eq!((thermal.freq_percent, thermal.min_freq_percent), (Some(50.0), Some(40.0)));
+        // 5 + 2 core events, and the shared package counter once
+        assert_eq!(thermal.throttle_events, Some(10));
+    }
+
+    #[test]
+    fn low_clocks_count_only_on_busy_cpus() {
+        // cpu0 is busy at full clock while cpu1 idles at 30%: power saving, not throttling
+        let idle_core = vec![snapshot(&[(0.0, 100.0), (100.0, 30.0)], 0, 60.0); 3];
+        assert!(detect_throttling(&idle_core, 70.0, 90.0, 2).is_none());
+
+        // a busy core held at 60% of its base clock in 2 of 3 ticks
+        let mut slow = idle_core.clone();
+        slow[0] = snapshot(&[(0.0, 60.0), (100.0, 30.0)], 0, 60.0);
+        slow[2] = snapshot(&[(0.0, 60.0), (100.0, 30.0)], 0, 60.0);
+        let t = detect_throttling(&slow, 70.0, 90.0, 2).unwrap();
+        assert_eq!(t.causes, vec!["low_frequency"]);
+        assert_eq!(t.busy_freq_percent.map(f32::round), Some(73.0));
+        assert!(detect_throttling(&slow, 70.0, 90.0, 3).is_none());
+
+        // below BUSY_PERCENT the core's clock is not judged at all
+        let light = vec![snapshot(&[(60.0, 40.0)], 0, 60.0); 3];
+        assert!(detect_throttling(&light, 70.0, 90.0, 2).is_none());
+    }
+
+    #[test]
+    fn throttle_events_and_heat_need_min_hits() {
+        let snapshots = vec![
+            snapshot(&[(0.0, 100.0)], 4, 95.0),
+            snapshot(&[(0.0, 100.0)], 0, 95.0),
+            snapshot(&[(0.0, 100.0)], 2, 70.0),
+        ];
+
+        let t = detect_throttling(&snapshots, 70.0, 90.0, 2).unwrap();
+        assert_eq!(t.causes, vec!["throttle_events", "high_temperature"]);
+        assert_eq!((t.throttle_events, t.hottest.map(|z| z.temp_c)), (6, Some(95.0)));
+        assert!(detect_throttling(&snapshots, 70.0, 90.0, 3).is_none());
+    }
+
+    #[test]
+    fn missing_sysfs_yields_nothing() {
+        let proc = FakeProc::new();
+        let source = proc.source();
+        assert!(ThermalSampler::new(&source).sample(&source).is_none());
+    }
+}
@@ -0,0 +1,97 @@
+// Paging activity from /proc/vmstat: swap traffic and major faults, as rates between ticks.
+use crate::mem;
+use crate::source::Source;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Paging rates over one tick.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct VmStat {
+    pub swap_in_bps: u64,
+    pub swap_out_bps: u64,
+    pub major_faults_per_sec: f32,
+    /// Processes killed by the OOM killer during the tick
+    #[serde(default)]
+    pub oom_kills: u64,
+    /// Cumulative `oom_kill` counter since boot (kernels 4.13+)
+    #[serde(default)]
+    pub oom_kill_total: u64,
+}
+
+/// The cumulative counters behind `VmStat`.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct Counters {
+    pswpin: u64,
+    pswpout: u64,
+    pgmajfault: u64,
+    oom_kill: u64,
+}
+
+fn parse_vmstat(contents: &str) -> Counters {
+    let fields: HashMap<&str, u64> = contents
+        .lines()
+        .filter_map(|line| {
+            let (key, value) = line.split_once(' ')?;
+            Some((key, value.trim().parse().ok()?))
+        })
+        .collect();
+    let get = |key: &str| fields.get(key).copied().unwrap_or(0);
+
+    Counters {
+        pswpin: get("pswpin"),
+        pswpout: get("pswpout"),
+        pgmajfault: get("pgmajfault"),
+        oom_kill: get("oom_kill"),
+    }
+}
+
+/// Computes paging rates from `/proc/vmstat` deltas between calls.
+pub struct VmStatSampler {
+    last: Option<Counters>,
+    page_bytes: u64,
+}
+
+impl VmStatSampler {
+    pub fn new(source: &Source) -> Self {
+        VmStatSampler {
+            last: source.read("vmstat").map(|c| parse_vmstat(&c)),
+            page_bytes: mem::page_kb() * 1024,
+        }
+    }
+
+    /// `None` when `/proc/vmstat` is unreadable or on the first readable call.
+    pub fn sample(&mut self, source: &Source, elapsed_secs: f64) -> Option<VmStat> {
+        let elapsed_secs = elapsed_secs.max(f64::EPSILON);
+        let current = parse_vmstat(&source.read("vmstat")?);
+        let last = self.last.replace(current)?;
+        let rate = |now: u64, before: u64| now.saturating_sub(before) as f64 / elapsed_secs;
+
+        Some(VmStat {
+            swap_in_bps: (rate(current.pswpin, last.pswpin) * self.page_bytes as f64) as u64,
+            swap_out_bps: (rate(current.pswpout, last.pswpout) * self.page_bytes as f64) as u64,
+            major_faults_per_sec: rate(current.pgmajfault, last.pgmajfault) as f32,
+            oom_kills: current.oom_kill.saturating_sub(last.oom_kill),
+            oom_kill_total: current.oom_kill,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::fake::FakeProc;
+
+    #[test]
+    fn reports_swap_and_fault_rates() {
+        let proc = FakeProc::new();
+        proc.file("vmstat", "nr_free_pages 100\npgmajfault 10\npswpin 0\npswpout 0\n");
+        let source = proc.source();
+        let mut sampler = VmStatSampler::new(&source);
+        sampler.page_bytes = 4096;
+
+        proc.file("vmstat", "nr_free_pages 100\npgmajfault 30\npswpin 100\npswpout 50\noom_kill 3\n");
+        let vm = sampler.sample(&source, 2.0).unwrap();
+        assert_eq!((vm.swap_in_bps, vm.swap_out_bps, vm.major_faults_per_sec), (204800, 102400, 10.0));
+        assert_eq!((vm.oom_kills, vm.oom_kill_total), (3, 3));
+    }
+}
@@ -0,0 +1,222 @@
+// Continuous mode: keep a rolling window of snapshots and report when detections start or clear.
+use crate::analysis::{self, Findings, Thresholds};
+use crate::cgroup;
+use crate::group::Grouper;
+use crate::record::Recorder;
+use crate::sampler::{Sampler, Snapshot};
+use serde_json::json;
+use std::collections::{BTreeMap, VecDeque};
+use std::thread::sleep;
+use std::time::Duration;
+
+/// One line per active detection, keyed by detector name.
+fn active(findings: &Findings) -> BTreeMap<&'static str, (String, serde_json::Value)> {
+    let mut out = BTreeMap::new();
+
+    if let Some(s) = &findings.cpu {
+        out.insert(
+            "cpu",
+            (
+                format!("{} (PID {}){} – {:.1}% CPU", s.name, s.pid, cgroup::suffix(s.cgroup.as_ref()), s.cpu),
+                json!(s),
+            ),
+        );
+    }
+    if let Some(s) = &findings.mem {
+        out.insert(
+            "mem",
+            (
+                format!(
+                    "{} (PID {}){} – {:.1}% mem (system {:.1}%)",
+                    s.name,
+                    s.pid,
+                    cgroup::suffix(s.cgroup.as_ref()),
+                    s.mem,
+                    s.used_percent
+                ),
+                json!(s),
+            ),
+        );
+    }
+    if let Some(s) = &findings.mem_growth {
+        out.insert(
+            "mem_growth",
+            (format!("{}{}", s.describe(), cgroup::suffix(s.cgroup.as_ref())), json!(s)),
+        );
+    }
+    if let Some(s) = &findings.disk {
+        out.insert(
+            "disk",
+            (format!("{} mounted on {} – {:.1}% used", s.fs, s.mount, s.used_percent), json!(s)),
+        );
+    }
+    if let Some(s) = &findings.disk_fill {
+        out.insert("disk_fill", (s.describe(), json!(s)));
+    }
+    if let Some(s) = &findings.inodes {
+        out.insert(
+            "inodes",
+            (
+                format!(
+                    "{} mounted on {} – {:.1}% of inodes used",
+                    s.fs,
+                    s.mount,
+                    s.inodes_used_percent.unwrap_or(0.0)
+                ),
+                json!(s),
+            ),
+        );
+    }
+    if let Some(s) = &findings.io {
+        out.insert(
+            "io",
+            (
+                format!(
+                    "{} (PID {}){} – read {} B/s, write {} B/s{}",
+                    s.name,
+                    s.pid,
+                    cgroup::suffix(s.cgroup.as_ref()),
+                    s.read_bps,
+                    s.write_bps,
+                    s.pattern.as_ref().map(|p| format!(" ({})", p)).unwrap_or_default()
+                ),
+                json!(s),
+            ),
+        );
+    }
+
+    if let Some(s) = &findings.device {
+        out.insert(
+            "device",
+            (
+                format!("{} – {:.1}% util, await {:.1} ms", s.device.name, s.util_avg, s.await_avg_ms),
+                json!(s),
+            ),
+        );
+    }
+    if let Some(s) = &findings.sched {
+        out.insert(
+            "sched",
+            (
+                format!(
+                    "{} (PID {}){} – waited {:.1}%, ran {:.1}%",
+                    s.name,
+                    s.pid,
+                    cgroup::suffix(s.cgroup.as_ref()),
+                    s.wait_percent_avg,
+                    s.run_percent_avg
+                ),
+                json!(s),
+            ),
+        );
+    }
+    if let Some(s) = &findings.d_state {
+        out.insert(
+            "d_state",
+            (
+                s.tasks
+                    .iter()
+                    .map(|t| format!("{} (PID {}){}", t.task.name, t.task.pid, if t.task.wchan.is_empty() { String::new() } else { format!(" in {}", t.task.wchan) }))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                json!(s),
+            ),
+        );
+    }
+    if let Some(s) = &findings.thermal {
+        out.insert(
+            "thermal",
+            (
+                format!(
+                    "{}{}",
+                    s.causes.join(", "),
+                    s.busy_freq_percent.map(|f| format!(" – busy CPUs at {:.0}% of base clock", f)).unwrap_or_default()
+                ),
+                json!(s),
+            ),
+        );
+    }
+    if let Some(s) = &findings.net {
+        out.insert(
+            "net",
+            (
+                format!(
+                    "{}{}",
+                    s.causes.join(", "),
+                    s.interface.as_ref().map(|i| format!(" on {}", i.name)).unwrap_or_default()
+                ),
+                json!(s),
+            ),
+        );
+    }
+    if let Some(s) = &findings.psi {
+        out.insert(
+            "psi",
+            (
+                format!("{} {} {} – {:.1}% stalled", s.resource, s.kind, s.window, s.value),
+                json!(s),
+            ),
+        );
+    }
+
+    out
+}
+
+fn emit(ts: u64, event: &str, kind: &str, text: &str, detail: &serde_json::Value, json: bool) {
+    if json {
+        let line = json!({ "ts": ts, "event": event, "kind": kind, "detail": detail });
+        println!("{}", line);
+    } else {
+        println!("[{}] {} {}: {}", ts, kind, event, text);
+    }
+}
+
+/// Samples forever, applying the sustained rule over the last `window` ticks.
+pub fn run(
+    sampler: &mut Sampler,
+    grouper: &Grouper,
+    interval_secs: u64,
+    window: usize,
+    thresholds: &Thresholds,
+    json: bool,
+    mut recorder: Option<&mut Recorder>,
+) {
+    let mut snapshots: VecDeque<Snapshot> = VecDeque::with_capacity(window);
+    let mut previous: BTreeMap<&str, (String, serde_json::Value)> = BTreeMap::new();
+
+    loop {
+        sleep(Duration::from_secs(interval_secs));
+
+        if snapshots.len() == window {
+            snapshots.pop_front();
+        }
+        let snapshot = sampler.tick();
+        if let Some(r) = recorder.as_mut()
+            && let Err(e) = r.write(&snapshot)
+        {
+            eprintln!("Failed to record sample: {}", e);
+        }
+        snapshots.push_back(grouper.apply(&snapshot));
+        let ts = snapshots.back().map(|s| s.ts).unwrap_or(0);
+
+        let findings = analysis::detect(snapshots.make_contiguous(), thresholds);
+        let current = active(&findings);
+
+        for (kind, (text, detail)) in &current {
+            if !previous.contains_key(kind) {
+                emit(ts, "started", kind, text, detail, json);
+            }
+        }
+        for (kind, (text, detail)) in &previous {
+            if !current.contains_key(kind) {
+                emit(ts, "cleared", kind, text, detail, json);
+            }
+        }
+        // kills are one-off events rather than conditions that start and clear
+        for kill in &snapshot.oom_kills {
+            emit(ts, "killed", "oom_kill", &kill.describe(), &json!(kill), json);
+        }
+
+        previous = current;
+    }
+}